@@ -0,0 +1,126 @@
+#import bevy_pbr::mesh_functions as mesh_functions
+#import bevy_pbr::mesh_bindings mesh
+#import bevy_pbr::mesh_view_bindings view
+#import bevy_pbr::pbr_functions as pbr_functions
+#import bevy_core_pipeline::tonemapping tone_mapping
+
+struct MatterLook {
+    color: vec4<f32>,
+    height: f32,
+};
+
+struct VoxelAoMaterial {
+    base_color: vec4<f32>,
+    ambient_occlusion: f32,
+    direct_occlusion: f32,
+    sky_light: f32,
+    blend_depth: f32,
+    texture_scale: f32,
+    triplanar_sharpness: f32,
+    matters: array<MatterLook, #{MATTER_COUNT}>,
+};
+
+@group(1) @binding(0) var<uniform> material: VoxelAoMaterial;
+@group(1) @binding(1) var matter_textures: texture_2d_array<f32>;
+@group(1) @binding(2) var matter_sampler: sampler;
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(4) color: vec4<f32>,
+    @location(5) matters: u32,
+    @location(6) matter_weights: vec4<f32>,
+};
+
+struct VoxelVertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec4<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(4) color: vec4<f32>,
+    // the same for all vertices of a triangle, see `chunk::blend::triangle_blend`
+    @location(5) @interpolate(flat) matters: u32,
+    @location(6) matter_weights: vec4<f32>,
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> VoxelVertexOutput {
+    var out: VoxelVertexOutput;
+    out.world_normal = mesh_functions::mesh_normal_local_to_world(vertex.normal);
+    out.world_position = mesh_functions::mesh_position_local_to_world(mesh.model, vec4<f32>(vertex.position, 1.0));
+    out.position = mesh_functions::mesh_position_world_to_clip(out.world_position);
+    out.color = vertex.color;
+    out.matters = vertex.matters;
+    out.matter_weights = vertex.matter_weights;
+    return out;
+}
+
+// the matter index in one of the 4 slots of a triangle, see `chunk::blend::triangle_blend`
+fn matter_index(matters: u32, slot: u32) -> u32 {
+    return min((matters >> (8u * slot)) & 0xffu, #{MATTER_COUNT}u - 1u);
+}
+
+// the texture layer projected along the x, y and z axis, weighted by how much the normal points along them
+fn triplanar(layer: i32, world_position: vec3<f32>, world_normal: vec3<f32>) -> vec4<f32> {
+    let p = world_position / material.texture_scale;
+    var weights = pow(abs(world_normal), vec3(material.triplanar_sharpness));
+    weights = weights / (weights.x + weights.y + weights.z);
+    return textureSampleBias(matter_textures, matter_sampler, p.zy, layer, view.mip_bias) * weights.x
+        + textureSampleBias(matter_textures, matter_sampler, p.xz, layer, view.mip_bias) * weights.y
+        + textureSampleBias(matter_textures, matter_sampler, p.xy, layer, view.mip_bias) * weights.z;
+}
+
+// height based blend of the up to 4 matters of a triangle: every matter rises by its blend weight
+// and its height, scaled by the brightness of its texture, and the highest matter covers the others,
+// except for the ones that are less than `blend_depth` below it.
+fn blend_matters(in: VoxelVertexOutput) -> vec4<f32> {
+    var colors: array<vec4<f32>, 4>;
+    var heights = vec4(-1.0);
+    for (var slot = 0u; slot < 4u; slot += 1u) {
+        let matter = matter_index(in.matters, slot);
+        let look = material.matters[matter];
+        // sampled for empty slots as well, textures need uniform control flow:
+        let texture_color = triplanar(i32(matter), in.world_position.xyz, in.world_normal);
+        colors[slot] = look.color * texture_color;
+        let detail = dot(texture_color.rgb, vec3(0.299, 0.587, 0.114));
+        if in.matter_weights[slot] > 0.0 {
+            heights[slot] = in.matter_weights[slot] + look.height * detail;
+        }
+    }
+    let top = max(max(heights.x, heights.y), max(heights.z, heights.w)) - material.blend_depth;
+    let blend = max(heights - vec4(top), vec4(0.0));
+    var color = vec4(0.0);
+    for (var slot = 0u; slot < 4u; slot += 1u) {
+        color += blend[slot] * colors[slot];
+    }
+    return color / max(blend.x + blend.y + blend.z + blend.w, 0.0001);
+}
+
+@fragment
+fn fragment(
+    in: VoxelVertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> @location(0) vec4<f32> {
+    // the mesher bakes ambient occlusion, sky light and block light into the vertex colour
+    let ao = in.color.r;
+    let light = max(in.color.g * material.sky_light, in.color.b);
+
+    var base_color = material.base_color * blend_matters(in);
+    base_color = vec4(base_color.rgb * mix(1.0, ao, material.direct_occlusion) * light, base_color.a);
+
+    var pbr_input = pbr_functions::pbr_input_new();
+    pbr_input.material.base_color = base_color;
+    pbr_input.occlusion = vec3(mix(1.0, ao, material.ambient_occlusion));
+    pbr_input.frag_coord = in.position;
+    pbr_input.world_position = in.world_position;
+    pbr_input.world_normal = pbr_functions::prepare_world_normal(in.world_normal, false, is_front);
+    pbr_input.is_orthographic = view.projection[3].w == 1.0;
+    pbr_input.N = normalize(pbr_input.world_normal);
+    pbr_input.V = pbr_functions::calculate_view(in.world_position, pbr_input.is_orthographic);
+    pbr_input.flags = mesh.flags;
+
+    var output_color = pbr_functions::pbr(pbr_input);
+#ifdef TONEMAP_IN_SHADER
+    output_color = tone_mapping(output_color, view.color_grading);
+#endif
+    return output_color;
+}
@@ -0,0 +1,464 @@
+//! A kinematic character controller that walks on `ChunkCollider`s.
+//!
+//! The movement core `CharacterController::step` is a pure function of the controller state,
+//! the input, the collider and the time step, so the same inputs always produce the same movement.
+
+use bevy::prelude::*;
+
+use crate::chunk::collider::{Capsule, ChunkCollider, Contact};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CharacterSettings {
+    pub radius: f32,
+    pub height: f32,
+    /// horizontal speed at full input, in voxels per second.
+    pub walk_speed: f32,
+    pub jump_speed: f32,
+    pub gravity: f32,
+    /// obstacles up to this height are stepped onto while walking, a bit more than one voxel.
+    pub step_height: f32,
+    /// steeper surfaces are walls: the character slides down on them and cannot stand.
+    pub max_slope_degrees: f32,
+    /// while grounded the character follows the ground down by up to this distance,
+    /// so it does not start falling on every step down.
+    pub snap_distance: f32,
+}
+
+impl Default for CharacterSettings {
+    fn default() -> Self {
+        Self {
+            radius: 0.3,
+            height: 1.6,
+            walk_speed: 4.0,
+            jump_speed: 6.0,
+            gravity: 20.0,
+            step_height: 1.05,
+            max_slope_degrees: 50.0,
+            snap_distance: 0.3,
+        }
+    }
+}
+
+impl CharacterSettings {
+    fn walkable(&self, normal: Vec3) -> bool {
+        normal.y >= self.max_slope_degrees.to_radians().cos()
+    }
+
+    fn capsule(&self, position: Vec3) -> Capsule {
+        Capsule::upright(position, self.height, self.radius)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CharacterInput {
+    /// desired horizontal direction in world space, y is ignored. Longer than 1.0 is clamped.
+    pub movement: Vec3,
+    pub jump: bool,
+}
+
+/// State of a character, `position` is the lowest point of its capsule.
+#[derive(Component, Debug, Clone, Copy, PartialEq)]
+pub struct CharacterController {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub grounded: bool,
+    pub settings: CharacterSettings,
+}
+
+impl CharacterController {
+    pub fn new(position: Vec3, settings: CharacterSettings) -> Self {
+        Self {
+            position,
+            velocity: Vec3::ZERO,
+            grounded: false,
+            settings,
+        }
+    }
+
+    /// Advances the character by `dt` seconds.
+    pub fn step(&mut self, collider: &ChunkCollider, input: CharacterInput, dt: f32) {
+        let settings = self.settings;
+        let horizontal = Vec3::new(input.movement.x, 0.0, input.movement.z).clamp_length_max(1.0)
+            * settings.walk_speed;
+        self.velocity.x = horizontal.x;
+        self.velocity.z = horizontal.z;
+        self.velocity.y -= settings.gravity * dt;
+        let jumping = self.grounded && input.jump;
+        if jumping {
+            self.velocity.y = settings.jump_speed;
+        }
+        let was_grounded = self.grounded;
+
+        // horizontal movement, stepping onto obstacles if it is blocked:
+        let start = self.position;
+        let (walked, walk_contacts) = self.move_and_slide(collider, start, horizontal * dt);
+        let blocked = walk_contacts.iter().any(|c| !settings.walkable(c.normal));
+        self.position = walked;
+        if blocked && was_grounded && !jumping {
+            if let Some(stepped) = self.try_step_up(collider, start, horizontal * dt) {
+                if horizontal_distance(stepped, start) > horizontal_distance(walked, start) + 1e-4 {
+                    self.position = stepped;
+                }
+            }
+        }
+        for contact in walk_contacts.iter() {
+            self.slide_velocity(contact.normal);
+        }
+
+        // vertical movement:
+        let (fallen, fall_contacts) =
+            self.move_and_slide(collider, self.position, Vec3::Y * self.velocity.y * dt);
+        self.position = fallen;
+        self.grounded = false;
+        for contact in fall_contacts.iter() {
+            self.slide_velocity(contact.normal);
+            if settings.walkable(contact.normal) {
+                self.grounded = true;
+            }
+        }
+
+        // stick to the ground when walking down slopes and steps:
+        if was_grounded && !self.grounded && !jumping {
+            let probe = Vec3::NEG_Y * settings.snap_distance;
+            let (snapped, snap_contacts) = self.move_and_slide(collider, self.position, probe);
+            if snap_contacts.iter().any(|c| settings.walkable(c.normal)) {
+                self.position = snapped;
+                self.grounded = true;
+            }
+        }
+        if self.grounded {
+            self.velocity.y = self.velocity.y.max(0.0);
+        }
+    }
+
+    /// Moves from `position` by `displacement` in small substeps, so thin faces are not skipped,
+    /// and pushes the capsule out of every face it touches.
+    /// Walkable ground pushes straight up, so standing on slopes does not slide the character down.
+    fn move_and_slide(
+        &self,
+        collider: &ChunkCollider,
+        position: Vec3,
+        displacement: Vec3,
+    ) -> (Vec3, Vec<Contact>) {
+        const MAX_PUSHES: usize = 4;
+        const SKIN: f32 = 1e-3;
+        let settings = self.settings;
+        let substeps = (displacement.length() / (settings.radius * 0.5))
+            .ceil()
+            .max(1.0) as usize;
+        let mut position = position;
+        let mut contacts = vec![];
+        for _ in 0..substeps {
+            position += displacement / substeps as f32;
+            for _ in 0..MAX_PUSHES {
+                let Some(deepest) = collider
+                    .capsule_contacts(&settings.capsule(position))
+                    .into_iter()
+                    .max_by(|a, b| a.depth.total_cmp(&b.depth))
+                else {
+                    break;
+                };
+                position += if settings.walkable(deepest.normal) {
+                    Vec3::Y * (deepest.depth / deepest.normal.y + SKIN)
+                } else {
+                    deepest.normal * (deepest.depth + SKIN)
+                };
+                contacts.push(deepest);
+            }
+        }
+        (position, contacts)
+    }
+
+    /// Moves up by the step height, then forward, then down onto the obstacle.
+    /// None if there is no room above or nothing walkable to land on.
+    ///
+    /// The forward move is at least the radius long, otherwise the round bottom of the capsule
+    /// would slide off the edge of the obstacle instead of landing on it.
+    fn try_step_up(
+        &self,
+        collider: &ChunkCollider,
+        start: Vec3,
+        displacement: Vec3,
+    ) -> Option<Vec3> {
+        let settings = self.settings;
+        let up = Vec3::Y * settings.step_height;
+        let raised = start + up;
+        if collider.capsule_intersects(&settings.capsule(raised)) {
+            return None;
+        }
+        let forward = displacement.normalize_or_zero() * displacement.length().max(settings.radius);
+        let (forward, _) = self.move_and_slide(collider, raised, forward);
+        let (landed, contacts) = self.move_and_slide(collider, forward, -up);
+        contacts
+            .iter()
+            .any(|c| settings.walkable(c.normal))
+            .then_some(landed)
+    }
+
+    /// Removes the part of the velocity that points into the surface.
+    fn slide_velocity(&mut self, normal: Vec3) {
+        let into = self.velocity.dot(normal);
+        if into < 0.0 {
+            self.velocity -= normal * into;
+        }
+    }
+}
+
+fn horizontal_distance(a: Vec3, b: Vec3) -> f32 {
+    Vec2::new(a.x - b.x, a.z - b.z).length()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{
+        brush::{Brush, BrushMode, BrushShape},
+        edit::ChunkEdit,
+        ir::{ChunkIR, Mesher},
+        pos::Pos,
+        voxel::{Corner, Matter},
+        Chunk,
+    };
+
+    const DT: f32 = 1.0 / 60.0;
+    const FLOOR: f32 = 4.0;
+
+    fn solid_box(min: Vec3, max: Vec3) -> Brush {
+        Brush {
+            shape: BrushShape::Box {
+                half_extents: (max - min) / 2.0,
+            },
+            center: (min + max) / 2.0,
+            mode: BrushMode::Union,
+            matter: Matter::Dirt,
+        }
+    }
+
+    /// A floor with its top at `FLOOR` that covers most of the chunk, plus `brushes`.
+    fn scene(brushes: &[Brush]) -> ChunkCollider {
+        let mut chunk = Chunk::default();
+        let floor = solid_box(Vec3::new(2.0, 0.0, 2.0), Vec3::new(29.0, FLOOR, 29.0));
+        for brush in std::iter::once(&floor).chain(brushes) {
+            chunk.brush_edit(brush).apply(&mut chunk);
+        }
+        ChunkIR::construct(&chunk, Mesher::Corners).build_collider()
+    }
+
+    fn run(
+        character: &mut CharacterController,
+        collider: &ChunkCollider,
+        input: CharacterInput,
+        seconds: f32,
+    ) {
+        for _ in 0..(seconds / DT).round() as usize {
+            character.step(collider, input, DT);
+        }
+    }
+
+    fn walk(direction: Vec3) -> CharacterInput {
+        CharacterInput {
+            movement: direction,
+            jump: false,
+        }
+    }
+
+    /// A character that fell onto the floor at x/z and came to rest.
+    fn landed(collider: &ChunkCollider, x: f32, z: f32) -> CharacterController {
+        let mut character =
+            CharacterController::new(Vec3::new(x, FLOOR + 2.0, z), CharacterSettings::default());
+        run(&mut character, collider, CharacterInput::default(), 1.0);
+        assert!(character.grounded);
+        character
+    }
+
+    #[test]
+    fn falls_and_lands() {
+        let collider = scene(&[]);
+        let mut character = CharacterController::new(
+            Vec3::new(16.0, FLOOR + 2.0, 16.0),
+            CharacterSettings::default(),
+        );
+        character.step(&collider, CharacterInput::default(), DT);
+        assert!(!character.grounded);
+        assert!(character.velocity.y < 0.0);
+        assert!(character.position.y < FLOOR + 2.0);
+
+        run(&mut character, &collider, CharacterInput::default(), 1.0);
+        assert!(character.grounded);
+        assert_eq!(character.velocity.y, 0.0);
+        assert!((character.position.y - FLOOR).abs() < 0.05, "{character:?}");
+        assert!((character.position.x - 16.0).abs() < 1e-4);
+
+        // standing still stays in place:
+        let rest = character.position;
+        run(&mut character, &collider, CharacterInput::default(), 1.0);
+        assert!(character.position.distance(rest) < 1e-3);
+    }
+
+    #[test]
+    fn same_inputs_same_movement() {
+        let collider = scene(&[solid_box(
+            Vec3::new(18.0, 0.0, 2.0),
+            Vec3::new(26.0, FLOOR + 1.0, 29.0),
+        )]);
+        let mut a = landed(&collider, 12.0, 16.0);
+        let mut b = a;
+        for i in 0..120 {
+            let input = CharacterInput {
+                movement: Vec3::new(1.0, 0.0, (i as f32 * 0.1).sin()),
+                jump: i == 60,
+            };
+            a.step(&collider, input, DT);
+            b.step(&collider, input, DT);
+        }
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn jumps_only_from_the_ground() {
+        let collider = scene(&[]);
+        let mut character = landed(&collider, 16.0, 16.0);
+        let jump = CharacterInput {
+            movement: Vec3::ZERO,
+            jump: true,
+        };
+        character.step(&collider, jump, DT);
+        assert!(!character.grounded);
+        assert!(character.velocity.y > 0.0);
+        let velocity = character.velocity.y;
+        character.step(&collider, jump, DT);
+        assert!(character.velocity.y < velocity);
+    }
+
+    #[test]
+    fn steps_up_one_voxel() {
+        let collider = scene(&[solid_box(
+            Vec3::new(18.0, 0.0, 2.0),
+            Vec3::new(26.0, FLOOR + 1.0, 29.0),
+        )]);
+        let mut character = landed(&collider, 14.0, 16.0);
+        run(&mut character, &collider, walk(Vec3::X), 1.5);
+        assert!(character.grounded);
+        assert!(character.position.x > 19.0, "{character:?}");
+        assert!(
+            (character.position.y - (FLOOR + 1.0)).abs() < 0.05,
+            "{character:?}"
+        );
+    }
+
+    #[test]
+    fn blocked_by_wall() {
+        let collider = scene(&[solid_box(
+            Vec3::new(18.0, 0.0, 2.0),
+            Vec3::new(20.0, FLOOR + 4.0, 29.0),
+        )]);
+        let mut character = landed(&collider, 14.0, 16.0);
+        run(&mut character, &collider, walk(Vec3::X), 2.0);
+        let settings = character.settings;
+        assert!(character.grounded);
+        assert!(
+            character.position.x < 18.0 - settings.radius + 0.05,
+            "{character:?}"
+        );
+        assert!(
+            character.position.x > 18.0 - settings.radius - 0.1,
+            "{character:?}"
+        );
+        assert!((character.position.y - FLOOR).abs() < 0.05, "{character:?}");
+        assert!(character.velocity.x.abs() < 1e-3);
+
+        // walking diagonally into the wall slides along it:
+        let z = character.position.z;
+        run(
+            &mut character,
+            &collider,
+            walk(Vec3::new(1.0, 0.0, 1.0)),
+            0.5,
+        );
+        assert!(character.position.x < 18.0 - settings.radius + 0.05);
+        assert!(character.position.z > z + 0.5, "{character:?}");
+    }
+
+    /// A one voxel step at x = 13 whose top front edge is Weak, so its front and top faces are cut
+    /// diagonally into a 45° slope from the floor at x = 13 up to x = 14.
+    fn slope() -> ChunkCollider {
+        let mut chunk = Chunk::default();
+        let floor = solid_box(Vec3::new(2.0, 0.0, 2.0), Vec3::new(29.0, FLOOR, 29.0));
+        let step = solid_box(
+            Vec3::new(13.0, 0.0, 2.0),
+            Vec3::new(26.0, FLOOR + 1.0, 29.0),
+        );
+        for brush in [floor, step] {
+            chunk.brush_edit(&brush).apply(&mut chunk);
+        }
+        let top = FLOOR as i8 + 1;
+        for z in 2..=29 {
+            ChunkEdit::SetCorner {
+                pos: Pos::new(13, top, z),
+                corner: Corner::Weak,
+            }
+            .apply(&mut chunk);
+            // the diagonal faces are inner faces of the cut cells:
+            let pos = Pos::new(13, top - 1, z);
+            let mut voxel = chunk.get_voxel(pos);
+            voxel.inner = Some(Matter::Dirt);
+            chunk.set_voxel(pos, voxel);
+        }
+        ChunkIR::construct(&chunk, Mesher::Corners).build_collider()
+    }
+
+    #[test]
+    fn walks_up_slope() {
+        let collider = slope();
+        let mut character = landed(&collider, 10.0, 16.0);
+        let mut previous = character.position;
+        for _ in 0..90 {
+            character.step(&collider, walk(Vec3::X), DT);
+            // following the 45° surface, without the jump of stepping up:
+            let rise = character.position.y - previous.y;
+            let run = character.position.x - previous.x;
+            assert!(rise <= run + 0.01, "{character:?}");
+            assert!(character.grounded);
+            previous = character.position;
+        }
+        assert!(character.position.x > 14.5, "{character:?}");
+        assert!(
+            (character.position.y - (FLOOR + 1.0)).abs() < 0.05,
+            "{character:?}"
+        );
+    }
+
+    /// A character that walked from the floor onto the middle of the slope and stopped there.
+    fn on_slope(collider: &ChunkCollider) -> CharacterController {
+        let mut character = landed(collider, 10.0, 16.0);
+        while character.position.y < FLOOR + 0.5 {
+            character.step(collider, walk(Vec3::X), DT);
+            assert!(character.position.x < 14.0, "{character:?}");
+        }
+        character.step(collider, CharacterInput::default(), DT);
+        character
+    }
+
+    #[test]
+    fn stands_on_walkable_slope() {
+        let collider = slope();
+        let mut character = on_slope(&collider);
+        let rest = character.position;
+        run(&mut character, &collider, CharacterInput::default(), 1.0);
+        assert!(character.grounded);
+        assert!(character.position.distance(rest) < 0.02, "{character:?}");
+    }
+
+    #[test]
+    fn slides_down_steep_slope() {
+        let collider = slope();
+        let mut character = on_slope(&collider);
+        let start = character.position;
+
+        // the same slope is too steep with a lower limit:
+        character.settings.max_slope_degrees = 30.0;
+        run(&mut character, &collider, CharacterInput::default(), 3.0);
+        assert!(character.position.x < start.x - 0.3, "{character:?}");
+        assert!((character.position.y - FLOOR).abs() < 0.05, "{character:?}");
+    }
+}
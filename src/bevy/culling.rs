@@ -0,0 +1,188 @@
+use bevy::{
+    prelude::*,
+    render::{
+        primitives::{Aabb, Frustum},
+        view::VisibilitySystems,
+    },
+    transform::TransformSystem,
+};
+
+use crate::chunk::{
+    section::{section_origin, SIDES},
+    ChunkPos, CHUNK_SIZE,
+};
+
+use super::sections::{SectionMesh, SectionMeshes};
+
+/// Hides section meshes outside of the camera frustum and sections that are occluded by solid neighbours.
+pub struct ChunkCullingPlugin;
+
+impl Plugin for ChunkCullingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CullingStats>().add_systems(
+            PostUpdate,
+            cull_sections
+                .after(TransformSystem::TransformPropagate)
+                .before(VisibilitySystems::VisibilityPropagate),
+        );
+    }
+}
+
+/// Counts of the last culling pass.
+#[derive(Resource, Debug, Default, Clone, Copy)]
+pub struct CullingStats {
+    pub sections: usize,
+    pub frustum_culled: usize,
+    pub occlusion_culled: usize,
+}
+
+impl CullingStats {
+    pub fn visible(&self) -> usize {
+        self.sections - self.frustum_culled - self.occlusion_culled
+    }
+}
+
+/// Frustum of the camera, built on the CPU from its view projection matrix.
+pub fn camera_frustum(camera: &Camera, camera_transform: &GlobalTransform) -> Frustum {
+    let view_projection = camera.projection_matrix() * camera_transform.compute_matrix().inverse();
+    Frustum::from_view_projection(&view_projection)
+}
+
+/// True if every side of the section that faces the camera touches a solid side of its neighbour.
+/// A camera inside the section never occludes it.
+pub fn is_occluded(
+    chunk_pos: ChunkPos,
+    camera_position: Vec3,
+    solid_sides: impl Fn(ChunkPos) -> [bool; 6],
+) -> bool {
+    let min = section_origin(chunk_pos);
+    let max = min + Vec3::splat(CHUNK_SIZE as f32);
+    let mut facing_any = false;
+
+    for (i, (_, positive)) in SIDES.iter().enumerate() {
+        let axis = i / 2;
+        let facing = if *positive {
+            camera_position[axis] > max[axis]
+        } else {
+            camera_position[axis] < min[axis]
+        };
+        if !facing {
+            continue;
+        }
+        facing_any = true;
+
+        let step = if *positive { 1 } else { -1 };
+        let neighbor = match axis {
+            0 => (chunk_pos.0 + step, chunk_pos.1, chunk_pos.2),
+            1 => (chunk_pos.0, chunk_pos.1 + step, chunk_pos.2),
+            _ => (chunk_pos.0, chunk_pos.1, chunk_pos.2 + step),
+        };
+        // the side of the neighbour that faces this section:
+        let opposite = i ^ 1;
+        if !solid_sides(neighbor)[opposite] {
+            return false;
+        }
+    }
+    facing_any
+}
+
+fn cull_sections(
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    section_meshes: Res<SectionMeshes>,
+    mut sections: Query<(
+        &SectionMesh,
+        &GlobalTransform,
+        Option<&Aabb>,
+        &mut Visibility,
+    )>,
+    mut stats: ResMut<CullingStats>,
+) {
+    let Some((camera, camera_transform)) = cameras.iter().find(|(c, _)| c.is_active) else {
+        return;
+    };
+    let frustum = camera_frustum(camera, camera_transform);
+    let camera_position = camera_transform.translation();
+
+    let mut new_stats = CullingStats::default();
+    for (section, transform, aabb, mut visibility) in sections.iter_mut() {
+        new_stats.sections += 1;
+        let in_frustum = match aabb {
+            Some(aabb) => frustum.intersects_obb(aabb, &transform.compute_matrix(), true, false),
+            None => true,
+        };
+        let visible = if !in_frustum {
+            new_stats.frustum_culled += 1;
+            false
+        } else if is_occluded(section.chunk_pos, camera_position, |p| {
+            section_meshes.solid_sides(p)
+        }) {
+            new_stats.occlusion_culled += 1;
+            false
+        } else {
+            true
+        };
+
+        let new_visibility = if visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        if *visibility != new_visibility {
+            *visibility = new_visibility;
+        }
+    }
+    *stats = new_stats;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: f32 = CHUNK_SIZE as f32;
+
+    /// The sections in `solid` are solid on all sides, all others on none.
+    fn solid(solid: &[ChunkPos]) -> impl Fn(ChunkPos) -> [bool; 6] + '_ {
+        move |chunk_pos| [solid.contains(&chunk_pos); 6]
+    }
+
+    #[test]
+    fn camera_inside_sees_its_section() {
+        let inside = Vec3::splat(SIZE / 2.0);
+        assert!(!is_occluded((0, 0, 0), inside, |_| [true; 6]));
+    }
+
+    #[test]
+    fn solid_neighbour_towards_camera_occludes() {
+        let camera = Vec3::new(3.5 * SIZE, SIZE / 2.0, SIZE / 2.0);
+        assert!(is_occluded((0, 0, 0), camera, solid(&[(1, 0, 0)])));
+        assert!(!is_occluded((0, 0, 0), camera, solid(&[(-1, 0, 0)])));
+        assert!(!is_occluded((0, 0, 0), camera, solid(&[])));
+
+        // only the side of the neighbour that faces the section counts:
+        let minus_x_only = |chunk_pos: ChunkPos| {
+            let mut sides = [false; 6];
+            sides[0] = chunk_pos == (1, 0, 0);
+            sides
+        };
+        assert!(is_occluded((0, 0, 0), camera, minus_x_only));
+        let plus_x_only = |chunk_pos: ChunkPos| {
+            let mut sides = [false; 6];
+            sides[1] = chunk_pos == (1, 0, 0);
+            sides
+        };
+        assert!(!is_occluded((0, 0, 0), camera, plus_x_only));
+    }
+
+    #[test]
+    fn every_facing_side_has_to_be_covered() {
+        // diagonally above, the section is seen through its +x and its +y side:
+        let camera = Vec3::new(3.5 * SIZE, 2.5 * SIZE, SIZE / 2.0);
+        assert!(!is_occluded((0, 0, 0), camera, solid(&[(1, 0, 0)])));
+        assert!(!is_occluded((0, 0, 0), camera, solid(&[(0, 1, 0)])));
+        assert!(is_occluded(
+            (0, 0, 0),
+            camera,
+            solid(&[(1, 0, 0), (0, 1, 0)])
+        ));
+    }
+}
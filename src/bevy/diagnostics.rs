@@ -0,0 +1,82 @@
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticId, Diagnostics, RegisterDiagnostic},
+    prelude::*,
+};
+
+use super::sections::SectionMeshes;
+
+/// Reports the `chunk::stats::MeshStats` of the section meshes as diagnostics, print them with
+/// `LogDiagnosticsPlugin::filtered(MeshDiagnosticsPlugin::ALL.to_vec())`.
+/// Needs the `SectionMeshes` of the `VoxelWorldPlugin`, nothing is measured without them.
+#[derive(Default)]
+pub struct MeshDiagnosticsPlugin;
+
+impl Plugin for MeshDiagnosticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_diagnostic(Diagnostic::new(
+            Self::SECTIONS_MESHED,
+            "sections_meshed",
+            20,
+        ))
+        .register_diagnostic(
+            Diagnostic::new(Self::MESHING_TIME, "meshing_time", 20).with_suffix("ms"),
+        )
+        .register_diagnostic(
+            Diagnostic::new(Self::TRIANGLES, "section_triangles", 1).with_smoothing_factor(0.0),
+        )
+        .register_diagnostic(
+            Diagnostic::new(Self::VERTICES, "section_vertices", 1).with_smoothing_factor(0.0),
+        )
+        .register_diagnostic(
+            Diagnostic::new(Self::BYTES, "section_mesh_bytes", 1)
+                .with_suffix("MiB")
+                .with_smoothing_factor(0.0),
+        )
+        .add_systems(PostUpdate, Self::diagnostic_system);
+    }
+}
+
+impl MeshDiagnosticsPlugin {
+    /// meshes swapped in per frame.
+    pub const SECTIONS_MESHED: DiagnosticId =
+        DiagnosticId::from_u128(198673636217106221542181917665083244833);
+    /// time the meshing tasks of the meshes swapped in this frame took, summed over all threads.
+    pub const MESHING_TIME: DiagnosticId =
+        DiagnosticId::from_u128(229061475352848297675318386720928868471);
+    /// triangles of all current section meshes.
+    pub const TRIANGLES: DiagnosticId =
+        DiagnosticId::from_u128(249129638745901500037236064193161820934);
+    /// vertices of all current section meshes.
+    pub const VERTICES: DiagnosticId =
+        DiagnosticId::from_u128(165594288513841580471622777029505236978);
+    /// vertex attributes and indices of all current section meshes.
+    pub const BYTES: DiagnosticId =
+        DiagnosticId::from_u128(133736251122945121127360992582733764690);
+
+    pub const ALL: [DiagnosticId; 5] = [
+        Self::SECTIONS_MESHED,
+        Self::MESHING_TIME,
+        Self::TRIANGLES,
+        Self::VERTICES,
+        Self::BYTES,
+    ];
+
+    pub fn diagnostic_system(
+        mut diagnostics: Diagnostics,
+        section_meshes: Option<ResMut<SectionMeshes>>,
+    ) {
+        let Some(mut section_meshes) = section_meshes else {
+            return;
+        };
+        let finished = section_meshes.take_finished_stats();
+        diagnostics.add_measurement(Self::SECTIONS_MESHED, || finished.meshes as f64);
+        diagnostics.add_measurement(Self::MESHING_TIME, || {
+            finished.total_time().as_secs_f64() * 1000.0
+        });
+
+        let current = section_meshes.stats();
+        diagnostics.add_measurement(Self::TRIANGLES, || current.triangles as f64);
+        diagnostics.add_measurement(Self::VERTICES, || current.vertices as f64);
+        diagnostics.add_measurement(Self::BYTES, || current.bytes as f64 / (1024.0 * 1024.0));
+    }
+}
@@ -0,0 +1,186 @@
+use bevy::{
+    pbr::{MaterialPipeline, MaterialPipelineKey},
+    prelude::*,
+    reflect::{TypePath, TypeUuid},
+    render::{
+        mesh::MeshVertexBufferLayout,
+        render_resource::{
+            AddressMode, AsBindGroup, Extent3d, RenderPipelineDescriptor, SamplerDescriptor,
+            ShaderDefVal, ShaderRef, ShaderType, SpecializedMeshPipelineError, TextureDimension,
+            TextureViewDescriptor, TextureViewDimension,
+        },
+        texture::ImageSampler,
+    },
+};
+
+use crate::chunk::{
+    ir::{ATTRIBUTE_MATTERS, ATTRIBUTE_MATTER_WEIGHTS},
+    voxel::{Matter, MATTER_COUNT},
+};
+
+const SHADER: &str = "shaders/voxel_ao.wgsl";
+
+/// Lit material for chunk meshes that applies the ambient occlusion and voxel light the mesher bakes into
+/// the vertex colours, see `chunk::ao` and `chunk::light`, and blends the matters of neighbouring faces,
+/// see `chunk::blend`. Needs `MaterialPlugin::<VoxelAoMaterial>` and meshes built from `ir::MeshBuffers`.
+///
+/// The texture of every matter is projected onto the faces along the three world axes and blended by the
+/// face normal (triplanar mapping), so sloped and diagonal faces get the same texel density as the
+/// axis aligned ones. The UVs of the mesh are not used.
+#[derive(AsBindGroup, TypeUuid, TypePath, Debug, Clone)]
+#[uuid = "5c0d35a4-6f0e-4b8e-9a51-3f7c2b9d8e61"]
+pub struct VoxelAoMaterial {
+    #[uniform(0)]
+    pub base_color: Color,
+    /// how much of the ambient light is occluded, 0.0 disables the ambient occlusion.
+    #[uniform(0)]
+    pub ambient_occlusion: f32,
+    /// how much the direct light is darkened as well. Physically only ambient light is occluded,
+    /// but with few lights in the scene the effect is hardly visible otherwise.
+    #[uniform(0)]
+    pub direct_occlusion: f32,
+    /// brightness of the sky light, lower it for the night. Block light is not affected.
+    #[uniform(0)]
+    pub sky_light: f32,
+    /// how soft the transitions between matters are: matters within this height of the highest one
+    /// at a pixel are mixed in. Small values give sharp, irregular borders.
+    #[uniform(0)]
+    pub blend_depth: f32,
+    /// how many voxels one repetition of the matter textures covers.
+    #[uniform(0)]
+    pub texture_scale: f32,
+    /// the projections of the texture are weighted by the normal raised to this power,
+    /// higher values narrow the transitions between them on sloped faces.
+    #[uniform(0)]
+    pub triplanar_sharpness: f32,
+    /// the look of every matter, indexed by `Matter::index`.
+    #[uniform(0)]
+    pub matters: [MatterLook; MATTER_COUNT],
+    /// one layer per matter, ordered by `Matter::index`, see `matter_texture_array`.
+    /// Without it every matter only has the colour of its `MatterLook`.
+    #[texture(1, dimension = "2d_array")]
+    #[sampler(2)]
+    pub matter_textures: Option<Handle<Image>>,
+}
+
+/// How a matter looks in a `VoxelAoMaterial`.
+#[derive(ShaderType, Debug, Clone, Copy)]
+pub struct MatterLook {
+    /// multiplied with the base colour of the material and the texture of the matter.
+    pub color: Color,
+    /// where two matters meet, the higher one covers the lower one. It is added to the blend weight
+    /// of the matter, scaled by the brightness of its texture, so bright spots of a high matter
+    /// reach further into its neighbours.
+    pub height: f32,
+}
+
+impl MatterLook {
+    pub fn of(matter: Matter) -> Self {
+        match matter {
+            Matter::Dirt => MatterLook {
+                color: Color::rgb(0.55, 0.42, 0.3),
+                height: 0.2,
+            },
+            Matter::Wood => MatterLook {
+                color: Color::rgb(0.75, 0.55, 0.35),
+                height: 0.5,
+            },
+            Matter::Lamp => MatterLook {
+                color: Color::rgb(1.0, 0.95, 0.8),
+                height: 0.4,
+            },
+        }
+    }
+}
+
+impl Default for VoxelAoMaterial {
+    fn default() -> Self {
+        VoxelAoMaterial {
+            base_color: Color::WHITE,
+            ambient_occlusion: 1.0,
+            direct_occlusion: 0.5,
+            sky_light: 1.0,
+            blend_depth: 0.2,
+            texture_scale: 1.0,
+            triplanar_sharpness: 4.0,
+            matters: Matter::ALL.map(MatterLook::of),
+            matter_textures: None,
+        }
+    }
+}
+
+impl Material for VoxelAoMaterial {
+    fn vertex_shader() -> ShaderRef {
+        SHADER.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        SHADER.into()
+    }
+
+    fn specialize(
+        pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayout,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        // the prepass and shadow pipelines keep their own vertex shader and attributes:
+        if pipeline.vertex_shader.as_ref() != Some(&descriptor.vertex.shader) {
+            return Ok(());
+        }
+        descriptor.vertex.buffers = vec![layout.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            Mesh::ATTRIBUTE_NORMAL.at_shader_location(1),
+            Mesh::ATTRIBUTE_COLOR.at_shader_location(4),
+            ATTRIBUTE_MATTERS.at_shader_location(5),
+            ATTRIBUTE_MATTER_WEIGHTS.at_shader_location(6),
+        ])?];
+        let matter_count = ShaderDefVal::UInt("MATTER_COUNT".into(), MATTER_COUNT as u32);
+        descriptor.vertex.shader_defs.push(matter_count.clone());
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            fragment.shader_defs.push(matter_count);
+        }
+        Ok(())
+    }
+}
+
+/// Builds the texture array of `VoxelAoMaterial::matter_textures` out of one image per matter,
+/// ordered by `Matter::index`. The array repeats in both directions, as the projection onto the faces
+/// covers the whole world.
+///
+/// None if the images are not 2D or differ in size or format.
+pub fn matter_texture_array(layers: [&Image; MATTER_COUNT]) -> Option<Image> {
+    let descriptor = &layers[0].texture_descriptor;
+    let matches = |image: &&Image| {
+        let other = &image.texture_descriptor;
+        other.dimension == TextureDimension::D2
+            && other.size == descriptor.size
+            && other.format == descriptor.format
+    };
+    if descriptor.size.depth_or_array_layers != 1 || !layers.iter().all(matches) {
+        return None;
+    }
+    let mut array = Image::new(
+        Extent3d {
+            depth_or_array_layers: MATTER_COUNT as u32,
+            ..descriptor.size
+        },
+        TextureDimension::D2,
+        layers
+            .iter()
+            .flat_map(|image| image.data.iter().copied())
+            .collect(),
+        descriptor.format,
+    );
+    array.sampler_descriptor = ImageSampler::Descriptor(SamplerDescriptor {
+        address_mode_u: AddressMode::Repeat,
+        address_mode_v: AddressMode::Repeat,
+        ..ImageSampler::linear_descriptor()
+    });
+    // a single layer would otherwise be viewed as a plain 2D texture:
+    array.texture_view_descriptor = Some(TextureViewDescriptor {
+        dimension: Some(TextureViewDimension::D2Array),
+        ..default()
+    });
+    Some(array)
+}
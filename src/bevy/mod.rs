@@ -1 +1,7 @@
+pub mod character;
+pub mod culling;
+pub mod diagnostics;
+pub mod material;
 pub mod pan_orbit_cam;
+pub mod sections;
+pub mod world;
@@ -0,0 +1,474 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex,
+    },
+    time::Instant,
+};
+
+use bevy::{
+    prelude::*,
+    render::primitives::Aabb,
+    tasks::{AsyncComputeTaskPool, Task},
+    utils::{HashMap, HashSet},
+};
+
+use crate::chunk::{
+    ir::{ChunkIR, Mesher},
+    lod::lod_factor,
+    pos::Pos,
+    remesh::CachedChunkIR,
+    section::section_origin,
+    stats::MeshStats,
+    Chunk, ChunkPos, ChunkWorld,
+};
+
+use super::material::VoxelAoMaterial;
+
+/// Marks the mesh entity of one vertical chunk section.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SectionMesh {
+    pub chunk_pos: ChunkPos,
+}
+
+#[derive(Debug, Default)]
+struct SectionState {
+    /// None until the first full resolution meshing task of the section finished.
+    ir: Option<CachedChunkIR>,
+    /// true once a meshing task finished, its mesh stays current until voxels of the section get dirty.
+    /// Meshes with a reduced level of detail have no `ir`.
+    meshed: bool,
+    /// level of detail of the current or pending mesh.
+    lod: u8,
+    /// mesher of the current or pending mesh.
+    mesher: Mesher,
+    /// see `Chunk::solid_sides`, used for occlusion culling.
+    solid_sides: [bool; 6],
+    /// None while the section has no faces, empty sections get no entity.
+    entity: Option<(Entity, Handle<Mesh>)>,
+    /// of the current mesh.
+    stats: MeshStats,
+    pending: Option<PendingMesh>,
+}
+
+/// A meshing task running on the AsyncComputeTaskPool.
+#[derive(Debug)]
+struct PendingMesh {
+    generation: u64,
+    /// the dirty voxels the task was started with,
+    /// they have to be remeshed again if the task is cancelled.
+    dirty: HashSet<Pos>,
+    cancelled: Arc<AtomicBool>,
+    /// dropping the task cancels it if it did not start yet.
+    _task: Task<()>,
+}
+
+impl PendingMesh {
+    fn cancel(self) -> HashSet<Pos> {
+        self.cancelled.store(true, Ordering::Relaxed);
+        self.dirty
+    }
+}
+
+struct MeshResult {
+    chunk_pos: ChunkPos,
+    generation: u64,
+    /// None for meshes with a reduced level of detail, they are not built incrementally.
+    ir: Option<CachedChunkIR>,
+    lod: u8,
+    mesh: Mesh,
+    /// bounds of the mesh in its local space
+    aabb: Option<Aabb>,
+    solid_sides: [bool; 6],
+    stats: MeshStats,
+}
+
+/// Cached ChunkIRs and mesh entities of all sections of a ChunkWorld.
+///
+/// Meshing runs in the background on the AsyncComputeTaskPool. Every task works on a snapshot
+/// of its section, which includes the corners of the neighbouring sections along the border.
+/// Finished meshes are sent back through a channel and swapped in by `update`.
+/// Tasks of sections that are modified again or unloaded before they finish are cancelled.
+#[derive(Resource)]
+pub struct SectionMeshes {
+    sections: HashMap<ChunkPos, SectionState>,
+    /// requested level of detail per column (chunk x and z), 0 if not set.
+    lods: HashMap<(isize, isize), u8>,
+    /// mesher of the full resolution meshes, reduced levels of detail always use `Mesher::Corners`.
+    mesher: Mesher,
+    /// of the meshes swapped in since the last `take_finished_stats`.
+    finished: MeshStats,
+    next_generation: u64,
+    sender: Sender<MeshResult>,
+    receiver: Mutex<Receiver<MeshResult>>,
+}
+
+impl Default for SectionMeshes {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            sections: HashMap::new(),
+            lods: HashMap::new(),
+            mesher: Mesher::default(),
+            finished: MeshStats::default(),
+            next_generation: 0,
+            sender,
+            receiver: Mutex::new(receiver),
+        }
+    }
+}
+
+impl SectionMeshes {
+    /// Starts meshing tasks for all sections with dirty voxels and swaps in the meshes of finished tasks,
+    /// spawning, updating or despawning their mesh entities.
+    /// Sections that were removed from the world are despawned.
+    pub fn update(
+        &mut self,
+        world: &mut ChunkWorld,
+        commands: &mut Commands,
+        meshes: &mut Assets<Mesh>,
+        material: &Handle<VoxelAoMaterial>,
+    ) {
+        let mut changed: Vec<ChunkPos> = world
+            .chunks
+            .iter()
+            .filter(|(chunk_pos, chunk)| {
+                !chunk.dirty.is_empty() || !self.sections.contains_key(*chunk_pos)
+            })
+            .map(|(chunk_pos, _)| *chunk_pos)
+            .collect();
+
+        // sections that were removed from the world are despawned:
+        self.sections.retain(|chunk_pos, state| {
+            if world.chunks.contains_key(chunk_pos) {
+                return true;
+            }
+            if let Some(pending) = state.pending.take() {
+                pending.cancel();
+            }
+            if let Some((entity, _)) = state.entity.take() {
+                commands.entity(entity).despawn();
+            }
+            changed.push(*chunk_pos);
+            false
+        });
+
+        // all neighbours see a changed section through their border:
+        let mut to_remesh: HashSet<ChunkPos> = HashSet::new();
+        for (x, y, z) in changed {
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        to_remesh.insert((x + dx, y + dy, z + dz));
+                    }
+                }
+            }
+        }
+
+        // sections whose level of detail or mesher changed:
+        for (chunk_pos, state) in self.sections.iter() {
+            if state.lod != self.lod(*chunk_pos) || state.mesher != self.mesher {
+                to_remesh.insert(*chunk_pos);
+            }
+        }
+
+        for chunk_pos in to_remesh {
+            let lod = self.lod(chunk_pos);
+            world.sync_border(chunk_pos);
+            let Some(chunk) = world.chunks.get_mut(&chunk_pos) else {
+                continue;
+            };
+            let state = self.sections.entry(chunk_pos).or_default();
+            let lod_changed = state.lod != lod;
+            state.lod = lod;
+            let mesher_changed = state.mesher != self.mesher;
+            state.mesher = self.mesher;
+            // fast path: empty and full sections produce no faces,
+            // dual contouring still builds the surface towards a differing neighbour:
+            let uniform = match (self.mesher, lod) {
+                (Mesher::DualContouring, 0) => chunk.uniform_with_border(),
+                _ => chunk.is_empty() || chunk.is_full(),
+            };
+            if uniform {
+                chunk.dirty.clear();
+                state.ir = None;
+                state.meshed = false;
+                state.stats = MeshStats::default();
+                state.solid_sides = chunk.solid_sides();
+                if let Some(pending) = state.pending.take() {
+                    pending.cancel();
+                }
+                if let Some((entity, _)) = state.entity.take() {
+                    commands.entity(entity).despawn();
+                }
+                continue;
+            }
+            // sections around a changed one often see no change through their border,
+            // their current mesh or the one of their pending task is still up to date:
+            let current = state.meshed || state.pending.is_some();
+            if current && chunk.dirty.is_empty() && !lod_changed && !mesher_changed {
+                continue;
+            }
+
+            let mut snapshot = chunk.clone();
+            chunk.dirty.clear();
+            if let Some(pending) = state.pending.take() {
+                snapshot.dirty.extend(pending.cancel());
+            }
+            self.next_generation += 1;
+            state.pending = Some(spawn_meshing_task(
+                chunk_pos,
+                self.next_generation,
+                snapshot,
+                state.ir.clone(),
+                lod,
+                self.mesher,
+                self.sender.clone(),
+            ));
+        }
+
+        let results: Vec<MeshResult> = self.receiver.lock().unwrap().try_iter().collect();
+        for result in results {
+            let Some(state) = self.sections.get_mut(&result.chunk_pos) else {
+                continue;
+            };
+            if state.pending.as_ref().map(|p| p.generation) != Some(result.generation) {
+                // outdated result of a cancelled task
+                continue;
+            }
+            state.pending = None;
+            state.meshed = true;
+            state.ir = result.ir;
+            state.solid_sides = result.solid_sides;
+            self.finished.add(&result.stats);
+            state.stats = result.stats;
+            let transform = Transform::from_translation(section_origin(result.chunk_pos))
+                .with_scale(Vec3::splat(lod_factor(result.lod) as f32));
+
+            match &state.entity {
+                Some((entity, handle)) => {
+                    if let Some(old) = meshes.get_mut(handle) {
+                        *old = result.mesh;
+                    }
+                    commands.entity(*entity).insert(transform);
+                    match result.aabb {
+                        Some(aabb) => commands.entity(*entity).insert(aabb),
+                        None => commands.entity(*entity).remove::<Aabb>(),
+                    };
+                }
+                None => {
+                    let handle = meshes.add(result.mesh);
+                    let entity = commands
+                        .spawn((
+                            MaterialMeshBundle {
+                                mesh: handle.clone(),
+                                material: material.clone(),
+                                transform,
+                                ..default()
+                            },
+                            SectionMesh {
+                                chunk_pos: result.chunk_pos,
+                            },
+                        ))
+                        .id();
+                    if let Some(aabb) = result.aabb {
+                        commands.entity(entity).insert(aabb);
+                    }
+                    state.entity = Some((entity, handle));
+                }
+            }
+        }
+    }
+
+    /// Chooses the mesher of all full resolution meshes, sections meshed with another one are remeshed.
+    pub fn set_mesher(&mut self, mesher: Mesher) {
+        self.mesher = mesher;
+    }
+
+    pub fn mesher(&self) -> Mesher {
+        self.mesher
+    }
+
+    /// Requests a level of detail for all sections of the column, see `lod::downsample`.
+    pub fn set_lod(&mut self, column: (isize, isize), lod: u8) {
+        if lod == 0 {
+            self.lods.remove(&column);
+        } else {
+            self.lods.insert(column, lod);
+        }
+    }
+
+    pub fn lod(&self, chunk_pos: ChunkPos) -> u8 {
+        self.lods
+            .get(&(chunk_pos.0, chunk_pos.2))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The full resolution ChunkIR of the section, None while it is meshed with a reduced level of detail.
+    pub fn ir(&self, chunk_pos: ChunkPos) -> Option<&CachedChunkIR> {
+        self.sections.get(&chunk_pos).and_then(|s| s.ir.as_ref())
+    }
+
+    /// see `Chunk::solid_sides`, all false for sections that are not loaded or not meshed yet.
+    pub fn solid_sides(&self, chunk_pos: ChunkPos) -> [bool; 6] {
+        self.sections
+            .get(&chunk_pos)
+            .map(|s| s.solid_sides)
+            .unwrap_or_default()
+    }
+
+    /// Counts and sizes of the current meshes of all sections, with the time it took to build them.
+    pub fn stats(&self) -> MeshStats {
+        let mut stats = MeshStats::default();
+        for state in self.sections.values() {
+            stats.add(&state.stats);
+        }
+        stats
+    }
+
+    /// Counts, sizes and timings of the meshes that were swapped in since the last call.
+    /// Meshes of cancelled tasks are not included.
+    pub fn take_finished_stats(&mut self) -> MeshStats {
+        std::mem::take(&mut self.finished)
+    }
+
+    /// Number of meshing tasks that did not finish yet.
+    pub fn pending_tasks(&self) -> usize {
+        self.sections
+            .values()
+            .filter(|s| s.pending.is_some())
+            .count()
+    }
+}
+
+/// Remeshes the dirty regions of `ir` from the snapshot, or the whole snapshot if there is no `ir` yet
+/// or it was built with another mesher. Reduced levels of detail are always meshed from scratch.
+fn spawn_meshing_task(
+    chunk_pos: ChunkPos,
+    generation: u64,
+    mut snapshot: Chunk,
+    ir: Option<CachedChunkIR>,
+    lod: u8,
+    mesher: Mesher,
+    sender: Sender<MeshResult>,
+) -> PendingMesh {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let dirty = snapshot.dirty.clone();
+    let task_cancelled = cancelled.clone();
+    let task = AsyncComputeTaskPool::get().spawn(async move {
+        let solid_sides = snapshot.solid_sides();
+        let start = Instant::now();
+        let (ir, mesh, bounds, stats) = if lod == 0 {
+            // the buffers of the cached regions are built together with their ChunkIR:
+            let ir = match ir.filter(|ir| ir.mesher() == mesher) {
+                Some(mut ir) => {
+                    ir.update(&mut snapshot);
+                    ir
+                }
+                None => CachedChunkIR::construct_with_mesher(&mut snapshot, mesher),
+            };
+            if task_cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+            let ir_time = start.elapsed();
+            let start = Instant::now();
+            let mesh = ir.construct_mesh();
+            let stats = MeshStats {
+                ir_time,
+                mesh_time: start.elapsed(),
+                ..MeshStats::count(ir.ir(), &mesh)
+            };
+            let bounds = ir.ir().bounds();
+            (Some(ir), mesh, bounds, stats)
+        } else {
+            let lod_ir = ChunkIR::construct_lod(&snapshot, lod);
+            if task_cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+            let ir_time = start.elapsed();
+            let start = Instant::now();
+            let buffers = lod_ir.construct_mesh_buffers();
+            let buffer_time = start.elapsed();
+            let start = Instant::now();
+            let mesh = buffers.into_mesh();
+            let stats = MeshStats {
+                ir_time,
+                buffer_time,
+                mesh_time: start.elapsed(),
+                ..MeshStats::count(&lod_ir, &mesh)
+            };
+            (None, mesh, lod_ir.bounds(), stats)
+        };
+        let aabb = bounds.map(|(min, max)| Aabb::from_min_max(min.into(), max.into()));
+        // the receiver is only gone if the SectionMeshes resource was dropped.
+        let _ = sender.send(MeshResult {
+            chunk_pos,
+            generation,
+            ir,
+            lod,
+            mesh,
+            aabb,
+            solid_sides,
+            stats,
+        });
+    });
+    PendingMesh {
+        generation,
+        dirty,
+        cancelled,
+        _task: task,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::asset::FileAssetIo;
+
+    use super::*;
+    use crate::bevy::world::{ChunkLoader, LoadedColumns, StreamingSettings, VoxelWorldPlugin};
+
+    fn spawned_tasks(app: &App) -> u64 {
+        app.world.resource::<SectionMeshes>().next_generation
+    }
+
+    #[test]
+    fn clean_sections_are_not_remeshed() {
+        let mut app = App::new();
+        // the mesh assets without the AssetPlugin, which would add the render pipelines of the material:
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(AssetServer::new(FileAssetIo::new("assets", &None)))
+            .add_asset::<Mesh>()
+            .add_asset::<VoxelAoMaterial>()
+            .add_plugins(VoxelWorldPlugin {
+                settings: StreamingSettings {
+                    load_radius: 1,
+                    unload_radius: 2,
+                    columns_per_frame: 1,
+                    // the columns around the center are meshed without an `ir`:
+                    lod_distances: vec![1],
+                    ..default()
+                },
+            });
+        app.world
+            .spawn((ChunkLoader, GlobalTransform::from_translation(Vec3::ZERO)));
+        for _ in 0..9 {
+            app.update();
+        }
+        assert_eq!(app.world.resource::<LoadedColumns>().0.len(), 9);
+        // only the ground section of each column has faces, it is meshed once when its column is loaded
+        // and again for every column loaded next to it, as its border changes:
+        let neighbouring_columns = 20;
+        assert_eq!(spawned_tasks(&app), 9 + neighbouring_columns);
+
+        // the sections around a changed sky section see no change through their border,
+        // even while their own task is still pending:
+        let mut world = app.world.resource_mut::<ChunkWorld>();
+        let sky = world.chunks.get_mut(&(0, 1, 0)).unwrap();
+        sky.dirty.insert(Pos::new(16, 16, 16));
+        app.update();
+        app.update();
+        assert_eq!(spawned_tasks(&app), 9 + neighbouring_columns);
+    }
+}
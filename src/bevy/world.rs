@@ -0,0 +1,294 @@
+use std::sync::Arc;
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+
+use crate::chunk::{
+    examples::flat_ground, ir::Mesher, lod::MAX_LOD, section::SECTIONS_PER_COLUMN, Chunk, ChunkPos,
+    ChunkWorld, CHUNK_SIZE,
+};
+
+use super::{culling::ChunkCullingPlugin, material::VoxelAoMaterial, sections::SectionMeshes};
+
+/// Owns the `ChunkWorld` resource, streams chunk columns in and out around every entity
+/// with a `ChunkLoader`, keeps their light up to date and keeps the section mesh entities up to date.
+///
+/// Meshes are only built if `Assets<Mesh>` and `Assets<VoxelAoMaterial>` exist,
+/// so the streaming also runs headless with `MinimalPlugins`.
+#[derive(Default)]
+pub struct VoxelWorldPlugin {
+    pub settings: StreamingSettings,
+}
+
+impl Plugin for VoxelWorldPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(self.settings.clone())
+            .init_resource::<ChunkWorld>()
+            .init_resource::<LoadedColumns>()
+            .init_resource::<ChunkGenerator>()
+            .init_resource::<SectionMeshes>()
+            .add_plugins(ChunkCullingPlugin)
+            .add_systems(
+                Update,
+                (stream_chunks, update_light, update_section_meshes).chain(),
+            );
+        if app.is_plugin_added::<AssetPlugin>()
+            && !app.is_plugin_added::<MaterialPlugin<VoxelAoMaterial>>()
+        {
+            app.add_plugins(MaterialPlugin::<VoxelAoMaterial>::default());
+        }
+    }
+}
+
+/// Chunks are loaded around entities with this component, usually the camera or the player.
+#[derive(Component, Debug, Default, Clone, Copy)]
+pub struct ChunkLoader;
+
+#[derive(Resource, Debug, Clone)]
+pub struct StreamingSettings {
+    /// columns with a horizontal distance (in chunks) up to this radius are loaded.
+    pub load_radius: isize,
+    /// columns further away than this are unloaded. Should be larger than `load_radius`,
+    /// so chunks at the border do not flicker in and out.
+    pub unload_radius: isize,
+    /// maximum number of columns generated per frame.
+    pub columns_per_frame: usize,
+    /// columns at least `lod_distances[i]` chunks away are meshed with level of detail `i + 1`.
+    pub lod_distances: Vec<isize>,
+    /// mesher of the full resolution section meshes.
+    pub mesher: Mesher,
+}
+
+impl Default for StreamingSettings {
+    fn default() -> Self {
+        Self {
+            load_radius: 8,
+            unload_radius: 10,
+            columns_per_frame: 4,
+            lod_distances: vec![2, 4, 6],
+            mesher: Mesher::default(),
+        }
+    }
+}
+
+/// Generates the content of one section when it is loaded.
+#[derive(Resource, Clone)]
+pub struct ChunkGenerator(pub Arc<dyn Fn(ChunkPos) -> Chunk + Send + Sync>);
+
+impl Default for ChunkGenerator {
+    fn default() -> Self {
+        Self(Arc::new(|chunk_pos| flat_ground(chunk_pos, 8)))
+    }
+}
+
+/// The x/z positions of all columns currently loaded.
+#[derive(Resource, Debug, Default)]
+pub struct LoadedColumns(pub HashSet<(isize, isize)>);
+
+/// The material used for all section meshes. Created with default values if not inserted by the app.
+#[derive(Resource, Debug, Clone)]
+pub struct VoxelMaterial(pub Handle<VoxelAoMaterial>);
+
+/// The column (chunk x and z) a world space position lies in.
+pub fn column_of(translation: Vec3) -> (isize, isize) {
+    let size = CHUNK_SIZE as f32;
+    (
+        (translation.x / size).floor() as isize,
+        (translation.z / size).floor() as isize,
+    )
+}
+
+fn column_distance(a: (isize, isize), b: (isize, isize)) -> isize {
+    (a.0 - b.0).abs().max((a.1 - b.1).abs())
+}
+
+/// The level of detail a column `distance` chunks away from the closest loader is meshed with.
+pub fn lod_for_distance(settings: &StreamingSettings, distance: isize) -> u8 {
+    let lod = settings
+        .lod_distances
+        .iter()
+        .take_while(|d| distance >= **d)
+        .count();
+    lod.min(MAX_LOD as usize) as u8
+}
+
+fn stream_chunks(
+    loaders: Query<&GlobalTransform, With<ChunkLoader>>,
+    settings: Res<StreamingSettings>,
+    generator: Res<ChunkGenerator>,
+    mut world: ResMut<ChunkWorld>,
+    mut loaded: ResMut<LoadedColumns>,
+    mut section_meshes: ResMut<SectionMeshes>,
+) {
+    let centers: Vec<(isize, isize)> = loaders.iter().map(|t| column_of(t.translation())).collect();
+
+    // unload columns that are too far away from every loader:
+    let to_unload: Vec<(isize, isize)> = loaded
+        .0
+        .iter()
+        .filter(|column| {
+            centers
+                .iter()
+                .all(|c| column_distance(**column, *c) > settings.unload_radius)
+        })
+        .copied()
+        .collect();
+    for (x, z) in to_unload {
+        loaded.0.remove(&(x, z));
+        section_meshes.set_lod((x, z), 0);
+        for y in 0..SECTIONS_PER_COLUMN {
+            world.chunks.remove(&(x, y, z));
+        }
+    }
+
+    // load missing columns, closest first:
+    let mut missing: HashMap<(isize, isize), isize> = HashMap::new();
+    for center in centers.iter() {
+        let r = settings.load_radius;
+        for x in (center.0 - r)..=(center.0 + r) {
+            for z in (center.1 - r)..=(center.1 + r) {
+                if loaded.0.contains(&(x, z)) {
+                    continue;
+                }
+                let distance = column_distance((x, z), *center);
+                let entry = missing.entry((x, z)).or_insert(distance);
+                *entry = (*entry).min(distance);
+            }
+        }
+    }
+    let mut to_load: Vec<(isize, (isize, isize))> =
+        missing.into_iter().map(|(column, d)| (d, column)).collect();
+    to_load.sort();
+
+    for (_, (x, z)) in to_load.into_iter().take(settings.columns_per_frame) {
+        loaded.0.insert((x, z));
+        // empty sections are stored as well, they carry the sky light.
+        for y in 0..SECTIONS_PER_COLUMN {
+            world.chunks.insert((x, y, z), (generator.0)((x, y, z)));
+        }
+        world.light_column(x, z);
+    }
+
+    // pick the level of detail by distance to the closest loader:
+    for column in loaded.0.iter() {
+        let Some(distance) = centers.iter().map(|c| column_distance(*column, *c)).min() else {
+            continue;
+        };
+        section_meshes.set_lod(*column, lod_for_distance(&settings, distance));
+    }
+}
+
+fn update_light(mut world: ResMut<ChunkWorld>) {
+    world.update_light();
+}
+
+fn update_section_meshes(
+    mut commands: Commands,
+    settings: Res<StreamingSettings>,
+    mut world: ResMut<ChunkWorld>,
+    mut section_meshes: ResMut<SectionMeshes>,
+    material: Option<Res<VoxelMaterial>>,
+    meshes: Option<ResMut<Assets<Mesh>>>,
+    materials: Option<ResMut<Assets<VoxelAoMaterial>>>,
+) {
+    let (Some(mut meshes), Some(mut materials)) = (meshes, materials) else {
+        return;
+    };
+    let material = match material {
+        Some(material) => material.0.clone(),
+        None => {
+            let handle = materials.add(VoxelAoMaterial::default());
+            commands.insert_resource(VoxelMaterial(handle.clone()));
+            handle
+        }
+    };
+    section_meshes.set_mesher(settings.mesher);
+    section_meshes.update(&mut world, &mut commands, &mut meshes, &material);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_app() -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            VoxelWorldPlugin {
+                settings: StreamingSettings {
+                    load_radius: 1,
+                    unload_radius: 2,
+                    columns_per_frame: 100,
+                    ..default()
+                },
+            },
+        ));
+        app
+    }
+
+    fn loaded_columns(app: &App) -> HashSet<(isize, isize)> {
+        app.world.resource::<LoadedColumns>().0.clone()
+    }
+
+    fn columns_around(center: (isize, isize), radius: isize) -> HashSet<(isize, isize)> {
+        let mut columns = HashSet::new();
+        for x in (center.0 - radius)..=(center.0 + radius) {
+            for z in (center.1 - radius)..=(center.1 + radius) {
+                columns.insert((x, z));
+            }
+        }
+        columns
+    }
+
+    #[test]
+    fn streams_columns_around_loader() {
+        let mut app = test_app();
+        // without transform propagation the GlobalTransform is set directly:
+        let loader = app
+            .world
+            .spawn((ChunkLoader, GlobalTransform::from_translation(Vec3::ZERO)))
+            .id();
+        app.update();
+        assert_eq!(loaded_columns(&app), columns_around((0, 0), 1));
+        let world = app.world.resource::<ChunkWorld>();
+        assert_eq!(world.chunks.len(), 9 * SECTIONS_PER_COLUMN as usize);
+
+        // two columns further, the columns at distance 2 stay loaded:
+        let size = CHUNK_SIZE as f32;
+        *app.world.get_mut::<GlobalTransform>(loader).unwrap() =
+            GlobalTransform::from_translation(Vec3::new(2.0 * size + 1.0, 0.0, 0.0));
+        app.update();
+        let loaded = loaded_columns(&app);
+        assert!(columns_around((2, 0), 1).is_subset(&loaded));
+        assert!(loaded.contains(&(0, 0)));
+        assert!(!loaded.iter().any(|column| column.0 < 0));
+
+        // far away, everything around the origin is unloaded:
+        *app.world.get_mut::<GlobalTransform>(loader).unwrap() =
+            GlobalTransform::from_translation(Vec3::new(20.0 * size, 0.0, -20.0 * size));
+        app.update();
+        assert_eq!(loaded_columns(&app), columns_around((20, -20), 1));
+        let world = app.world.resource::<ChunkWorld>();
+        assert!(world.chunks.keys().all(|(x, _, z)| *x >= 19 && *z <= -19));
+    }
+
+    #[test]
+    fn columns_per_frame_is_limited() {
+        let mut app = test_app();
+        app.world
+            .resource_mut::<StreamingSettings>()
+            .columns_per_frame = 4;
+        app.world
+            .spawn((ChunkLoader, GlobalTransform::from_translation(Vec3::ZERO)));
+        app.update();
+        let loaded = loaded_columns(&app);
+        assert_eq!(loaded.len(), 4);
+        // the closest column first:
+        assert!(loaded.contains(&(0, 0)));
+        app.update();
+        app.update();
+        assert_eq!(loaded_columns(&app), columns_around((0, 0), 1));
+    }
+}
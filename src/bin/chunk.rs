@@ -8,7 +8,24 @@ use bevy::{
 };
 use bevy_flycam::prelude::*;
 use voxelengine::{
-    chunk::{examples::example_chunks, ir::ChunkIR, Chunk},
+    bevy::{
+        character::{CharacterController, CharacterInput, CharacterSettings},
+        material::{matter_texture_array, VoxelAoMaterial},
+        pan_orbit_cam::PanOrbitCamera,
+    },
+    chunk::{
+        brush::{Brush, BrushMode, BrushShape},
+        collider::ChunkCollider,
+        edit::{ChunkEdit, EditHistory},
+        examples::example_chunks,
+        ir::{ChunkIR, Mesher},
+        picking::RayHit,
+        pos::{Axis, Pos},
+        remesh::CachedChunkIR,
+        structure::{Debris, StructureSettings},
+        voxel::{Corner, EdgeKind, Matter, MATTER_COUNT},
+        Chunk, Corners,
+    },
     PanOrbitCameraPlugin,
 };
 
@@ -16,8 +33,33 @@ fn main() {
     println!("Hello, world!");
     App::new()
         .add_plugins(DefaultPlugins)
+        .add_plugins(MaterialPlugin::<VoxelAoMaterial>::default())
+        .init_resource::<Editor>()
         .add_systems(Startup, (setup_cam_and_light, setup_mesh))
-        .add_systems(Update, (draw_gizmos, show_hide_chunks, switch_chunks))
+        .add_systems(
+            Update,
+            (
+                draw_gizmos,
+                show_hide_chunks,
+                switch_chunks,
+                switch_mesher,
+                build_matter_textures,
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                editor_keys,
+                editor_hover,
+                editor_click,
+                update_dirty_meshes,
+                draw_editor_gizmos,
+            )
+                .chain(),
+        )
+        .add_systems(Update, (spawn_debris, fall_debris))
+        .add_systems(Update, (toggle_walk_mode, follow_character))
+        .add_systems(FixedUpdate, move_character)
         .add_plugins(PanOrbitCameraPlugin)
         .run();
 }
@@ -45,14 +87,45 @@ fn setup_cam_and_light(mut commands: Commands) {
 
 #[derive(Resource, Debug, Clone)]
 pub struct ChunkResource {
-    chunks: Vec<(Chunk, ChunkIR)>,
+    chunks: Vec<(Chunk, CachedChunkIR)>,
     current_index: usize,
+    /// indices of chunks whose ChunkIR changed and whose mesh needs to be replaced.
+    dirty: Vec<usize>,
+    /// one collider per chunk, rebuilt together with the ChunkIR.
+    colliders: Vec<ChunkCollider>,
 }
 
 impl ChunkResource {
+    /// Remeshes the dirty regions of the chunk at `index` after it was modified and marks its mesh as dirty.
+    pub fn rebuild(&mut self, index: usize) {
+        let (chunk, chunk_ir) = &mut self.chunks[index];
+        chunk.update_light();
+        if chunk_ir.update(chunk) {
+            self.colliders[index] = chunk_ir.ir().build_collider();
+            if !self.dirty.contains(&index) {
+                self.dirty.push(index);
+            }
+        }
+    }
+
+    /// Remeshes all chunks with the mesher and marks their meshes as dirty.
+    pub fn set_mesher(&mut self, mesher: Mesher) {
+        for (index, (chunk, chunk_ir)) in self.chunks.iter_mut().enumerate() {
+            *chunk_ir = CachedChunkIR::construct_with_mesher(chunk, mesher);
+            self.colliders[index] = chunk_ir.ir().build_collider();
+            if !self.dirty.contains(&index) {
+                self.dirty.push(index);
+            }
+        }
+    }
+
     pub fn current_chunk(&self) -> (&Chunk, &ChunkIR) {
         let (chunk, chunk_ir) = &self.chunks[self.current_index];
-        (chunk, chunk_ir)
+        (chunk, chunk_ir.ir())
+    }
+
+    pub fn current_collider(&self) -> &ChunkCollider {
+        &self.colliders[self.current_index]
     }
 
     pub fn increment_index(&mut self) {
@@ -78,20 +151,21 @@ pub struct ChunkMesh {
 fn setup_mesh(
     mut commands: Commands,
     asset_server: ResMut<AssetServer>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut materials: ResMut<Assets<VoxelAoMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>,
 ) {
-    // setup the material:
-    let texture_handle: Handle<Image> = asset_server.load("textures/seamless_stone.png");
-    let material_handle = materials.add(StandardMaterial {
-        base_color_texture: Some(texture_handle),
-        ..default()
+    // setup the material, the matter textures are added once they are loaded:
+    let material_handle = materials.add(VoxelAoMaterial::default());
+    commands.insert_resource(MatterTextures {
+        layers: Matter::ALL.map(|_| asset_server.load("textures/seamless_stone.png")),
+        material: material_handle.clone(),
     });
 
-    let chunks: Vec<(Chunk, ChunkIR)> = example_chunks()
+    let chunks: Vec<(Chunk, CachedChunkIR)> = example_chunks()
         .into_iter()
-        .map(|c| {
-            let ir = ChunkIR::construct_from_chunk(&c);
+        .map(|mut c| {
+            c.compute_light();
+            let ir = CachedChunkIR::construct_from_chunk(&mut c);
             (c, ir)
         })
         .collect();
@@ -100,7 +174,7 @@ fn setup_mesh(
         let chunk_mesh = chunk_ir.construct_mesh();
         let chunk_mesh_handle: Handle<Mesh> = meshes.add(chunk_mesh);
         commands.spawn((
-            PbrBundle {
+            MaterialMeshBundle {
                 mesh: chunk_mesh_handle,
                 material: material_handle.clone(),
                 visibility: if index == 0 {
@@ -114,12 +188,54 @@ fn setup_mesh(
         ));
     }
 
+    let colliders = chunks
+        .iter()
+        .map(|(_chunk, chunk_ir)| chunk_ir.ir().build_collider())
+        .collect();
     commands.insert_resource(ChunkResource {
         chunks,
         current_index: 0,
+        dirty: vec![],
+        colliders,
     });
 }
 
+/// The textures of all matters, ordered by `Matter::index`, for the texture array of the material.
+#[derive(Resource)]
+struct MatterTextures {
+    layers: [Handle<Image>; MATTER_COUNT],
+    material: Handle<VoxelAoMaterial>,
+}
+
+fn build_matter_textures(
+    mut commands: Commands,
+    textures: Option<Res<MatterTextures>>,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<VoxelAoMaterial>>,
+) {
+    let Some(textures) = textures else {
+        return;
+    };
+    let loaded: Vec<&Image> = textures
+        .layers
+        .iter()
+        .filter_map(|handle| images.get(handle))
+        .collect();
+    let Ok(layers) = <[&Image; MATTER_COUNT]>::try_from(loaded) else {
+        return;
+    };
+    match matter_texture_array(layers) {
+        Some(array) => {
+            let array = images.add(array);
+            if let Some(material) = materials.get_mut(&textures.material) {
+                material.matter_textures = Some(array);
+            }
+        }
+        None => warn!("the matter textures differ in size or format"),
+    }
+    commands.remove_resource::<MatterTextures>();
+}
+
 fn show_hide_chunks(
     mut query: Query<(&mut Visibility, &ChunkMesh)>,
     mut chunks: ResMut<ChunkResource>,
@@ -142,8 +258,566 @@ fn switch_chunks(input: Res<Input<KeyCode>>, mut chunks: ResMut<ChunkResource>)
     }
 }
 
+/// N switches between the corner mesher and dual contouring and logs the triangles of each example chunk.
+fn switch_mesher(input: Res<Input<KeyCode>>, mut chunks: ResMut<ChunkResource>) {
+    if !input.just_pressed(KeyCode::N) {
+        return;
+    }
+    let mesher = match chunks.chunks[0].1.mesher() {
+        Mesher::Corners => Mesher::DualContouring,
+        Mesher::DualContouring => Mesher::Corners,
+    };
+    chunks.set_mesher(mesher);
+    let triangles: Vec<usize> = chunks
+        .chunks
+        .iter()
+        .map(|(_, chunk_ir)| chunk_ir.ir().triangle_count())
+        .collect();
+    info!("mesher: {mesher:?}, triangles per chunk: {triangles:?}");
+}
+
 fn draw_gizmos(mut gizmos: Gizmos, chunks: Res<ChunkResource>) {
     let (chunk, chunk_ir) = chunks.current_chunk();
     chunk.draw_gizmos(&mut gizmos);
     chunk_ir.draw_gizmos(&mut gizmos);
 }
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum EditorTool {
+    /// places a cube in front of the clicked face
+    #[default]
+    Place,
+    /// removes the cube behind the clicked face
+    Remove,
+    /// cycles the corner closest to the cursor between Air, Weak and Strong
+    Corner,
+    /// cycles the matter of the clicked side
+    Side,
+    /// adds a sphere around the clicked point
+    AddSphere,
+    /// carves a sphere out around the clicked point
+    CarveSphere,
+}
+
+/// Radius of the spheres of `EditorTool::AddSphere` and `EditorTool::CarveSphere`.
+const SPHERE_BRUSH_RADIUS: f32 = 2.5;
+
+/// Editor mode: toggle with Tab, select tools with 1-6, switch matter with M.
+/// Left click applies the tool, Ctrl+Z undoes, Ctrl+Y redoes.
+/// G toggles between highlighting the voxels an edit would make collapse and letting them fall down.
+#[derive(Resource, Debug)]
+pub struct Editor {
+    enabled: bool,
+    tool: EditorTool,
+    matter: Matter,
+    hover: Option<RayHit>,
+    /// one history per chunk index
+    histories: Vec<EditHistory<Chunk>>,
+    structure: StructureSettings,
+    /// corners left unsupported by the last edit, if they were not detached.
+    collapsing: Vec<Pos>,
+    /// detached islands waiting to be spawned by `spawn_debris`.
+    debris: Vec<Debris>,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tool: EditorTool::default(),
+            matter: Matter::Dirt,
+            hover: None,
+            histories: vec![],
+            structure: StructureSettings::default(),
+            collapsing: vec![],
+            debris: vec![],
+        }
+    }
+}
+
+impl Editor {
+    fn history(&mut self, index: usize) -> &mut EditHistory<Chunk> {
+        if self.histories.len() <= index {
+            self.histories.resize_with(index + 1, Default::default);
+        }
+        &mut self.histories[index]
+    }
+}
+
+fn editor_keys(
+    input: Res<Input<KeyCode>>,
+    mut editor: ResMut<Editor>,
+    mut chunks: ResMut<ChunkResource>,
+) {
+    if input.just_pressed(KeyCode::Tab) {
+        editor.enabled = !editor.enabled;
+        info!("editor enabled: {}", editor.enabled);
+    }
+    if !editor.enabled {
+        return;
+    }
+
+    for (key, tool) in [
+        (KeyCode::Key1, EditorTool::Place),
+        (KeyCode::Key2, EditorTool::Remove),
+        (KeyCode::Key3, EditorTool::Corner),
+        (KeyCode::Key4, EditorTool::Side),
+        (KeyCode::Key5, EditorTool::AddSphere),
+        (KeyCode::Key6, EditorTool::CarveSphere),
+    ] {
+        if input.just_pressed(key) {
+            editor.tool = tool;
+            info!("editor tool: {tool:?}");
+        }
+    }
+    if input.just_pressed(KeyCode::M) {
+        editor.matter = next_matter(Some(editor.matter)).unwrap_or(Matter::Dirt);
+        info!("editor matter: {:?}", editor.matter);
+    }
+    if input.just_pressed(KeyCode::G) {
+        editor.structure.detach_debris = !editor.structure.detach_debris;
+        info!("detach debris: {}", editor.structure.detach_debris);
+    }
+
+    let ctrl = input.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let index = chunks.current_index;
+    let (chunk, _chunk_ir) = &mut chunks.chunks[index];
+    let changed = if ctrl && input.just_pressed(KeyCode::Z) {
+        editor.history(index).undo(chunk).is_some()
+    } else if ctrl && input.just_pressed(KeyCode::Y) {
+        editor.history(index).redo(chunk).is_some()
+    } else {
+        false
+    };
+    if changed {
+        editor.collapsing.clear();
+        chunks.rebuild(index);
+    }
+}
+
+fn editor_hover(
+    windows: Query<&Window>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    chunks: Res<ChunkResource>,
+    mut editor: ResMut<Editor>,
+) {
+    editor.hover = None;
+    if !editor.enabled {
+        return;
+    }
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = cameras.get_single() else {
+        return;
+    };
+    let Some(ray) = camera.viewport_to_world(camera_transform, cursor) else {
+        return;
+    };
+    let (_chunk, chunk_ir) = chunks.current_chunk();
+    editor.hover = chunk_ir.raycast(ray);
+}
+
+fn editor_click(
+    mouse: Res<Input<MouseButton>>,
+    mut editor: ResMut<Editor>,
+    mut chunks: ResMut<ChunkResource>,
+) {
+    if !editor.enabled || !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(hit) = editor.hover else {
+        return;
+    };
+    let index = chunks.current_index;
+    let (chunk, _chunk_ir) = &mut chunks.chunks[index];
+    let Some(edit) = tool_edit(editor.tool, editor.matter, chunk, &hit) else {
+        return;
+    };
+    let positions = edit.positions();
+    // the edit and the islands it detaches are undone together:
+    editor.history(index).begin_stroke();
+    editor.history(index).apply(chunk, edit);
+
+    let report = chunk.check_structure(&positions, &editor.structure);
+    editor.collapsing.clear();
+    if !report.is_stable() {
+        info!(
+            "{} voxels in {} islands are not supported",
+            report.collapsing.len(),
+            report.islands.len()
+        );
+        if editor.structure.detach_debris {
+            let (detach, debris) = chunk.detach_islands(&report);
+            editor.history(index).apply(chunk, detach);
+            editor.debris.extend(debris);
+        } else {
+            editor.collapsing = report.collapsing;
+        }
+    }
+    editor.history(index).end_stroke();
+    chunks.rebuild(index);
+}
+
+/// The edit that applies `tool` at `hit`, None if the tool does not apply there.
+fn tool_edit(tool: EditorTool, matter: Matter, chunk: &Chunk, hit: &RayHit) -> Option<ChunkEdit> {
+    match tool {
+        EditorTool::Place => {
+            let cell = floor_pos(hit.point + hit.normal * 0.5)?;
+            Some(toggle_cell(chunk, cell, matter, true))
+        }
+        EditorTool::Remove => {
+            let cell = floor_pos(hit.point - hit.normal * 0.5)?;
+            Some(toggle_cell(chunk, cell, matter, false))
+        }
+        EditorTool::Corner => {
+            let pos = floor_pos(hit.point + Vec3::splat(0.5))?;
+            let corner = match chunk.get_voxel(pos).corner {
+                Corner::Air => Corner::Weak,
+                Corner::Weak => Corner::Strong,
+                Corner::Strong => Corner::Air,
+            };
+            Some(ChunkEdit::SetCorner { pos, corner })
+        }
+        EditorTool::Side => {
+            let axis = hit.side?;
+            let matter = next_matter(chunk.get_voxel(hit.voxel).side(axis));
+            Some(ChunkEdit::SetSide {
+                pos: hit.voxel,
+                axis,
+                matter,
+            })
+        }
+        EditorTool::AddSphere | EditorTool::CarveSphere => {
+            let mode = match tool {
+                EditorTool::AddSphere => BrushMode::Union,
+                _ => BrushMode::Subtract,
+            };
+            Some(chunk.brush_edit(&Brush {
+                shape: BrushShape::Sphere {
+                    radius: SPHERE_BRUSH_RADIUS,
+                },
+                center: hit.point,
+                mode,
+                matter,
+            }))
+        }
+    }
+}
+
+/// Toggles the 6 faces of the cube cell with its minimum corner at `cell`.
+/// Faces shared with a neighbouring cube disappear, the others appear.
+///
+/// Placing makes the 8 corners of the cell Strong, removing sets the corners that no face
+/// or edge uses afterwards back to Air.
+fn toggle_cell(chunk: &Chunk, cell: Pos, matter: Matter, place: bool) -> ChunkEdit {
+    let faces = [
+        (cell, Axis::X),
+        (cell.plus_x(), Axis::X),
+        (cell, Axis::Y),
+        (cell.plus_y(), Axis::Y),
+        (cell, Axis::Z),
+        (cell.plus_z(), Axis::Z),
+    ];
+    let toggled: Vec<(Pos, Axis, Option<Matter>)> = faces
+        .into_iter()
+        .map(|(pos, axis)| {
+            let matter = match chunk.get_voxel(pos).side(axis) {
+                Some(_) => None,
+                None => Some(matter),
+            };
+            (pos, axis, matter)
+        })
+        .collect();
+    let mut edits: Vec<ChunkEdit> = toggled
+        .iter()
+        .map(|&(pos, axis, matter)| ChunkEdit::SetSide { pos, axis, matter })
+        .collect();
+
+    let corners = Corners::OFFSETS.map(|offset| cell + offset).to_array();
+    if place {
+        edits.extend(corners.into_iter().map(|pos| ChunkEdit::SetCorner {
+            pos,
+            corner: Corner::Strong,
+        }));
+    } else {
+        let side =
+            |pos: Pos, axis: Axis| match toggled.iter().find(|(p, a, _)| *p == pos && *a == axis) {
+                Some((_, _, matter)) => *matter,
+                None => chunk.get_voxel(pos).side(axis),
+            };
+        edits.extend(
+            corners
+                .into_iter()
+                .filter(|pos| {
+                    !chunk.get_voxel(*pos).corner.air() && !corner_used(chunk, *pos, side)
+                })
+                .map(|pos| ChunkEdit::SetCorner {
+                    pos,
+                    corner: Corner::Air,
+                }),
+        );
+    }
+    ChunkEdit::Batch(edits)
+}
+
+/// Whether a face, an inner face or an edge touches the corner at `pos`. `side` gives the matter
+/// of the side along `axis` of the voxel at a position.
+fn corner_used(chunk: &Chunk, pos: Pos, side: impl Fn(Pos, Axis) -> Option<Matter>) -> bool {
+    // the square side along an axis of a voxel spans the corners of the voxel in that plane:
+    let in_plane = |axis: Axis| match axis {
+        Axis::X => [
+            Pos::ZERO,
+            Pos::new(0, 1, 0),
+            Pos::new(0, 0, 1),
+            Pos::new(0, 1, 1),
+        ],
+        Axis::Y => [
+            Pos::ZERO,
+            Pos::new(1, 0, 0),
+            Pos::new(0, 0, 1),
+            Pos::new(1, 0, 1),
+        ],
+        Axis::Z => [
+            Pos::ZERO,
+            Pos::new(1, 0, 0),
+            Pos::new(0, 1, 0),
+            Pos::new(1, 1, 0),
+        ],
+    };
+    let face = [Axis::X, Axis::Y, Axis::Z].into_iter().any(|axis| {
+        in_plane(axis)
+            .into_iter()
+            .filter_map(|offset| pos.checked_sub(offset))
+            .any(|voxel| side(voxel, axis).is_some())
+    });
+    let inner = Corners::OFFSETS
+        .to_array()
+        .into_iter()
+        .filter_map(|offset| pos.checked_sub(offset))
+        .any(|voxel| chunk.get_voxel(voxel).inner.is_some());
+    let edge = chunk.edges.get(&pos).is_some_and(|edges| !edges.is_empty())
+        || EdgeKind::iter().any(|kind| {
+            let (x, y, z) = kind.offset();
+            pos.checked_sub(Pos::new(x, y, z))
+                .and_then(|start| chunk.edges.get(&start))
+                .is_some_and(|edges| edges.iter().any(|edge| edge.kind() == kind))
+        });
+    face || inner || edge
+}
+
+fn next_matter(matter: Option<Matter>) -> Option<Matter> {
+    match matter {
+        None => Some(Matter::Dirt),
+        Some(Matter::Dirt) => Some(Matter::Wood),
+        Some(Matter::Wood) => Some(Matter::Lamp),
+        Some(Matter::Lamp) => None,
+    }
+}
+
+/// None if the point lies outside of the range a Pos can represent.
+fn floor_pos(v: Vec3) -> Option<Pos> {
+    let v = v.floor();
+    let range = (i8::MIN as f32)..(i8::MAX as f32);
+    if !(range.contains(&v.x) && range.contains(&v.y) && range.contains(&v.z)) {
+        return None;
+    }
+    Some(Pos::new(v.x as i8, v.y as i8, v.z as i8))
+}
+
+fn update_dirty_meshes(
+    mut chunks: ResMut<ChunkResource>,
+    query: Query<(&Handle<Mesh>, &ChunkMesh)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    if chunks.dirty.is_empty() {
+        return;
+    }
+    let dirty = std::mem::take(&mut chunks.dirty);
+    for (mesh_handle, chunk_mesh) in query.iter() {
+        if dirty.contains(&chunk_mesh.index) {
+            let (_chunk, chunk_ir) = &chunks.chunks[chunk_mesh.index];
+            if let Some(mesh) = meshes.get_mut(mesh_handle) {
+                *mesh = chunk_ir.construct_mesh();
+            }
+        }
+    }
+}
+
+fn draw_editor_gizmos(mut gizmos: Gizmos, editor: Res<Editor>) {
+    for pos in editor.collapsing.iter() {
+        gizmos.sphere((*pos).into(), Default::default(), 0.1, Color::RED);
+    }
+    let Some(hit) = editor.hover else {
+        return;
+    };
+    gizmos.sphere(hit.point, Default::default(), 0.08, Color::YELLOW);
+    gizmos.line(hit.point, hit.point + hit.normal * 0.5, Color::YELLOW);
+}
+
+/// A detached island falling down until it is far below the chunk.
+#[derive(Component, Debug, Clone, Copy, Default)]
+struct FallingDebris {
+    velocity: f32,
+}
+
+const DEBRIS_GRAVITY: f32 = 20.0;
+
+fn spawn_debris(
+    mut commands: Commands,
+    mut editor: ResMut<Editor>,
+    chunk_meshes: Query<&Handle<VoxelAoMaterial>, With<ChunkMesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    if editor.debris.is_empty() {
+        return;
+    }
+    let Some(material) = chunk_meshes.iter().next() else {
+        return;
+    };
+    for mut debris in std::mem::take(&mut editor.debris) {
+        debris.chunk.compute_light();
+        let chunk_ir = CachedChunkIR::construct_from_chunk(&mut debris.chunk);
+        let (x, y, z) = debris.origin;
+        commands.spawn((
+            MaterialMeshBundle {
+                mesh: meshes.add(chunk_ir.construct_mesh()),
+                material: material.clone(),
+                transform: Transform::from_xyz(x as f32, y as f32, z as f32),
+                ..default()
+            },
+            FallingDebris::default(),
+        ));
+    }
+}
+
+fn fall_debris(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut debris: Query<(Entity, &mut FallingDebris, &mut Transform)>,
+) {
+    let dt = time.delta_seconds();
+    for (entity, mut falling, mut transform) in debris.iter_mut() {
+        falling.velocity += DEBRIS_GRAVITY * dt;
+        transform.translation.y -= falling.velocity * dt;
+        if transform.translation.y < RESPAWN_DEPTH {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Walk mode: toggle with C. WASD walks relative to the camera, Space jumps,
+/// the orbit camera follows the character.
+#[derive(Component, Debug, Clone, Copy)]
+struct Walker {
+    /// where the character respawns after falling off the chunk.
+    spawn: Vec3,
+}
+
+/// Characters that fall this far below the chunk respawn.
+const RESPAWN_DEPTH: f32 = -20.0;
+
+fn toggle_walk_mode(
+    mut commands: Commands,
+    input: Res<Input<KeyCode>>,
+    chunks: Res<ChunkResource>,
+    walkers: Query<Entity, With<Walker>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !input.just_pressed(KeyCode::C) {
+        return;
+    }
+    if let Ok(entity) = walkers.get_single() {
+        commands.entity(entity).despawn();
+        info!("walk mode disabled");
+        return;
+    }
+
+    // spawn above the middle of the current chunk:
+    let spawn = match chunks.current_chunk().1.bounds() {
+        Some((min, max)) => {
+            let center = (Vec3::from(min) + Vec3::from(max)) / 2.0;
+            Vec3::new(center.x, max.y as f32 + 1.0, center.z)
+        }
+        None => Vec3::new(0.0, 5.0, 0.0),
+    };
+    let settings = CharacterSettings::default();
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(
+                shape::Capsule {
+                    radius: settings.radius,
+                    depth: settings.height - 2.0 * settings.radius,
+                    ..default()
+                }
+                .into(),
+            ),
+            material: materials.add(Color::ORANGE.into()),
+            ..default()
+        },
+        CharacterController::new(spawn, settings),
+        Walker { spawn },
+    ));
+    info!("walk mode enabled");
+}
+
+fn move_character(
+    input: Res<Input<KeyCode>>,
+    time: Res<FixedTime>,
+    chunks: Res<ChunkResource>,
+    cameras: Query<&Transform, With<PanOrbitCamera>>,
+    mut characters: Query<(&mut CharacterController, &Walker)>,
+) {
+    let Ok(camera) = cameras.get_single() else {
+        return;
+    };
+    let flat = |v: Vec3| Vec3::new(v.x, 0.0, v.z).normalize_or_zero();
+    let forward = flat(camera.forward());
+    let right = flat(camera.right());
+    let mut movement = Vec3::ZERO;
+    for (key, direction) in [
+        (KeyCode::W, forward),
+        (KeyCode::S, -forward),
+        (KeyCode::D, right),
+        (KeyCode::A, -right),
+    ] {
+        if input.pressed(key) {
+            movement += direction;
+        }
+    }
+    let character_input = CharacterInput {
+        movement,
+        jump: input.pressed(KeyCode::Space),
+    };
+
+    for (mut character, walker) in characters.iter_mut() {
+        character.step(
+            chunks.current_collider(),
+            character_input,
+            time.period.as_secs_f32(),
+        );
+        if character.position.y < RESPAWN_DEPTH {
+            *character = CharacterController::new(walker.spawn, character.settings);
+        }
+    }
+}
+
+fn follow_character(
+    mut characters: Query<(&CharacterController, &mut Transform), Without<PanOrbitCamera>>,
+    mut cameras: Query<(&mut PanOrbitCamera, &mut Transform)>,
+) {
+    let Ok((character, mut transform)) = characters.get_single_mut() else {
+        return;
+    };
+    let center = character.position + Vec3::Y * character.settings.height / 2.0;
+    transform.translation = center;
+    for (mut pan_orbit, mut camera_transform) in cameras.iter_mut() {
+        pan_orbit.focus = center;
+        camera_transform.translation =
+            center + camera_transform.rotation * Vec3::new(0.0, 0.0, pan_orbit.radius);
+    }
+}
@@ -0,0 +1,36 @@
+//! Generates and verifies the cube lookup tables of `voxelengine::chunk::lut`:
+//! `cargo run --bin lut -- generate > src/chunk/lut/table.rs` writes the tables,
+//! `cargo run --bin lut -- verify` checks the tables the crate was built with.
+
+use std::{io, process::ExitCode};
+
+use voxelengine::chunk::lut::{generate, verify, CUBE_CONFIGURATIONS};
+
+fn main() -> ExitCode {
+    match std::env::args().nth(1).as_deref() {
+        Some("generate") => match generate::write_tables(&mut io::stdout().lock()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => {
+                eprintln!("failed to write the tables: {error}");
+                ExitCode::FAILURE
+            }
+        },
+        Some("verify") => {
+            let errors = verify::verify();
+            for error in errors.iter() {
+                println!("{error}");
+            }
+            if errors.is_empty() {
+                println!("all {CUBE_CONFIGURATIONS} cube configurations are consistent");
+                ExitCode::SUCCESS
+            } else {
+                println!("{} inconsistencies", errors.len());
+                ExitCode::FAILURE
+            }
+        }
+        _ => {
+            eprintln!("usage: lut generate | verify");
+            ExitCode::FAILURE
+        }
+    }
+}
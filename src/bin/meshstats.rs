@@ -0,0 +1,67 @@
+//! Prints the `MeshStats` of the example chunks and a section of flat ground, without a window:
+//! `cargo run --release --bin meshstats -- [corners | dual]` measures one mesher, both by default.
+
+use std::process::ExitCode;
+
+use voxelengine::chunk::{
+    examples::{example_chunks, flat_ground},
+    ir::Mesher,
+    section::SECTIONS_PER_COLUMN,
+    stats::MeshStats,
+    Chunk, ChunkWorld,
+};
+
+fn main() -> ExitCode {
+    let meshers = match std::env::args().nth(1).as_deref() {
+        None => vec![Mesher::Corners, Mesher::DualContouring],
+        Some("corners") => vec![Mesher::Corners],
+        Some("dual") => vec![Mesher::DualContouring],
+        _ => {
+            eprintln!("usage: meshstats [corners | dual]");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut chunks: Vec<(String, Chunk)> = example_chunks()
+        .into_iter()
+        .enumerate()
+        .map(|(index, mut chunk)| {
+            chunk.compute_light();
+            (format!("example {index}"), chunk)
+        })
+        .collect();
+    chunks.push(("flat ground".to_string(), ground_section()));
+
+    for mesher in meshers {
+        println!("{mesher:?}:");
+        let mut total = MeshStats::default();
+        for (name, chunk) in chunks.iter() {
+            let (_, stats) = MeshStats::measure(chunk, mesher);
+            println!(
+                "  {name:<12} {:>6} triangles {:>8} bytes {:>10.3?}",
+                stats.triangles,
+                stats.bytes,
+                stats.total_time()
+            );
+            total.add(&stats);
+        }
+        println!("{total}");
+        println!();
+    }
+    ExitCode::SUCCESS
+}
+
+/// The section with the surface of a flat world, lit and with the border of its neighbours.
+fn ground_section() -> Chunk {
+    let mut world = ChunkWorld::default();
+    for x in -1..=1 {
+        for z in -1..=1 {
+            for y in 0..SECTIONS_PER_COLUMN {
+                world.chunks.insert((x, y, z), flat_ground((x, y, z), 8));
+            }
+            world.light_column(x, z);
+        }
+    }
+    world.sync_border((0, 0, 0));
+    world.chunks.remove(&(0, 0, 0)).unwrap_or_default()
+}
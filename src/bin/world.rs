@@ -0,0 +1,35 @@
+use bevy::{diagnostic::LogDiagnosticsPlugin, prelude::*};
+use voxelengine::{
+    bevy::{diagnostics::MeshDiagnosticsPlugin, world::ChunkLoader},
+    PanOrbitCameraPlugin, VoxelWorldPlugin,
+};
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins((PanOrbitCameraPlugin, VoxelWorldPlugin::default()))
+        .add_plugins((
+            MeshDiagnosticsPlugin,
+            LogDiagnosticsPlugin::filtered(MeshDiagnosticsPlugin::ALL.to_vec()),
+        ))
+        .add_systems(Startup, setup_light)
+        .add_systems(Update, track_camera)
+        .run();
+}
+
+fn setup_light(mut commands: Commands) {
+    commands.spawn(DirectionalLightBundle {
+        transform: Transform::from_xyz(30., 50., 10.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+}
+
+/// chunks are streamed around the camera
+fn track_camera(
+    mut commands: Commands,
+    cameras: Query<Entity, (With<Camera3d>, Without<ChunkLoader>)>,
+) {
+    for entity in cameras.iter() {
+        commands.entity(entity).insert(ChunkLoader);
+    }
+}
@@ -0,0 +1,94 @@
+use bevy::prelude::Vec3;
+
+use super::{ir::calculate_triag_normal, pos::Pos, voxel::Corner, Chunk};
+
+/// Ambient occlusion of one vertex of a face.
+/// Faces are rendered double sided, so both sides get their own value.
+///
+/// 1.0 means fully lit, 0.0 fully occluded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VertexAo {
+    /// the side the face normal points to, see `ir::calculate_triag_normal`.
+    pub front: f32,
+    pub back: f32,
+}
+
+impl Default for VertexAo {
+    fn default() -> Self {
+        VertexAo {
+            front: 1.0,
+            back: 1.0,
+        }
+    }
+}
+
+impl Chunk {
+    /// Ambient occlusion of a vertex at the corner `vertex`, lit from the direction of `normal`.
+    ///
+    /// Looks at the 26 neighbouring corners of the vertex and counts the ones that lie in front
+    /// of the plane through the vertex: Strong corners occlude fully, Weak corners half.
+    /// This works the same for axis aligned quads and sloped triangles.
+    pub fn vertex_ao(&self, vertex: Pos, normal: Vec3) -> f32 {
+        let mut total = 0.0;
+        let mut occluded = 0.0;
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let offset = Pos::new(dx, dy, dz);
+                    if normal.dot(offset.into()) <= 0.0 {
+                        continue;
+                    }
+                    total += 1.0;
+                    occluded += match self.get_voxel_corner(&(vertex + offset)) {
+                        Corner::Air => 0.0,
+                        Corner::Weak => 0.5,
+                        Corner::Strong => 1.0,
+                    };
+                }
+            }
+        }
+        if total == 0.0 {
+            return 1.0;
+        }
+        1.0 - occluded / total
+    }
+
+    /// Ambient occlusion of all corners of a planar face, for both of its sides.
+    pub fn face_ao<const N: usize>(&self, corners: [Pos; N]) -> [VertexAo; N] {
+        let normal =
+            calculate_triag_normal(corners[0].into(), corners[1].into(), corners[2].into());
+        corners.map(|vertex| VertexAo {
+            front: self.vertex_ao(vertex, normal),
+            back: self.vertex_ao(vertex, -normal),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{examples::flat_ground, voxel::Voxel, ChunkWorld};
+
+    #[test]
+    fn vertices_on_the_border_see_the_neighbour() {
+        let mut world = ChunkWorld::default();
+        for x in 0..=1 {
+            world.chunks.insert((x, 0, 0), flat_ground((x, 0, 0), 8));
+        }
+        // a corner on the ground one voxel into the +x neighbour:
+        let stone = Voxel {
+            corner: Corner::Strong,
+            ..Default::default()
+        };
+        let neighbour = world.chunks.get_mut(&(1, 0, 0)).unwrap();
+        neighbour.set_voxel(Pos::new(1, 9, 5), stone);
+        world.sync_border((0, 0, 0));
+        world.sync_border((1, 0, 0));
+
+        // the same vertex seen from both chunks:
+        let here = world.chunks[&(0, 0, 0)].vertex_ao(Pos::new(32, 8, 5), Vec3::Y);
+        let there = world.chunks[&(1, 0, 0)].vertex_ao(Pos::new(0, 8, 5), Vec3::Y);
+        assert!(here < 1.0);
+        assert_eq!(here, there);
+    }
+}
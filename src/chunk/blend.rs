@@ -0,0 +1,182 @@
+//! Blending between the matters of neighbouring faces.
+//!
+//! Every face takes the matter of a single voxel side, so without blending the texture switches
+//! hard where two matters meet. Instead every vertex gets the share of each matter among the faces
+//! meeting at its corner, and the material blends the matters of a triangle by these weights,
+//! see `bevy::material::VoxelAoMaterial`. Inside of an area of one matter the vertices only see that
+//! matter, at a seam between dirt and wood they see half of each, so the transition fades out
+//! over the faces next to the seam.
+//!
+//! Faces of neighbouring chunks are not known to a chunk, so seams along chunk borders only
+//! blend with the faces on this side of the border.
+
+use super::{
+    pos::{Axis, Pos},
+    voxel::{Matter, MATTER_COUNT},
+    Chunk, Corners,
+};
+
+/// Number of matters a single triangle can blend, see `triangle_blend`.
+pub const BLEND_SLOTS: usize = 4;
+
+/// How much of each matter is seen at a vertex, indexed by `Matter::index`, summing up to 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MatterWeights(pub [f32; MATTER_COUNT]);
+
+impl MatterWeights {
+    /// All weight on a single matter.
+    pub fn only(matter: Matter) -> Self {
+        let mut weights = [0.0; MATTER_COUNT];
+        weights[matter.index()] = 1.0;
+        MatterWeights(weights)
+    }
+
+    pub fn get(&self, matter: Matter) -> f32 {
+        self.0[matter.index()]
+    }
+}
+
+impl Chunk {
+    /// Matter weights of a vertex at the corner `vertex` of a face of `matter`.
+    ///
+    /// Every face of the 8 voxels around the corner that has the corner as one of its own
+    /// counts once for its matter. `matter` only decides the weights if no face meets at the corner.
+    pub fn vertex_matter(&self, vertex: Pos, matter: Matter) -> MatterWeights {
+        let mut counts = [0u32; MATTER_COUNT];
+        for offset in Corners::OFFSETS {
+            let Some(pos) = vertex.checked_sub(offset) else {
+                continue;
+            };
+            // unstored voxels have no sides:
+            let Some(voxel) = self.voxels.get(&pos) else {
+                continue;
+            };
+            if voxel.corner.air() {
+                continue;
+            }
+            let corner_info = self.get_voxel_corner_info(pos, voxel.corner);
+            for axis in [Axis::X, Axis::Y, Axis::Z] {
+                if let Some(side_matter) = voxel.side(axis) {
+                    if corner_info.side(axis).contains(vertex) {
+                        counts[side_matter.index()] += 1;
+                    }
+                }
+            }
+            if let Some(inner_matter) = voxel.inner {
+                counts[inner_matter.index()] += corner_info
+                    .inner_sides()
+                    .filter(|side| side.contains(vertex))
+                    .count() as u32;
+            }
+        }
+        let total: u32 = counts.iter().sum();
+        if total == 0 {
+            return MatterWeights::only(matter);
+        }
+        MatterWeights(counts.map(|count| count as f32 / total as f32))
+    }
+
+    /// Matter weights of all corners of a face of `matter`.
+    pub fn face_matter<const N: usize>(
+        &self,
+        matter: Matter,
+        corners: [Pos; N],
+    ) -> [MatterWeights; N] {
+        corners.map(|vertex| self.vertex_matter(vertex, matter))
+    }
+}
+
+/// Packs the matter weights of the 3 vertices of a triangle for the mesh: the indices of the
+/// `BLEND_SLOTS` matters with the most weight in the triangle, one byte each starting at the lowest,
+/// and the weights of these matters at every vertex.
+///
+/// All 3 vertices get the same indices, so only the weights are interpolated across the triangle.
+pub fn triangle_blend(weights: [MatterWeights; 3]) -> (u32, [[f32; BLEND_SLOTS]; 3]) {
+    let total = |matter: usize| weights.iter().map(|w| w.0[matter]).sum::<f32>();
+    let mut order: [usize; MATTER_COUNT] = std::array::from_fn(|matter| matter);
+    order.sort_by(|a, b| total(*b).total_cmp(&total(*a)));
+    let matters = &order[..MATTER_COUNT.min(BLEND_SLOTS)];
+
+    let packed = matters
+        .iter()
+        .enumerate()
+        .fold(0, |packed, (slot, matter)| {
+            packed | (*matter as u32) << (8 * slot)
+        });
+    let slots = weights.map(|w| {
+        let mut slots = [0.0; BLEND_SLOTS];
+        for (slot, matter) in matters.iter().enumerate() {
+            slots[slot] = w.0[*matter];
+        }
+        // matters that did not fit into the slots are left out:
+        let sum: f32 = slots.iter().sum();
+        match sum > 0.0 {
+            true => slots.map(|weight| weight / sum),
+            false => slots,
+        }
+    });
+    (packed, slots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::voxel::{Corner, Voxel};
+
+    #[test]
+    fn seam_is_shared_half_and_half() {
+        let mut chunk = Chunk::default();
+        let solid = Voxel {
+            corner: Corner::Strong,
+            ..Default::default()
+        };
+        for x in 0..=2 {
+            for z in 0..=1 {
+                chunk.set_voxel(Pos::new(x, 0, z), solid);
+            }
+        }
+        for (x, matter) in [(0, Matter::Dirt), (1, Matter::Wood)] {
+            let voxel = Voxel {
+                y_side: Some(matter),
+                ..solid
+            };
+            chunk.set_voxel(Pos::new(x, 0, 0), voxel);
+        }
+
+        for z in 0..=1 {
+            let seam = chunk.vertex_matter(Pos::new(1, 0, z), Matter::Dirt);
+            assert_eq!(seam.get(Matter::Dirt), 0.5);
+            assert_eq!(seam.get(Matter::Wood), 0.5);
+            let dirt = chunk.vertex_matter(Pos::new(0, 0, z), Matter::Dirt);
+            assert_eq!(dirt, MatterWeights::only(Matter::Dirt));
+            let wood = chunk.vertex_matter(Pos::new(2, 0, z), Matter::Dirt);
+            assert_eq!(wood, MatterWeights::only(Matter::Wood));
+        }
+        // without any face at the vertex, the matter of the face itself is used:
+        let lone = chunk.vertex_matter(Pos::new(5, 5, 5), Matter::Lamp);
+        assert_eq!(lone, MatterWeights::only(Matter::Lamp));
+    }
+
+    #[test]
+    fn triangle_blend_packs_the_heaviest_matters() {
+        let weights = [
+            MatterWeights::only(Matter::Wood),
+            MatterWeights([0.25, 0.25, 0.0]),
+            MatterWeights([0.0, 0.0, 0.5]),
+        ];
+        let (packed, slots) = triangle_blend(weights);
+        // wood 1.25, lamp 0.5 and dirt 0.25 in total:
+        let order = [Matter::Wood, Matter::Lamp, Matter::Dirt];
+        for (slot, matter) in order.iter().enumerate() {
+            assert_eq!((packed >> (8 * slot)) & 0xff, matter.index() as u32);
+        }
+        assert_eq!(
+            slots,
+            [
+                [1.0, 0.0, 0.0, 0.0],
+                [0.5, 0.0, 0.5, 0.0],
+                [0.0, 1.0, 0.0, 0.0],
+            ]
+        );
+    }
+}
@@ -0,0 +1,401 @@
+//! Constructive solid geometry brushes for bulk editing.
+//!
+//! A brush is a signed distance field, negative inside of the shape. Corners inside of the shape become Strong,
+//! corners just outside of it become Weak, so the surface cuts the cells next to them diagonally,
+//! see `VoxelCornerInfo`. Afterwards every face between solid and empty cells gets a side.
+
+use std::{fmt, sync::Arc};
+
+use bevy::{prelude::Vec3, utils::HashMap};
+
+use super::{
+    edit::{ChunkEdit, WorldEdit},
+    light::{from_world_pos, WorldPos},
+    pos::Axis,
+    structure::{to_pos, Solids},
+    voxel::{Corner, Matter, Voxel},
+    Chunk, ChunkWorld,
+};
+
+/// Corners outside of the shape that are closer to its surface than this become Weak.
+pub const WEAK_BAND: f32 = 0.5;
+
+/// Shapes are centered on the brush center, cylinders and cones stand upright along the y axis.
+#[derive(Clone)]
+pub enum BrushShape {
+    Sphere {
+        radius: f32,
+    },
+    Box {
+        half_extents: Vec3,
+    },
+    Cylinder {
+        radius: f32,
+        half_height: f32,
+    },
+    /// the base lies at `-height / 2`, the tip at `height / 2`.
+    Cone {
+        radius: f32,
+        height: f32,
+    },
+    /// any signed distance field in coordinates relative to the brush center, negative inside.
+    /// `half_extents` bound the region it is evaluated in.
+    Sdf {
+        sdf: Arc<dyn Fn(Vec3) -> f32 + Send + Sync>,
+        half_extents: Vec3,
+    },
+}
+
+impl fmt::Debug for BrushShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BrushShape::Sphere { radius } => {
+                f.debug_struct("Sphere").field("radius", radius).finish()
+            }
+            BrushShape::Box { half_extents } => f
+                .debug_struct("Box")
+                .field("half_extents", half_extents)
+                .finish(),
+            BrushShape::Cylinder {
+                radius,
+                half_height,
+            } => f
+                .debug_struct("Cylinder")
+                .field("radius", radius)
+                .field("half_height", half_height)
+                .finish(),
+            BrushShape::Cone { radius, height } => f
+                .debug_struct("Cone")
+                .field("radius", radius)
+                .field("height", height)
+                .finish(),
+            BrushShape::Sdf { half_extents, .. } => f
+                .debug_struct("Sdf")
+                .field("half_extents", half_extents)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+impl BrushShape {
+    /// Signed distance of a point relative to the brush center to the surface of the shape.
+    pub fn distance(&self, p: Vec3) -> f32 {
+        match self {
+            BrushShape::Sphere { radius } => p.length() - radius,
+            BrushShape::Box { half_extents } => {
+                let q = p.abs() - *half_extents;
+                q.max(Vec3::ZERO).length() + q.max_element().min(0.0)
+            }
+            BrushShape::Cylinder {
+                radius,
+                half_height,
+            } => {
+                let d = (
+                    Vec3::new(p.x, 0.0, p.z).length() - radius,
+                    p.y.abs() - half_height,
+                );
+                d.0.max(d.1).min(0.0) + Vec3::new(d.0.max(0.0), d.1.max(0.0), 0.0).length()
+            }
+            BrushShape::Cone { radius, height } => {
+                // distance to the slanted side, scaled by the cosine of its slope:
+                let y = p.y + height / 2.0;
+                let side = (Vec3::new(p.x, 0.0, p.z).length() - radius * (1.0 - y / height))
+                    * height
+                    / (height * height + radius * radius).sqrt();
+                side.max(-y).max(y - height)
+            }
+            BrushShape::Sdf { sdf, .. } => sdf(p),
+        }
+    }
+
+    pub fn half_extents(&self) -> Vec3 {
+        match self {
+            BrushShape::Sphere { radius } => Vec3::splat(*radius),
+            BrushShape::Box { half_extents } | BrushShape::Sdf { half_extents, .. } => {
+                *half_extents
+            }
+            BrushShape::Cylinder {
+                radius,
+                half_height,
+            } => Vec3::new(*radius, *half_height, *radius),
+            BrushShape::Cone { radius, height } => Vec3::new(*radius, height / 2.0, *radius),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BrushMode {
+    /// adds the shape.
+    #[default]
+    Union,
+    /// carves the shape out.
+    Subtract,
+    /// a masked clip: inside of `Brush::bounds`, only what lies inside of the shape is kept.
+    /// Everything outside of the bounds is left as it is, unlike a true intersection,
+    /// which would clear the whole world around the shape.
+    Clip,
+}
+
+#[derive(Debug, Clone)]
+pub struct Brush {
+    pub shape: BrushShape,
+    pub center: Vec3,
+    pub mode: BrushMode,
+    /// matter of the sides created by the brush. Existing sides keep their matter.
+    pub matter: Matter,
+}
+
+impl Brush {
+    /// The corner the shape alone puts at a point: Strong inside, Weak within `WEAK_BAND` outside, Air beyond.
+    fn shape_corner(distance: f32) -> Corner {
+        if distance <= 0.0 {
+            Corner::Strong
+        } else if distance < WEAK_BAND {
+            Corner::Weak
+        } else {
+            Corner::Air
+        }
+    }
+
+    /// The corner at `world_pos` after applying the brush to the corner `old`.
+    pub fn apply_to_corner(&self, world_pos: WorldPos, old: Corner) -> Corner {
+        let p = Vec3::new(world_pos.0 as f32, world_pos.1 as f32, world_pos.2 as f32) - self.center;
+        let distance = self.shape.distance(p);
+        match self.mode {
+            BrushMode::Union => old.max(Brush::shape_corner(distance)),
+            BrushMode::Subtract => old.min(Brush::shape_corner(-distance)),
+            BrushMode::Clip => old.min(Brush::shape_corner(distance)),
+        }
+    }
+
+    /// Smallest and largest corner the brush can modify, both inclusive.
+    pub fn bounds(&self) -> (WorldPos, WorldPos) {
+        let extents = self.shape.half_extents() + Vec3::splat(WEAK_BAND);
+        let min = (self.center - extents).floor();
+        let max = (self.center + extents).ceil();
+        (
+            (min.x as isize, min.y as isize, min.z as isize),
+            (max.x as isize, max.y as isize, max.z as isize),
+        )
+    }
+}
+
+fn offset(world_pos: WorldPos, x: isize, y: isize, z: isize) -> WorldPos {
+    (world_pos.0 + x, world_pos.1 + y, world_pos.2 + z)
+}
+
+/// The 4 corners of the face of `axis` at the voxel, in the order `VoxelCornerInfo` classifies them.
+fn face_corners(world_pos: WorldPos, axis: Axis) -> [WorldPos; 4] {
+    let (u, v) = match axis {
+        Axis::X => ((0, 1, 0), (0, 0, 1)),
+        Axis::Y => ((1, 0, 0), (0, 0, 1)),
+        Axis::Z => ((1, 0, 0), (0, 1, 0)),
+    };
+    [
+        world_pos,
+        offset(world_pos, u.0, u.1, u.2),
+        offset(world_pos, u.0 + v.0, u.1 + v.1, u.2 + v.2),
+        offset(world_pos, v.0, v.1, v.2),
+    ]
+}
+
+fn normal(axis: Axis) -> (isize, isize, isize) {
+    match axis {
+        Axis::X => (1, 0, 0),
+        Axis::Y => (0, 1, 0),
+        Axis::Z => (0, 0, 1),
+    }
+}
+
+/// All voxels the brush changes, with their new content.
+fn brush_voxels(solids: &impl Solids, brush: &Brush) -> Vec<(WorldPos, Voxel)> {
+    let (min, max) = brush.bounds();
+    let mut corners: HashMap<WorldPos, Corner> = HashMap::new();
+    for x in min.0..=max.0 {
+        for y in min.1..=max.1 {
+            for z in min.2..=max.2 {
+                let world_pos = (x, y, z);
+                let old = solids.corner(world_pos);
+                let new = brush.apply_to_corner(world_pos, old);
+                if new != old {
+                    corners.insert(world_pos, new);
+                }
+            }
+        }
+    }
+    let corner = |world_pos: WorldPos| {
+        corners
+            .get(&world_pos)
+            .copied()
+            .unwrap_or_else(|| solids.corner(world_pos))
+    };
+    let mut voxels: HashMap<WorldPos, Voxel> = HashMap::new();
+    for (world_pos, new) in corners.iter() {
+        voxels
+            .entry(*world_pos)
+            .or_insert_with(|| solids.voxel(*world_pos))
+            .corner = *new;
+    }
+
+    // the faces containing a modified corner are owned by the voxels at the corner minus the in plane offsets:
+    let mut faces: Vec<(WorldPos, Axis)> = vec![];
+    for world_pos in corners.keys() {
+        for axis in [Axis::X, Axis::Y, Axis::Z] {
+            for face_corner in face_corners(*world_pos, axis) {
+                let owner = (
+                    2 * world_pos.0 - face_corner.0,
+                    2 * world_pos.1 - face_corner.1,
+                    2 * world_pos.2 - face_corner.2,
+                );
+                faces.push((owner, axis));
+            }
+        }
+    }
+    faces.sort_by_key(|(world_pos, axis)| (*world_pos, *axis as u8));
+    faces.dedup();
+
+    // a face gets a side if it is at the surface: the layer of corners on one side of it is all Strong,
+    // the layer on the other side is not.
+    for (world_pos, axis) in faces {
+        let face = face_corners(world_pos, axis).map(corner);
+        let is_face =
+            face.iter().all(|c| !c.air()) && face.iter().filter(|c| c.strong()).count() >= 3;
+        let n = normal(axis);
+        let solid_layer = |k: isize| {
+            face_corners(offset(world_pos, k * n.0, k * n.1, k * n.2), axis)
+                .into_iter()
+                .all(|c| corner(c).strong())
+        };
+        let surface = is_face && solid_layer(1) != solid_layer(-1);
+        let voxel = voxels
+            .entry(world_pos)
+            .or_insert_with(|| solids.voxel(world_pos));
+        let side = voxel.side_mut(axis);
+        *side = match (surface, *side) {
+            (true, Some(matter)) => Some(matter),
+            (true, None) => Some(brush.matter),
+            (false, _) => None,
+        };
+    }
+
+    let mut voxels: Vec<(WorldPos, Voxel)> = voxels
+        .into_iter()
+        .filter(|(world_pos, voxel)| solids.voxel(*world_pos) != *voxel)
+        .collect();
+    voxels.sort_by_key(|(world_pos, _)| *world_pos);
+    voxels
+}
+
+impl ChunkWorld {
+    /// The edit that applies the brush to all loaded sections it touches.
+    pub fn brush_edit(&self, brush: &Brush) -> WorldEdit {
+        let mut edits: HashMap<_, Vec<ChunkEdit>> = HashMap::new();
+        for (world_pos, voxel) in brush_voxels(self, brush) {
+            let (chunk_pos, pos) = from_world_pos(world_pos);
+            if self.chunks.contains_key(&chunk_pos) {
+                edits
+                    .entry(chunk_pos)
+                    .or_default()
+                    .push(ChunkEdit::SetVoxel { pos, voxel });
+            }
+        }
+        let mut edits: Vec<_> = edits.into_iter().collect();
+        edits.sort_by_key(|(chunk_pos, _)| *chunk_pos);
+        WorldEdit::Batch(
+            edits
+                .into_iter()
+                .map(|(chunk_pos, edits)| WorldEdit::Chunk(chunk_pos, ChunkEdit::Batch(edits)))
+                .collect(),
+        )
+    }
+}
+
+impl Chunk {
+    /// The edit that applies the brush to a chunk that is not part of a ChunkWorld,
+    /// with the brush center in the coordinates of the chunk. Everything outside of the chunk bounds is left out.
+    pub fn brush_edit(&self, brush: &Brush) -> ChunkEdit {
+        ChunkEdit::Batch(
+            brush_voxels(self, brush)
+                .into_iter()
+                .filter_map(|(world_pos, voxel)| {
+                    let pos = to_pos(world_pos).filter(|pos| Chunk::in_bounds(*pos))?;
+                    Some(ChunkEdit::SetVoxel { pos, voxel })
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::pos::Pos;
+
+    const CENTER: Pos = Pos::new(16, 16, 16);
+
+    fn sphere(mode: BrushMode) -> Brush {
+        Brush {
+            shape: BrushShape::Sphere { radius: 4.0 },
+            center: Vec3::from(CENTER),
+            mode,
+            matter: Matter::Wood,
+        }
+    }
+
+    fn brushed(mut chunk: Chunk, mode: BrushMode) -> Chunk {
+        chunk.brush_edit(&sphere(mode)).apply(&mut chunk);
+        chunk
+    }
+
+    fn corner(chunk: &Chunk, offset: (i8, i8, i8)) -> Corner {
+        chunk
+            .get_voxel(CENTER + Pos::new(offset.0, offset.1, offset.2))
+            .corner
+    }
+
+    #[test]
+    fn union() {
+        let chunk = brushed(Chunk::default(), BrushMode::Union);
+        assert_eq!(corner(&chunk, (0, 0, 0)), Corner::Strong);
+        // on the surface:
+        assert_eq!(corner(&chunk, (4, 0, 0)), Corner::Strong);
+        // √17 and √20 are less than half a voxel outside:
+        assert_eq!(corner(&chunk, (4, 1, 0)), Corner::Weak);
+        assert_eq!(corner(&chunk, (0, -4, 2)), Corner::Weak);
+        assert_eq!(corner(&chunk, (4, 3, 0)), Corner::Air);
+        assert_eq!(corner(&chunk, (5, 0, 0)), Corner::Air);
+        // the top of the sphere got a side below the Weak corners that round it off:
+        assert_eq!(
+            chunk.get_voxel(CENTER + Pos::new(0, 3, 0)).y_side,
+            Some(Matter::Wood)
+        );
+    }
+
+    #[test]
+    fn subtract() {
+        let chunk = brushed(Chunk::full(), BrushMode::Subtract);
+        assert_eq!(corner(&chunk, (0, 0, 0)), Corner::Air);
+        assert_eq!(corner(&chunk, (3, 0, 0)), Corner::Air);
+        // √13 is less than half a voxel inside:
+        assert_eq!(corner(&chunk, (3, 2, 0)), Corner::Weak);
+        assert_eq!(corner(&chunk, (4, 0, 0)), Corner::Strong);
+        assert_eq!(corner(&chunk, (6, 0, 0)), Corner::Strong);
+    }
+
+    #[test]
+    fn clip() {
+        let chunk = brushed(Chunk::full(), BrushMode::Clip);
+        assert_eq!(corner(&chunk, (0, 0, 0)), Corner::Strong);
+        assert_eq!(corner(&chunk, (4, 0, 0)), Corner::Strong);
+        assert_eq!(corner(&chunk, (4, 1, 0)), Corner::Weak);
+        assert_eq!(corner(&chunk, (5, 0, 0)), Corner::Air);
+        assert_eq!(corner(&chunk, (4, 4, 4)), Corner::Air);
+        // outside of the bounds of the brush nothing changes:
+        let (min, max) = sphere(BrushMode::Clip).bounds();
+        assert_eq!((min.0, max.0), (11, 21));
+        assert_eq!(corner(&chunk, (6, 0, 0)), Corner::Strong);
+        assert_eq!(corner(&chunk, (-6, -6, -6)), Corner::Strong);
+        // clipping an empty chunk adds nothing:
+        assert!(brushed(Chunk::default(), BrushMode::Clip).is_empty());
+    }
+}
@@ -0,0 +1,562 @@
+//! Colliders built from a ChunkIR, independent of any physics crate.
+//!
+//! `ChunkCollider` holds the same surface in three shapes: a triangle mesh for engines that support
+//! trimesh colliders, simplified convex pieces for engines that only handle convex shapes,
+//! and capsules for the beams of `EdgeIR`. It also answers capsule queries on its own,
+//! which is enough to move a character over the terrain.
+
+use bevy::{
+    prelude::Vec3,
+    utils::{HashMap, HashSet},
+};
+
+use super::{
+    ir::{calculate_triag_normal, ChunkIR},
+    pos::{Axis, Pos},
+};
+
+/// Faces are infinitely thin, convex pieces extend this far to both sides of their face.
+pub const FACE_THICKNESS: f32 = 0.1;
+
+/// Radius of the capsules built for beams.
+pub const BEAM_RADIUS: f32 = 0.15;
+
+#[derive(Debug, Clone, Default)]
+pub struct TriMeshCollider {
+    pub vertices: Vec<Vec3>,
+    pub indices: Vec<[u32; 3]>,
+}
+
+/// The corner points of a convex shape, their convex hull is the collider.
+#[derive(Debug, Clone, Default)]
+pub struct ConvexPiece {
+    pub points: Vec<Vec3>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Capsule {
+    pub a: Vec3,
+    pub b: Vec3,
+    pub radius: f32,
+}
+
+impl Capsule {
+    /// An upright capsule, `bottom` is the lowest point of the capsule.
+    pub fn upright(bottom: Vec3, height: f32, radius: f32) -> Self {
+        let a = bottom + Vec3::Y * radius;
+        let b = bottom + Vec3::Y * (height - radius).max(radius);
+        Capsule { a, b, radius }
+    }
+
+    pub fn translated(&self, offset: Vec3) -> Self {
+        Capsule {
+            a: self.a + offset,
+            b: self.b + offset,
+            radius: self.radius,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    /// closest point on the collider.
+    pub point: Vec3,
+    /// points from the collider towards the capsule.
+    pub normal: Vec3,
+    /// how far the capsule has to move along `normal` to stop touching.
+    pub depth: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChunkCollider {
+    pub trimesh: TriMeshCollider,
+    pub convex: Vec<ConvexPiece>,
+    pub beams: Vec<Capsule>,
+}
+
+impl ChunkIR {
+    /// Builds all collider shapes of the quads, triangles and edges, in the coordinates of the chunk.
+    pub fn build_collider(&self) -> ChunkCollider {
+        ChunkCollider {
+            trimesh: self.build_trimesh(),
+            convex: self.build_convex_pieces(),
+            beams: self
+                .edges
+                .iter()
+                .map(|edge| Capsule {
+                    a: edge.a.into(),
+                    b: edge.b.into(),
+                    radius: BEAM_RADIUS,
+                })
+                .collect(),
+        }
+    }
+
+    /// One triangle per triangle and two per quad, shared corners share their vertex.
+    pub fn build_trimesh(&self) -> TriMeshCollider {
+        let mut trimesh = TriMeshCollider::default();
+        let mut vertex_indices: HashMap<Pos, u32> = HashMap::new();
+        let mut index = |pos: Pos| {
+            *vertex_indices.entry(pos).or_insert_with(|| {
+                trimesh.vertices.push(pos.into());
+                trimesh.vertices.len() as u32 - 1
+            })
+        };
+        let mut triangles: Vec<[u32; 3]> = vec![];
+        for quad in self.quads.iter() {
+            let [a, b, c, d] = [quad.a, quad.b, quad.c, quad.d].map(&mut index);
+            triangles.push([a, b, c]);
+            triangles.push([a, c, d]);
+        }
+        for triag in self.triags.iter() {
+            triangles.push([triag.a, triag.b, triag.c].map(&mut index));
+        }
+        // vertices of dual quads do not lie on corners, they are not shared:
+        for quad in self.dual_quads.iter() {
+            let first = trimesh.vertices.len() as u32;
+            trimesh.vertices.extend(quad.corners);
+            triangles.extend(quad.triangles().map(|t| t.map(|i| first + i as u32)));
+        }
+        trimesh.indices = triangles;
+        trimesh
+    }
+
+    /// Axis aligned unit quads that lie in the same plane are greedily merged into rectangles,
+    /// every rectangle becomes a thin box. All other faces become thin prisms of their own.
+    pub fn build_convex_pieces(&self) -> Vec<ConvexPiece> {
+        let mut pieces: Vec<ConvexPiece> = vec![];
+        // cells of unit quads per plane, the plane is the axis and its coordinate along that axis.
+        let mut planes: HashMap<(Axis, i8), Vec<(i8, i8)>> = HashMap::new();
+
+        for quad in self.quads.iter() {
+            let corners = [quad.a, quad.b, quad.c, quad.d];
+            match unit_square(&corners) {
+                Some((axis, plane, cell)) => planes.entry((axis, plane)).or_default().push(cell),
+                None => pieces.push(prism(&corners)),
+            }
+        }
+        for triag in self.triags.iter() {
+            pieces.push(prism(&[triag.a, triag.b, triag.c]));
+        }
+        for quad in self.dual_quads.iter() {
+            pieces.extend(
+                quad.triangles()
+                    .map(|t| thin_prism(&t.map(|i| quad.corners[i]))),
+            );
+        }
+
+        let mut keys: Vec<(Axis, i8)> = planes.keys().copied().collect();
+        keys.sort_by_key(|(axis, plane)| (*axis as u8, *plane));
+        for (axis, plane) in keys {
+            for (u, v, width, height) in merge_rectangles(&planes[&(axis, plane)]) {
+                let half = FACE_THICKNESS / 2.0;
+                let (u0, v0) = (u as f32, v as f32);
+                let (u1, v1) = (u0 + width as f32, v0 + height as f32);
+                let (c0, c1) = (plane as f32 - half, plane as f32 + half);
+                let mut points = Vec::with_capacity(8);
+                for c in [c0, c1] {
+                    for (pu, pv) in [(u0, v0), (u1, v0), (u1, v1), (u0, v1)] {
+                        points.push(from_plane(axis, c, pu, pv));
+                    }
+                }
+                pieces.push(ConvexPiece { points });
+            }
+        }
+        pieces
+    }
+}
+
+impl ChunkCollider {
+    /// All places where the capsule touches the triangle mesh or a beam.
+    pub fn capsule_contacts(&self, capsule: &Capsule) -> Vec<Contact> {
+        let min = capsule.a.min(capsule.b) - Vec3::splat(capsule.radius);
+        let max = capsule.a.max(capsule.b) + Vec3::splat(capsule.radius);
+        let center = (capsule.a + capsule.b) / 2.0;
+        let mut contacts = vec![];
+
+        for [i, j, k] in self.trimesh.indices.iter() {
+            let tri = [i, j, k].map(|i| self.trimesh.vertices[*i as usize]);
+            let tri_min = tri[0].min(tri[1]).min(tri[2]);
+            let tri_max = tri[0].max(tri[1]).max(tri[2]);
+            if tri_min.cmpgt(max).any() || tri_max.cmplt(min).any() {
+                continue;
+            }
+            let (on_segment, on_triangle) = closest_segment_triangle(capsule.a, capsule.b, tri);
+            let distance = on_segment.distance(on_triangle);
+            if distance >= capsule.radius {
+                continue;
+            }
+            let normal = if distance > 1e-5 {
+                (on_segment - on_triangle) / distance
+            } else {
+                // the segment passes through the triangle, push it out on the side of its center.
+                let n = calculate_triag_normal(tri[0], tri[1], tri[2]);
+                if n.dot(center - tri[0]) < 0.0 {
+                    -n
+                } else {
+                    n
+                }
+            };
+            contacts.push(Contact {
+                point: on_triangle,
+                normal,
+                depth: capsule.radius - distance,
+            });
+        }
+
+        for beam in self.beams.iter() {
+            let (on_capsule, on_beam) =
+                closest_segment_segment(capsule.a, capsule.b, beam.a, beam.b);
+            let distance = on_capsule.distance(on_beam);
+            let radius = capsule.radius + beam.radius;
+            if distance >= radius || distance <= 1e-5 {
+                continue;
+            }
+            contacts.push(Contact {
+                point: on_beam + (on_capsule - on_beam) / distance * beam.radius,
+                normal: (on_capsule - on_beam) / distance,
+                depth: radius - distance,
+            });
+        }
+        contacts
+    }
+
+    pub fn capsule_intersects(&self, capsule: &Capsule) -> bool {
+        !self.capsule_contacts(capsule).is_empty()
+    }
+
+    /// Offset that moves the capsule out of the collider, found by pushing it out of its deepest
+    /// contact for up to `iterations` times. Returns the offset and the contacts it was pushed out of.
+    pub fn depenetrate(&self, capsule: &Capsule, iterations: usize) -> (Vec3, Vec<Contact>) {
+        let mut offset = Vec3::ZERO;
+        let mut resolved = vec![];
+        for _ in 0..iterations {
+            let contacts = self.capsule_contacts(&capsule.translated(offset));
+            let Some(deepest) = contacts
+                .into_iter()
+                .max_by(|a, b| a.depth.total_cmp(&b.depth))
+            else {
+                break;
+            };
+            // a tiny bit more, so the capsule does not touch the same face again
+            offset += deepest.normal * (deepest.depth + 1e-4);
+            resolved.push(deepest);
+        }
+        (offset, resolved)
+    }
+}
+
+/// The plane and cell of an axis aligned unit square, None for any other quad.
+fn unit_square(corners: &[Pos; 4]) -> Option<(Axis, i8, (i8, i8))> {
+    let min = corners.iter().skip(1).fold(corners[0], |m, p| {
+        Pos::new(m.x.min(p.x), m.y.min(p.y), m.z.min(p.z))
+    });
+    let max = corners.iter().skip(1).fold(corners[0], |m, p| {
+        Pos::new(m.x.max(p.x), m.y.max(p.y), m.z.max(p.z))
+    });
+    let size = max - min;
+    match (size.x, size.y, size.z) {
+        (0, 1, 1) => Some((Axis::X, min.x, (min.y, min.z))),
+        (1, 0, 1) => Some((Axis::Y, min.y, (min.x, min.z))),
+        (1, 1, 0) => Some((Axis::Z, min.z, (min.x, min.y))),
+        _ => None,
+    }
+}
+
+/// World position of the point at coordinate `c` along `axis` and `u`, `v` along the other two axes.
+fn from_plane(axis: Axis, c: f32, u: f32, v: f32) -> Vec3 {
+    match axis {
+        Axis::X => Vec3::new(c, u, v),
+        Axis::Y => Vec3::new(u, c, v),
+        Axis::Z => Vec3::new(u, v, c),
+    }
+}
+
+/// Greedily covers the cells with rectangles (u, v, width, height): every rectangle grows along u first,
+/// then along v as long as the whole next row is covered.
+fn merge_rectangles(cells: &[(i8, i8)]) -> Vec<(i8, i8, i8, i8)> {
+    let mut remaining: Vec<(i8, i8)> = cells.to_vec();
+    remaining.sort_by_key(|(u, v)| (*v, *u));
+    remaining.dedup();
+    let mut open: HashSet<(i8, i8)> = remaining.iter().copied().collect();
+    let mut rectangles = vec![];
+
+    for (u, v) in remaining {
+        if !open.contains(&(u, v)) {
+            continue;
+        }
+        let mut width = 1;
+        while open.contains(&(u + width, v)) {
+            width += 1;
+        }
+        let mut height = 1;
+        while (u..u + width).all(|cu| open.contains(&(cu, v + height))) {
+            height += 1;
+        }
+        for cu in u..u + width {
+            for cv in v..v + height {
+                open.remove(&(cu, cv));
+            }
+        }
+        rectangles.push((u, v, width, height));
+    }
+    rectangles
+}
+
+/// The face and a copy of it on both sides, `FACE_THICKNESS` apart.
+fn prism(corners: &[Pos]) -> ConvexPiece {
+    let verts: Vec<Vec3> = corners.iter().map(|p| Vec3::from(*p)).collect();
+    thin_prism(&verts)
+}
+
+fn thin_prism(verts: &[Vec3]) -> ConvexPiece {
+    let normal = calculate_triag_normal(verts[0], verts[1], verts[2]) * FACE_THICKNESS / 2.0;
+    let points = verts
+        .iter()
+        .map(|v| *v + normal)
+        .chain(verts.iter().map(|v| *v - normal))
+        .collect();
+    ConvexPiece { points }
+}
+
+/// Closest points between the segments p1-q1 and p2-q2, see Ericson, Real-Time Collision Detection 5.1.9.
+fn closest_segment_segment(p1: Vec3, q1: Vec3, p2: Vec3, q2: Vec3) -> (Vec3, Vec3) {
+    const EPSILON: f32 = 1e-6;
+    let d1 = q1 - p1;
+    let d2 = q2 - p2;
+    let r = p1 - p2;
+    let a = d1.length_squared();
+    let e = d2.length_squared();
+    let f = d2.dot(r);
+
+    let (s, t) = if a <= EPSILON && e <= EPSILON {
+        (0.0, 0.0)
+    } else if a <= EPSILON {
+        (0.0, (f / e).clamp(0.0, 1.0))
+    } else {
+        let c = d1.dot(r);
+        if e <= EPSILON {
+            ((-c / a).clamp(0.0, 1.0), 0.0)
+        } else {
+            let b = d1.dot(d2);
+            let denom = a * e - b * b;
+            let mut s = if denom > EPSILON {
+                ((b * f - c * e) / denom).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let mut t = (b * s + f) / e;
+            if t < 0.0 {
+                t = 0.0;
+                s = (-c / a).clamp(0.0, 1.0);
+            } else if t > 1.0 {
+                t = 1.0;
+                s = ((b - c) / a).clamp(0.0, 1.0);
+            }
+            (s, t)
+        }
+    };
+    (p1 + d1 * s, p2 + d2 * t)
+}
+
+/// Closest point on the triangle to p, see Ericson, Real-Time Collision Detection 5.1.5.
+fn closest_point_triangle(p: Vec3, [a, b, c]: [Vec3; 3]) -> Vec3 {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = p - a;
+    let d1 = ab.dot(ap);
+    let d2 = ac.dot(ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a;
+    }
+    let bp = p - b;
+    let d3 = ab.dot(bp);
+    let d4 = ac.dot(bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return b;
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + ab * (d1 / (d1 - d3));
+    }
+    let cp = p - c;
+    let d5 = ab.dot(cp);
+    let d6 = ac.dot(cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return c;
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + ac * (d2 / (d2 - d6));
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+    let denom = 1.0 / (va + vb + vc);
+    a + ab * (vb * denom) + ac * (vc * denom)
+}
+
+/// Closest points between the segment p-q and a triangle.
+fn closest_segment_triangle(p: Vec3, q: Vec3, tri: [Vec3; 3]) -> (Vec3, Vec3) {
+    // the segment crosses the triangle:
+    let normal = (tri[1] - tri[0]).cross(tri[2] - tri[0]);
+    let dp = normal.dot(p - tri[0]);
+    let dq = normal.dot(q - tri[0]);
+    if dp * dq < 0.0 {
+        let hit = p + (q - p) * (dp / (dp - dq));
+        if closest_point_triangle(hit, tri).distance_squared(hit) < 1e-10 {
+            return (hit, hit);
+        }
+    }
+
+    let mut candidates = vec![
+        (p, closest_point_triangle(p, tri)),
+        (q, closest_point_triangle(q, tri)),
+    ];
+    for i in 0..3 {
+        candidates.push(closest_segment_segment(p, q, tri[i], tri[(i + 1) % 3]));
+    }
+    candidates
+        .into_iter()
+        .min_by(|(a1, b1), (a2, b2)| {
+            a1.distance_squared(*b1)
+                .total_cmp(&a2.distance_squared(*b2))
+        })
+        .expect("not empty")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{
+        examples::solid_cube,
+        voxel::{Corner, Edge, EdgeKind, Matter, Voxel},
+        Chunk,
+    };
+
+    /// Dirt faces on the plane y = 0 for x and z in 0..size.
+    fn floor(size: i8) -> Chunk {
+        let mut chunk = Chunk::default();
+        for x in 0..=size {
+            for z in 0..=size {
+                let side = (x < size && z < size).then_some(Matter::Dirt);
+                chunk.set_voxel(
+                    Pos::new(x, 0, z),
+                    Voxel::new(None, side, None, Corner::Strong),
+                );
+            }
+        }
+        chunk
+    }
+
+    #[test]
+    fn trimesh_shares_corners() {
+        let ir = ChunkIR::construct_from_chunk(&solid_cube());
+        assert_eq!((ir.quads.len(), ir.triags.len()), (6, 0));
+        let trimesh = ir.build_trimesh();
+        assert_eq!(trimesh.vertices.len(), 8);
+        assert_eq!(trimesh.indices.len(), 12);
+        for triangle in trimesh.indices.iter() {
+            assert!(triangle
+                .iter()
+                .all(|i| (*i as usize) < trimesh.vertices.len()));
+        }
+    }
+
+    #[test]
+    fn rectangles_are_merged_greedily() {
+        // an L shape: two full rows and one more cell on top of the first column.
+        let cells = [
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (0, 1),
+            (1, 1),
+            (2, 1),
+            (0, 2),
+            (0, 2),
+        ];
+        assert_eq!(merge_rectangles(&cells), vec![(0, 0, 3, 2), (0, 2, 1, 1)]);
+        assert_eq!(merge_rectangles(&[]), vec![]);
+    }
+
+    #[test]
+    fn floor_becomes_one_box() {
+        let ir = ChunkIR::construct_from_chunk(&floor(4));
+        assert_eq!(ir.quads.len(), 16);
+        let pieces = ir.build_convex_pieces();
+        assert_eq!(pieces.len(), 1);
+        let points = &pieces[0].points;
+        let min = points.iter().fold(Vec3::splat(f32::MAX), |m, p| m.min(*p));
+        let max = points.iter().fold(Vec3::splat(f32::MIN), |m, p| m.max(*p));
+        let half = FACE_THICKNESS / 2.0;
+        assert_eq!(min, Vec3::new(0.0, -half, 0.0));
+        assert_eq!(max, Vec3::new(4.0, half, 4.0));
+
+        // the faces of a cube lie in 6 different planes:
+        let cube = ChunkIR::construct_from_chunk(&solid_cube());
+        assert_eq!(cube.build_convex_pieces().len(), 6);
+    }
+
+    #[test]
+    fn capsule_on_floor() {
+        let collider = ChunkIR::construct_from_chunk(&floor(4)).build_collider();
+        let above = Capsule::upright(Vec3::new(2.0, 0.1, 2.0), 1.8, 0.3);
+        assert!(!collider.capsule_intersects(&above));
+
+        let sunken = above.translated(Vec3::new(0.0, -0.15, 0.0));
+        let contacts = collider.capsule_contacts(&sunken);
+        assert!(!contacts.is_empty());
+        for contact in contacts.iter() {
+            assert!(contact.normal.abs_diff_eq(Vec3::Y, 1e-5), "{contact:?}");
+            assert!((contact.depth - 0.05).abs() < 1e-4, "{contact:?}");
+        }
+        let (offset, resolved) = collider.depenetrate(&sunken, 4);
+        assert!(!resolved.is_empty());
+        assert!((offset.y - 0.05).abs() < 1e-3, "{offset}");
+        assert!(!collider.capsule_intersects(&sunken.translated(offset)));
+    }
+
+    #[test]
+    fn capsule_against_beam() {
+        let mut chunk = Chunk::default();
+        chunk.edges.insert(
+            Pos::new(1, 1, 1),
+            vec![
+                Edge::new(EdgeKind::XY, Some(Matter::Wood)),
+                // edges without matter are no beams:
+                Edge::new(EdgeKind::Z, None),
+            ],
+        );
+        let collider = ChunkIR::construct_from_chunk(&chunk).build_collider();
+        assert_eq!(
+            collider.beams,
+            vec![Capsule {
+                a: Vec3::new(1.0, 1.0, 1.0),
+                b: Vec3::new(2.0, 2.0, 1.0),
+                radius: BEAM_RADIUS,
+            }]
+        );
+
+        // a capsule along z, next to the middle of the beam:
+        let side = Vec3::new(1.0, -1.0, 0.0).normalize();
+        let center = Vec3::new(1.5, 1.5, 1.0) + side * 0.3;
+        let capsule = Capsule {
+            a: center - Vec3::Z,
+            b: center + Vec3::Z,
+            radius: 0.2,
+        };
+        let contacts = collider.capsule_contacts(&capsule);
+        assert_eq!(contacts.len(), 1);
+        let contact = contacts[0];
+        assert!(contact.normal.abs_diff_eq(side, 1e-5), "{contact:?}");
+        assert!((contact.depth - (0.2 + BEAM_RADIUS - 0.3)).abs() < 1e-5);
+        assert!(contact
+            .point
+            .abs_diff_eq(Vec3::new(1.5, 1.5, 1.0) + side * BEAM_RADIUS, 1e-5));
+
+        let away = capsule.translated(side);
+        assert!(!collider.capsule_intersects(&away));
+    }
+}
@@ -0,0 +1,380 @@
+//! Connected bodies of solid corners.
+//!
+//! Two direct neighbours that are not Air belong to the same body if a face runs along the line between them,
+//! so Weak corners join a body through the sloped faces that cut them. Strong corners that no face touches
+//! lie inside of a body, like the inside of solid terrain, and join all their solid neighbours.
+//! Corners joined by an edge belong to the same body as well.
+//!
+//! Two bodies next to each other stay apart, even though their corners are direct neighbours.
+
+use std::hash::Hash;
+
+use bevy::utils::HashMap;
+
+use super::{
+    edit::{ChunkEdit, WorldEdit},
+    light::{from_world_pos, to_world_pos, WorldPos},
+    pos::{Axis, Pos},
+    structure::{
+        from_pos, offset, removal_edits, to_pos, touching_matters, EdgeIndex, Solids, NEIGHBORS,
+    },
+    Chunk, ChunkWorld, CHUNK_SIZE,
+};
+
+/// One connected body, with `WorldPos` for a ChunkWorld and `Pos` for a single Chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectedComponent<P> {
+    /// number of solid corners in the body.
+    pub count: usize,
+    /// smallest and largest coordinates of its corners, both inclusive.
+    pub min: P,
+    pub max: P,
+    /// true if the body touches the ground, the lowest voxel layer of the world,
+    /// or may continue into sections that are not loaded.
+    pub grounded: bool,
+}
+
+/// All connected bodies of a Chunk or ChunkWorld.
+#[derive(Debug, Clone, Default)]
+pub struct Components<P> {
+    /// index into `components` for every solid corner.
+    pub labels: HashMap<P, usize>,
+    /// in the order of their lexicographically smallest corner.
+    pub components: Vec<ConnectedComponent<P>>,
+}
+
+impl<P: Copy + Eq + Hash> Components<P> {
+    /// Indices of the bodies that neither touch the ground nor unloaded sections.
+    pub fn floating(&self) -> impl Iterator<Item = usize> + '_ {
+        self.components
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| !c.grounded)
+            .map(|(i, _)| i)
+    }
+
+    /// All corners of the body at `index`.
+    pub fn positions(&self, index: usize) -> impl Iterator<Item = P> + '_ {
+        self.labels
+            .iter()
+            .filter(move |(_, label)| **label == index)
+            .map(|(pos, _)| *pos)
+    }
+
+    /// The corners of all floating bodies with at most `max_count` corners.
+    fn floating_positions(&self, max_count: usize) -> Vec<P> {
+        let pruned: Vec<bool> = self
+            .components
+            .iter()
+            .map(|c| !c.grounded && c.count <= max_count)
+            .collect();
+        self.labels
+            .iter()
+            .filter(|(_, label)| pruned[**label])
+            .map(|(pos, _)| *pos)
+            .collect()
+    }
+
+    fn map<Q: Eq + Hash>(&self, f: impl Fn(P) -> Q) -> Components<Q> {
+        Components {
+            labels: self.labels.iter().map(|(p, l)| (f(*p), *l)).collect(),
+            components: self
+                .components
+                .iter()
+                .map(|c| ConnectedComponent {
+                    count: c.count,
+                    min: f(c.min),
+                    max: f(c.max),
+                    grounded: c.grounded,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Flood fills from the sorted solid corners in order, so the labels do not depend on hash map order.
+fn label(solids: &impl Solids, mut corners: Vec<WorldPos>) -> Components<WorldPos> {
+    corners.sort();
+    let edges = EdgeIndex::new(solids);
+    let mut components = Components::default();
+    for start in corners {
+        if components.labels.contains_key(&start) {
+            continue;
+        }
+        let index = components.components.len();
+        let mut component = ConnectedComponent {
+            count: 0,
+            min: start,
+            max: start,
+            grounded: false,
+        };
+        components.labels.insert(start, index);
+        let mut stack = vec![start];
+        while let Some(world_pos) = stack.pop() {
+            component.count += 1;
+            component.min = (
+                component.min.0.min(world_pos.0),
+                component.min.1.min(world_pos.1),
+                component.min.2.min(world_pos.2),
+            );
+            component.max = (
+                component.max.0.max(world_pos.0),
+                component.max.1.max(world_pos.1),
+                component.max.2.max(world_pos.2),
+            );
+            if world_pos.1 == 0 {
+                component.grounded = true;
+            }
+            for to in joined(solids, world_pos, &edges) {
+                if !solids.loaded(to) {
+                    component.grounded = true;
+                    continue;
+                }
+                if solids.corner(to).air() || components.labels.contains_key(&to) {
+                    continue;
+                }
+                components.labels.insert(to, index);
+                stack.push(to);
+            }
+        }
+        components.components.push(component);
+    }
+    components
+}
+
+/// The corners the solid corner at `world_pos` is joined with, see the module documentation.
+/// Neighbours in sections that are not loaded are included, as the body may continue there.
+fn joined(solids: &impl Solids, world_pos: WorldPos, edges: &EdgeIndex) -> Vec<WorldPos> {
+    let inside = inside_of_body(solids, world_pos);
+    let mut joined: Vec<WorldPos> = NEIGHBORS
+        .iter()
+        .map(|d| (offset(world_pos, *d), *d))
+        .filter(|(to, d)| {
+            !solids.loaded(*to)
+                || inside
+                || inside_of_body(solids, *to)
+                || face_along(solids, world_pos, *d)
+        })
+        .map(|(to, _)| to)
+        .collect();
+    joined.extend(
+        solids
+            .edges(world_pos)
+            .iter()
+            .map(|edge| offset(world_pos, edge.kind().delta())),
+    );
+    joined.extend(edges.ending_at(world_pos).iter().map(|(start, _)| *start));
+    joined
+}
+
+/// A Strong corner that no face touches.
+fn inside_of_body(solids: &impl Solids, world_pos: WorldPos) -> bool {
+    solids.corner(world_pos).strong() && touching_matters(solids, world_pos).is_empty()
+}
+
+/// Whether a side or an inner face runs along the line from `world_pos` to its direct neighbour
+/// at `world_pos + d`.
+fn face_along(solids: &impl Solids, world_pos: WorldPos, d: Pos) -> bool {
+    let start = match d.x + d.y + d.z < 0 {
+        true => offset(world_pos, d),
+        false => world_pos,
+    };
+    // the two axes orthogonal to the line:
+    let (b, c) = match d {
+        Pos { x: 0, y: 0, .. } => ((Axis::X, Pos::X), (Axis::Y, Pos::Y)),
+        Pos { x: 0, .. } => ((Axis::X, Pos::X), (Axis::Z, Pos::Z)),
+        _ => ((Axis::Y, Pos::Y), (Axis::Z, Pos::Z)),
+    };
+    // a side across one of them covers the line if its voxel lies on the line or one step back along the other:
+    let side = [(b.0, c.1), (c.0, b.1)].into_iter().any(|(across, back)| {
+        [Pos::ZERO, Pos::ZERO - back]
+            .into_iter()
+            .any(|o| solids.voxel(offset(start, o)).side(across).is_some())
+    });
+    // inner faces of the 4 cells around the line:
+    side || [
+        Pos::ZERO,
+        Pos::ZERO - b.1,
+        Pos::ZERO - c.1,
+        Pos::ZERO - b.1 - c.1,
+    ]
+    .into_iter()
+    .any(|o| solids.voxel(offset(start, o)).inner.is_some())
+}
+
+impl Chunk {
+    /// Positions of all corners inside the chunk bounds that are not Air.
+    pub fn solid_corners(&self) -> Vec<Pos> {
+        if self.fill.air() {
+            return self
+                .voxels
+                .iter()
+                .filter(|(pos, voxel)| Chunk::in_bounds(**pos) && !voxel.corner.air())
+                .map(|(pos, _)| *pos)
+                .collect();
+        }
+        let size = CHUNK_SIZE as i8;
+        let mut corners = vec![];
+        for x in 0..size {
+            for y in 0..size {
+                for z in 0..size {
+                    let pos = Pos::new(x, y, z);
+                    if !self.get_voxel(pos).corner.air() {
+                        corners.push(pos);
+                    }
+                }
+            }
+        }
+        corners
+    }
+
+    /// Labels the connected bodies of a chunk that is not part of a ChunkWorld.
+    /// Like in `Chunk::check_structure`, its lowest layer at y = 0 is the ground.
+    pub fn connected_components(&self) -> Components<Pos> {
+        let corners = self.solid_corners().into_iter().map(from_pos).collect();
+        label(self, corners).map(|world_pos| to_pos(world_pos).expect("lies inside of the chunk"))
+    }
+
+    /// The edit that removes all floating bodies with at most `max_count` corners.
+    pub fn prune_floating(&self, components: &Components<Pos>, max_count: usize) -> ChunkEdit {
+        let mut positions = components.floating_positions(max_count);
+        positions.sort();
+        ChunkEdit::Batch(
+            positions
+                .into_iter()
+                .flat_map(|pos| removal_edits(self, pos, from_pos(pos)))
+                .collect(),
+        )
+    }
+}
+
+impl ChunkWorld {
+    /// Labels the connected bodies of all loaded sections. Bodies are followed across section borders.
+    pub fn connected_components(&self) -> Components<WorldPos> {
+        let corners = self
+            .chunks
+            .iter()
+            .flat_map(|(chunk_pos, chunk)| {
+                chunk
+                    .solid_corners()
+                    .into_iter()
+                    .map(|pos| to_world_pos(*chunk_pos, pos))
+            })
+            .collect();
+        label(self, corners)
+    }
+
+    /// The edit that removes all floating bodies with at most `max_count` corners.
+    pub fn prune_floating(&self, components: &Components<WorldPos>, max_count: usize) -> WorldEdit {
+        let mut positions = components.floating_positions(max_count);
+        positions.sort();
+        WorldEdit::Batch(
+            positions
+                .into_iter()
+                .map(|world_pos| {
+                    let (chunk_pos, pos) = from_world_pos(world_pos);
+                    WorldEdit::Chunk(
+                        chunk_pos,
+                        ChunkEdit::Batch(removal_edits(self, pos, world_pos)),
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{
+        examples::example_chunks,
+        voxel::{Corner, Edge, EdgeKind, Matter, Voxel},
+        Corners,
+    };
+
+    /// A closed unit cube in the cell with its minimum corner at `cell`.
+    fn add_cube(chunk: &mut Chunk, cell: Pos) {
+        for offset in Corners::OFFSETS.to_array() {
+            let mut voxel = chunk.get_voxel(cell + offset);
+            voxel.corner = Corner::Strong;
+            chunk.set_voxel(cell + offset, voxel);
+        }
+        for (axis, step) in [(Axis::X, Pos::X), (Axis::Y, Pos::Y), (Axis::Z, Pos::Z)] {
+            for pos in [cell, cell + step] {
+                let mut voxel = chunk.get_voxel(pos);
+                *voxel.side_mut(axis) = Some(Matter::Dirt);
+                chunk.set_voxel(pos, voxel);
+            }
+        }
+    }
+
+    #[test]
+    fn bodies_next_to_each_other_stay_apart() {
+        let mut chunk = Chunk::default();
+        // the corners at x = 3 and x = 4 are direct neighbours, but no face lies between them:
+        add_cube(&mut chunk, Pos::new(2, 2, 2));
+        add_cube(&mut chunk, Pos::new(4, 2, 2));
+        let components = chunk.connected_components();
+        assert_eq!(components.components.len(), 2);
+        for component in components.components.iter() {
+            assert_eq!(component.count, 8);
+            assert!(!component.grounded);
+        }
+        assert_eq!(components.components[0].max, Pos::new(3, 3, 3));
+        assert_eq!(components.components[1].min, Pos::new(4, 2, 2));
+    }
+
+    #[test]
+    fn edges_join_bodies() {
+        let mut chunk = Chunk::default();
+        add_cube(&mut chunk, Pos::new(2, 2, 2));
+        add_cube(&mut chunk, Pos::new(4, 2, 2));
+        chunk.edges.insert(
+            Pos::new(3, 3, 3),
+            vec![Edge::new(EdgeKind::X, Some(Matter::Wood))],
+        );
+        let components = chunk.connected_components();
+        assert_eq!(components.components.len(), 1);
+        assert_eq!(components.components[0].count, 16);
+    }
+
+    #[test]
+    fn inside_of_terrain_is_one_body() {
+        let components = Chunk::full().connected_components();
+        assert_eq!(components.components.len(), 1);
+        let body = &components.components[0];
+        assert_eq!(body.count, (CHUNK_SIZE as usize).pow(3));
+        assert!(body.grounded);
+    }
+
+    #[test]
+    fn weak_corners_join_through_their_faces() {
+        // every example is a single body, some with Weak corners cut by sloped faces:
+        for chunk in example_chunks() {
+            assert_eq!(chunk.connected_components().components.len(), 1);
+        }
+
+        // a Weak corner that no face touches is no part of the cube:
+        let mut chunk = Chunk::default();
+        add_cube(&mut chunk, Pos::new(2, 2, 2));
+        chunk.set_voxel(
+            Pos::new(2, 4, 2),
+            Voxel::new(None, None, None, Corner::Weak),
+        );
+        assert_eq!(chunk.connected_components().components.len(), 2);
+    }
+
+    #[test]
+    fn prunes_floating_bodies() {
+        let mut chunk = Chunk::default();
+        add_cube(&mut chunk, Pos::new(2, 0, 2));
+        add_cube(&mut chunk, Pos::new(2, 5, 2));
+        let components = chunk.connected_components();
+        assert_eq!(components.floating().collect::<Vec<_>>(), vec![1]);
+
+        chunk.prune_floating(&components, 8).apply(&mut chunk);
+        let components = chunk.connected_components();
+        assert_eq!(components.components.len(), 1);
+        assert!(components.components[0].grounded);
+    }
+}
@@ -0,0 +1,384 @@
+//! Dual contouring, an alternative to the faces built from the corners and sides of the voxels.
+//!
+//! Every corner gets a density: Strong corners are inside of the surface, Weak and Air corners outside,
+//! Weak corners closer to the surface than Air. Cells are the unit cubes between 8 corners.
+//! The surface crosses every cell edge between an inside and an outside corner, the crossing point and the
+//! surface normal there are the Hermite data of the edge.
+//! Every cell the surface passes through gets one vertex that minimises the quadratic error function
+//! of the planes through its crossings, which keeps sharp edges and corners of the terrain.
+//! Every crossed edge becomes a quad between the vertices of the 4 cells around it.
+//!
+//! With only three densities, a normal from the density gradient bends at every edge of a block and rounds
+//! off its flat faces. So normals point along their edge instead: flat faces stay flat and the edges and
+//! corners between them sharp, while the crossings moved by Weak corners tilt the surface, because
+//! the QEF averages parallel planes at different offsets.
+//!
+//! Sides only decide the matter of the quads, the surface itself is defined by the corners alone.
+
+use bevy::{prelude::Vec3, utils::HashMap};
+
+use super::{
+    ao::VertexAo,
+    blend::MatterWeights,
+    ir::{ChunkIR, DualQuadIR},
+    light::VertexLight,
+    pos::{Pos, PosRange},
+    structure::UNFACED_MATTER,
+    voxel::{Corner, Matter, MATTER_COUNT},
+    Chunk, Corners, CHUNK_SIZE,
+};
+
+/// Eigenvalues of the quadratic error function below this are treated as 0, so the vertices of flat
+/// and smooth regions stay at the mass point of their crossings instead of drifting along the surface.
+const QEF_THRESHOLD: f32 = 0.1;
+
+/// Jacobi sweeps, more than enough to converge for a 3x3 matrix.
+const JACOBI_SWEEPS: usize = 8;
+
+fn density(corner: Corner) -> f32 {
+    match corner {
+        Corner::Air => -1.0,
+        Corner::Weak => -0.25,
+        Corner::Strong => 1.0,
+    }
+}
+
+/// The densities of the chunk and the one corner thick shell around it,
+/// the part of `Chunk::border` that the cells of the chunk reach.
+struct Densities {
+    values: Vec<f32>,
+}
+
+impl Densities {
+    const MIN: i8 = -1;
+    const MAX: i8 = CHUNK_SIZE as i8;
+    const SIZE: usize = CHUNK_SIZE as usize + 2;
+
+    fn new(chunk: &Chunk) -> Self {
+        let range = PosRange::new(
+            Pos::new(Self::MIN, Self::MIN, Self::MIN),
+            Pos::new(Self::MAX, Self::MAX, Self::MAX),
+        );
+        Densities {
+            values: range
+                .into_iter()
+                .map(|pos| density(chunk.get_voxel_corner(&pos)))
+                .collect(),
+        }
+    }
+
+    /// Panics for positions outside of the chunk and its border shell.
+    fn get(&self, pos: Pos) -> f32 {
+        let index = |c: i8| (c - Self::MIN) as usize;
+        self.values[(index(pos.x) * Self::SIZE + index(pos.y)) * Self::SIZE + index(pos.z)]
+    }
+
+    fn inside(&self, pos: Pos) -> bool {
+        self.get(pos) > 0.0
+    }
+
+    /// Where the surface crosses the edge between the corners `a` and `b`, and its normal there,
+    /// pointing out of the surface. None if the surface does not cross the edge.
+    fn crossing(&self, a: Pos, b: Pos) -> Option<(Vec3, Vec3)> {
+        let (da, db) = (self.get(a), self.get(b));
+        if (da > 0.0) == (db > 0.0) {
+            return None;
+        }
+        let t = da / (da - db);
+        let point = Vec3::from(a).lerp(Vec3::from(b), t);
+        let normal = (Vec3::from(b) - Vec3::from(a)) * da.signum();
+        Some((point, normal))
+    }
+
+    /// The vertex of the cell with the corner `cell` at its minimum, clamped into the cell.
+    fn cell_vertex(&self, cell: Pos) -> Vec3 {
+        let mut crossings = Vec::with_capacity(12);
+        for (axis, u, v) in [
+            (Pos::X, Pos::Y, Pos::Z),
+            (Pos::Y, Pos::Z, Pos::X),
+            (Pos::Z, Pos::X, Pos::Y),
+        ] {
+            for start in [cell, cell + u, cell + v, cell + u + v] {
+                crossings.extend(self.crossing(start, start + axis));
+            }
+        }
+        let min = Vec3::from(cell);
+        if crossings.is_empty() {
+            return min + Vec3::splat(0.5);
+        }
+        solve_qef(&crossings).clamp(min, min + Vec3::ONE)
+    }
+}
+
+/// The point closest to all planes through the crossings, starting from their mass point.
+fn solve_qef(crossings: &[(Vec3, Vec3)]) -> Vec3 {
+    let mass = crossings.iter().map(|(point, _)| *point).sum::<Vec3>() / crossings.len() as f32;
+    let mut ata = [[0.0f32; 3]; 3];
+    let mut atb = Vec3::ZERO;
+    for (point, normal) in crossings {
+        let n = normal.to_array();
+        for (row, n_row) in ata.iter_mut().zip(n) {
+            for (value, n_col) in row.iter_mut().zip(n) {
+                *value += n_row * n_col;
+            }
+        }
+        atb += *normal * normal.dot(*point - mass);
+    }
+    // pseudo inverse of the symmetric matrix through its eigen decomposition:
+    let (values, vectors) = symmetric_eigen(ata);
+    let mut offset = Vec3::ZERO;
+    for (i, value) in values.iter().enumerate() {
+        if value.abs() < QEF_THRESHOLD {
+            continue;
+        }
+        let vector = Vec3::new(vectors[0][i], vectors[1][i], vectors[2][i]);
+        offset += vector * (vector.dot(atb) / value);
+    }
+    mass + offset
+}
+
+/// Eigenvalues and eigenvectors (the columns of the matrix) of a symmetric 3x3 matrix, by Jacobi rotations.
+fn symmetric_eigen(matrix: [[f32; 3]; 3]) -> ([f32; 3], [[f32; 3]; 3]) {
+    let mut a = matrix;
+    let mut v = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    for _ in 0..JACOBI_SWEEPS {
+        for (p, q) in [(0, 1), (0, 2), (1, 2)] {
+            if a[p][q].abs() < 1e-9 {
+                continue;
+            }
+            let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+            let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+            let c = 1.0 / (t * t + 1.0).sqrt();
+            let s = t * c;
+            // a = Jᵀ a J and v = v J with the rotation J in the p-q plane:
+            for row in a.iter_mut().chain(v.iter_mut()) {
+                let (kp, kq) = (row[p], row[q]);
+                row[p] = c * kp - s * kq;
+                row[q] = s * kp + c * kq;
+            }
+            let (row_p, row_q) = (a[p], a[q]);
+            a[p] = [0, 1, 2].map(|k| c * row_p[k] - s * row_q[k]);
+            a[q] = [0, 1, 2].map(|k| s * row_p[k] + c * row_q[k]);
+        }
+    }
+    ([a[0][0], a[1][1], a[2][2]], v)
+}
+
+impl Chunk {
+    /// true if the chunk and its border shell are all inside of the dual surface or all outside of it,
+    /// so no cell edge owned by the chunk is crossed.
+    /// Unlike for the corner mesher, a full chunk below an empty one still owns the surface between them.
+    pub fn uniform_with_border(&self) -> bool {
+        let shell = Densities::SIZE.pow(3) - (CHUNK_SIZE as usize).pow(3);
+        // the border reaches one corner further on the positive sides than the densities:
+        let mut border = self
+            .border
+            .iter()
+            .filter(|(pos, _)| [pos.x, pos.y, pos.z].iter().all(|c| *c <= Densities::MAX));
+        if self.is_empty() {
+            border.all(|(_, corner)| !corner.strong())
+        } else if self.is_full() {
+            border.filter(|(_, corner)| corner.strong()).count() == shell
+        } else {
+            false
+        }
+    }
+
+    /// The matter of the quads around a corner inside of the surface: the first side of its voxel,
+    /// or `UNFACED_MATTER` if it has none.
+    fn dual_matter(&self, pos: Pos) -> Matter {
+        let voxel = self.get_voxel(pos);
+        voxel
+            .x_side
+            .or(voxel.y_side)
+            .or(voxel.z_side)
+            .or(voxel.inner)
+            .unwrap_or(UNFACED_MATTER)
+    }
+
+    /// Matter weights of the vertex of `cell`: every inside corner of the cell with a crossed cell edge
+    /// counts once for its `dual_matter`, the matter of the quads around that edge.
+    fn cell_matter(&self, densities: &Densities, cell: Pos) -> MatterWeights {
+        let mut counts = [0u32; MATTER_COUNT];
+        for offset in Corners::OFFSETS {
+            let corner = cell + offset;
+            if !densities.inside(corner) {
+                continue;
+            }
+            // the neighbours of the corner inside of the cell differ in one offset axis:
+            let crossed = [Pos::X, Pos::Y, Pos::Z].into_iter().any(|axis| {
+                let flipped = Pos::new(offset.x ^ axis.x, offset.y ^ axis.y, offset.z ^ axis.z);
+                !densities.inside(cell + flipped)
+            });
+            if crossed {
+                counts[self.dual_matter(corner).index()] += 1;
+            }
+        }
+        let total: u32 = counts.iter().sum();
+        if total == 0 {
+            return MatterWeights::only(UNFACED_MATTER);
+        }
+        MatterWeights(counts.map(|count| count as f32 / total as f32))
+    }
+}
+
+impl ChunkIR {
+    /// Meshes the chunk with dual contouring, see `dual`. All faces end up in `dual_quads`.
+    ///
+    /// The chunk owns the cell edges starting at its corners, the cells around them reach one corner
+    /// into the border shell, so the border has to be up to date like for `construct_from_chunk`.
+    pub fn construct_dual(chunk: &Chunk) -> Self {
+        let mut ir = ChunkIR::default();
+        if chunk.uniform_with_border() {
+            return ir;
+        }
+        let densities = Densities::new(chunk);
+        let mut vertices = HashMap::new();
+        let mut vertex = |cell: Pos| {
+            *vertices.entry(cell).or_insert_with(|| {
+                (
+                    densities.cell_vertex(cell),
+                    chunk.cell_matter(&densities, cell),
+                )
+            })
+        };
+
+        for start in PosRange::chunk() {
+            for (axis, u, v) in [
+                (Pos::X, Pos::Y, Pos::Z),
+                (Pos::Y, Pos::Z, Pos::X),
+                (Pos::Z, Pos::X, Pos::Y),
+            ] {
+                let end = start + axis;
+                let inside = densities.inside(start);
+                if inside == densities.inside(end) {
+                    continue;
+                }
+                // counterclockwise around `axis`, so the quad faces along it:
+                let cells = [start, start - u, start - u - v, start - v];
+                let mut vertices = cells.map(&mut vertex);
+                if !inside {
+                    // the surface faces from the inside corner to the outside corner:
+                    vertices.reverse();
+                }
+                let solid = if inside { start } else { end };
+                ir.dual_quads.push(dual_quad(
+                    chunk,
+                    chunk.dual_matter(solid),
+                    vertices.map(|(corner, _)| corner),
+                    vertices.map(|(_, blend)| blend),
+                ));
+            }
+        }
+        ir
+    }
+}
+
+/// Ambient occlusion and light are taken from the corner closest to each vertex.
+fn dual_quad(
+    chunk: &Chunk,
+    matter: Matter,
+    corners: [Vec3; 4],
+    blend: [MatterWeights; 4],
+) -> DualQuadIR {
+    let normal = (corners[2] - corners[0])
+        .cross(corners[3] - corners[1])
+        .normalize_or_zero();
+    let nearest = |vertex: Vec3| {
+        let v = vertex.round();
+        Pos::new(v.x as i8, v.y as i8, v.z as i8)
+    };
+    DualQuadIR {
+        matter,
+        corners,
+        ao: corners.map(|vertex| VertexAo {
+            front: chunk.vertex_ao(nearest(vertex), normal),
+            back: chunk.vertex_ao(nearest(vertex), -normal),
+        }),
+        light: corners.map(|vertex| VertexLight {
+            front: chunk.vertex_light(nearest(vertex), normal),
+            back: chunk.vertex_light(nearest(vertex), -normal),
+        }),
+        blend,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{examples::flat_ground, ChunkPos, ChunkWorld};
+
+    /// The section at the origin, with its border synced from the 26 sections around it.
+    fn section(generate: impl Fn(ChunkPos) -> Chunk) -> Chunk {
+        let mut world = ChunkWorld::default();
+        for x in -1..=1 {
+            for y in -1..=1 {
+                for z in -1..=1 {
+                    world.chunks.insert((x, y, z), generate((x, y, z)));
+                }
+            }
+        }
+        world.sync_border((0, 0, 0));
+        world.chunks.remove(&(0, 0, 0)).unwrap()
+    }
+
+    #[test]
+    fn sharp_corner() {
+        // three orthogonal planes through (0.5, 0.5, 0.5), crossed at points away from the corner:
+        let crossings = [
+            (Vec3::new(0.5, 0.2, 0.9), Vec3::X),
+            (Vec3::new(0.5, 0.8, 0.1), Vec3::X),
+            (Vec3::new(0.1, 0.5, 0.3), Vec3::Y),
+            (Vec3::new(0.7, 0.8, 0.5), Vec3::Z),
+        ];
+        let vertex = solve_qef(&crossings);
+        assert!(vertex.abs_diff_eq(Vec3::splat(0.5), 1e-4), "{vertex}");
+    }
+
+    #[test]
+    fn flat_crossings_stay_at_mass_point() {
+        let crossings = [
+            (Vec3::new(0.0, 0.5, 0.0), Vec3::Y),
+            (Vec3::new(1.0, 0.5, 0.0), Vec3::Y),
+            (Vec3::new(1.0, 0.5, 1.0), Vec3::Y),
+            (Vec3::new(0.0, 0.5, 1.0), Vec3::Y),
+        ];
+        let vertex = solve_qef(&crossings);
+        assert!(
+            vertex.abs_diff_eq(Vec3::new(0.5, 0.5, 0.5), 1e-4),
+            "{vertex}"
+        );
+    }
+
+    #[test]
+    fn flat_plane() {
+        let ir = ChunkIR::construct_dual(&section(|pos| flat_ground(pos, 4)));
+        let size = CHUNK_SIZE as usize;
+        assert_eq!(ir.dual_quads.len(), size * size);
+        for quad in ir.dual_quads.iter() {
+            assert!(quad.corners.iter().all(|corner| corner.y == 4.5));
+            let normal =
+                (quad.corners[2] - quad.corners[0]).cross(quad.corners[3] - quad.corners[1]);
+            assert!(normal.y > 0.0, "{quad:?}");
+        }
+    }
+
+    #[test]
+    fn full_section_under_empty_one() {
+        let chunk = section(|(_, y, _)| match y {
+            ..=0 => Chunk::full(),
+            _ => Chunk::default(),
+        });
+        assert!(chunk.is_full());
+        assert!(!chunk.uniform_with_border());
+        let ir = ChunkIR::construct_dual(&chunk);
+        assert!(!ir.dual_quads.is_empty());
+        let size = CHUNK_SIZE as usize;
+        assert_eq!(ir.dual_quads.len(), size * size);
+
+        let inside = section(|_| Chunk::full());
+        assert!(inside.uniform_with_border());
+        assert!(ChunkIR::construct_dual(&inside).dual_quads.is_empty());
+        assert!(Chunk::default().uniform_with_border());
+    }
+}
@@ -0,0 +1,533 @@
+use std::collections::VecDeque;
+
+use super::{
+    pos::{Axis, Pos},
+    voxel::{Corner, Edge, Matter, Voxel},
+    Chunk, ChunkPos, ChunkWorld,
+};
+
+/// A single reversible modification of a Chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkEdit {
+    SetVoxel {
+        pos: Pos,
+        voxel: Voxel,
+    },
+    SetCorner {
+        pos: Pos,
+        corner: Corner,
+    },
+    SetSide {
+        pos: Pos,
+        axis: Axis,
+        matter: Option<Matter>,
+    },
+    AddEdge {
+        pos: Pos,
+        edge: Edge,
+    },
+    RemoveEdge {
+        pos: Pos,
+        edge: Edge,
+    },
+    /// edits are applied in order
+    Batch(Vec<ChunkEdit>),
+}
+
+impl ChunkEdit {
+    /// Applies the edit to the chunk and returns the edit that reverts it.
+    pub fn apply(&self, chunk: &mut Chunk) -> ChunkEdit {
+        match self {
+            ChunkEdit::SetVoxel { pos, voxel } => ChunkEdit::SetVoxel {
+                pos: *pos,
+                voxel: chunk.set_voxel(*pos, *voxel),
+            },
+            ChunkEdit::SetCorner { pos, corner } => {
+                let mut voxel = chunk.get_voxel(*pos);
+                let old = std::mem::replace(&mut voxel.corner, *corner);
+                chunk.set_voxel(*pos, voxel);
+                ChunkEdit::SetCorner {
+                    pos: *pos,
+                    corner: old,
+                }
+            }
+            ChunkEdit::SetSide { pos, axis, matter } => {
+                let mut voxel = chunk.get_voxel(*pos);
+                let old = std::mem::replace(voxel.side_mut(*axis), *matter);
+                chunk.set_voxel(*pos, voxel);
+                ChunkEdit::SetSide {
+                    pos: *pos,
+                    axis: *axis,
+                    matter: old,
+                }
+            }
+            ChunkEdit::AddEdge { pos, edge } => {
+                chunk.edges.entry(*pos).or_default().push(*edge);
+                chunk.dirty.insert(*pos);
+                ChunkEdit::RemoveEdge {
+                    pos: *pos,
+                    edge: *edge,
+                }
+            }
+            ChunkEdit::RemoveEdge { pos, edge } => {
+                let Some(edges) = chunk.edges.get_mut(pos) else {
+                    return ChunkEdit::Batch(vec![]);
+                };
+                let Some(i) = edges.iter().position(|e| e == edge) else {
+                    return ChunkEdit::Batch(vec![]);
+                };
+                edges.remove(i);
+                if edges.is_empty() {
+                    chunk.edges.remove(pos);
+                }
+                chunk.dirty.insert(*pos);
+                ChunkEdit::AddEdge {
+                    pos: *pos,
+                    edge: *edge,
+                }
+            }
+            ChunkEdit::Batch(edits) => {
+                let mut inverse: Vec<ChunkEdit> = edits.iter().map(|e| e.apply(chunk)).collect();
+                inverse.reverse();
+                ChunkEdit::Batch(inverse)
+            }
+        }
+    }
+
+    /// All voxel positions this edit touches.
+    pub fn positions(&self) -> Vec<Pos> {
+        match self {
+            ChunkEdit::SetVoxel { pos, .. }
+            | ChunkEdit::SetCorner { pos, .. }
+            | ChunkEdit::SetSide { pos, .. }
+            | ChunkEdit::AddEdge { pos, .. }
+            | ChunkEdit::RemoveEdge { pos, .. } => vec![*pos],
+            ChunkEdit::Batch(edits) => edits.iter().flat_map(|e| e.positions()).collect(),
+        }
+    }
+}
+
+/// A ChunkEdit addressed to one chunk of a ChunkWorld.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorldEdit {
+    Chunk(ChunkPos, ChunkEdit),
+    /// edits are applied in order
+    Batch(Vec<WorldEdit>),
+}
+
+impl WorldEdit {
+    /// Applies the edit to the world and returns the edit that reverts it.
+    /// Chunks that do not exist yet are created.
+    pub fn apply(&self, world: &mut ChunkWorld) -> WorldEdit {
+        match self {
+            WorldEdit::Chunk(chunk_pos, edit) => {
+                let chunk = world.chunks.entry(*chunk_pos).or_default();
+                WorldEdit::Chunk(*chunk_pos, edit.apply(chunk))
+            }
+            WorldEdit::Batch(edits) => {
+                let mut inverse: Vec<WorldEdit> = edits.iter().map(|e| e.apply(world)).collect();
+                inverse.reverse();
+                WorldEdit::Batch(inverse)
+            }
+        }
+    }
+}
+
+/// Something edits can be applied to, `Chunk` with `ChunkEdit`s or `ChunkWorld` with `WorldEdit`s.
+pub trait EditTarget {
+    type Edit: Clone;
+
+    /// Applies the edit and returns the edit that reverts it.
+    fn apply_edit(&mut self, edit: &Self::Edit) -> Self::Edit;
+
+    fn batch(edits: Vec<Self::Edit>) -> Self::Edit;
+
+    /// The edits of a batch, or the edit itself if it is no batch.
+    fn unbatch(edit: Self::Edit) -> Vec<Self::Edit>;
+}
+
+impl EditTarget for Chunk {
+    type Edit = ChunkEdit;
+
+    fn apply_edit(&mut self, edit: &ChunkEdit) -> ChunkEdit {
+        edit.apply(self)
+    }
+
+    fn batch(edits: Vec<ChunkEdit>) -> ChunkEdit {
+        ChunkEdit::Batch(edits)
+    }
+
+    fn unbatch(edit: ChunkEdit) -> Vec<ChunkEdit> {
+        match edit {
+            ChunkEdit::Batch(edits) => edits,
+            edit => vec![edit],
+        }
+    }
+}
+
+impl EditTarget for ChunkWorld {
+    type Edit = WorldEdit;
+
+    fn apply_edit(&mut self, edit: &WorldEdit) -> WorldEdit {
+        edit.apply(self)
+    }
+
+    fn batch(edits: Vec<WorldEdit>) -> WorldEdit {
+        WorldEdit::Batch(edits)
+    }
+
+    fn unbatch(edit: WorldEdit) -> Vec<WorldEdit> {
+        match edit {
+            WorldEdit::Batch(edits) => edits,
+            edit => vec![edit],
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct HistoryEntry<E> {
+    edit: E,
+    inverse: E,
+    /// entries with the same stroke id are merged into one undo step
+    stroke: Option<u64>,
+}
+
+/// Bounded undo/redo stack. The oldest entries are dropped once `capacity` is reached.
+///
+/// Edits applied between `begin_stroke` and `end_stroke` are merged into a single entry,
+/// so a brush stroke over many voxels is undone in one step.
+#[derive(Debug, Clone)]
+pub struct EditHistory<T: EditTarget> {
+    undo: VecDeque<HistoryEntry<T::Edit>>,
+    redo: Vec<HistoryEntry<T::Edit>>,
+    capacity: usize,
+    current_stroke: Option<u64>,
+    next_stroke: u64,
+}
+
+impl<T: EditTarget> Default for EditHistory<T> {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl<T: EditTarget> EditHistory<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: vec![],
+            capacity: capacity.max(1),
+            current_stroke: None,
+            next_stroke: 0,
+        }
+    }
+
+    /// Applies the edit to the target and records it. Clears the redo stack.
+    pub fn apply(&mut self, target: &mut T, edit: T::Edit) {
+        let inverse = target.apply_edit(&edit);
+        self.redo.clear();
+
+        if let Some(stroke) = self.current_stroke {
+            if let Some(top) = self.undo.back_mut() {
+                if top.stroke == Some(stroke) {
+                    // one flat batch per stroke, nesting would grow with every step of the stroke:
+                    let mut edits = T::unbatch(std::mem::replace(&mut top.edit, T::batch(vec![])));
+                    edits.push(edit);
+                    top.edit = T::batch(edits);
+                    // the inverses are kept in the order of the edits, `end_stroke` reverses them once:
+                    let mut inverses =
+                        T::unbatch(std::mem::replace(&mut top.inverse, T::batch(vec![])));
+                    inverses.push(inverse);
+                    top.inverse = T::batch(inverses);
+                    return;
+                }
+            }
+        }
+
+        // inside of a stroke the inverse is wrapped, so reversing the stroke keeps a batch inverse intact:
+        let inverse = match self.current_stroke {
+            Some(_) => T::batch(vec![inverse]),
+            None => inverse,
+        };
+        self.undo.push_back(HistoryEntry {
+            edit,
+            inverse,
+            stroke: self.current_stroke,
+        });
+        if self.undo.len() > self.capacity {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Reverts the last entry. Returns the edit that was applied to the target, if any.
+    pub fn undo(&mut self, target: &mut T) -> Option<&T::Edit> {
+        self.end_stroke();
+        let mut entry = self.undo.pop_back()?;
+        entry.edit = target.apply_edit(&entry.inverse);
+        self.redo.push(entry);
+        self.redo.last().map(|e| &e.inverse)
+    }
+
+    /// Re-applies the last undone entry. Returns the edit that was applied to the target, if any.
+    pub fn redo(&mut self, target: &mut T) -> Option<&T::Edit> {
+        self.end_stroke();
+        let mut entry = self.redo.pop()?;
+        entry.inverse = target.apply_edit(&entry.edit);
+        self.undo.push_back(entry);
+        self.undo.back().map(|e| &e.edit)
+    }
+
+    /// All following edits are merged into one entry until `end_stroke` is called.
+    pub fn begin_stroke(&mut self) {
+        self.end_stroke();
+        self.current_stroke = Some(self.next_stroke);
+        self.next_stroke += 1;
+    }
+
+    /// Finishes the entry of the current stroke, its inverse reverts the edits in reverse order.
+    pub fn end_stroke(&mut self) {
+        let Some(stroke) = self.current_stroke.take() else {
+            return;
+        };
+        if let Some(top) = self.undo.back_mut() {
+            if top.stroke == Some(stroke) {
+                let mut inverses =
+                    T::unbatch(std::mem::replace(&mut top.inverse, T::batch(vec![])));
+                inverses.reverse();
+                top.inverse = T::batch(inverses);
+            }
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.current_stroke = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::voxel::EdgeKind;
+
+    fn voxel(matter: Matter) -> Voxel {
+        Voxel::new(Some(matter), None, Some(matter), Corner::Strong)
+    }
+
+    fn test_chunk() -> Chunk {
+        let mut chunk = Chunk::default();
+        chunk.set_voxel(Pos::new(1, 1, 1), voxel(Matter::Dirt));
+        chunk.set_voxel(Pos::new(2, 1, 1), voxel(Matter::Wood));
+        chunk.edges.insert(
+            Pos::new(1, 1, 1),
+            vec![Edge::new(EdgeKind::X, Some(Matter::Dirt))],
+        );
+        chunk
+    }
+
+    fn assert_same_content(a: &Chunk, b: &Chunk) {
+        assert_eq!(a.voxels, b.voxels);
+        assert_eq!(a.edges, b.edges);
+    }
+
+    fn all_edits() -> Vec<ChunkEdit> {
+        let pos = Pos::new(1, 1, 1);
+        vec![
+            ChunkEdit::SetVoxel {
+                pos: Pos::new(3, 3, 3),
+                voxel: voxel(Matter::Lamp),
+            },
+            ChunkEdit::SetVoxel {
+                pos,
+                voxel: Voxel::default(),
+            },
+            ChunkEdit::SetCorner {
+                pos,
+                corner: Corner::Weak,
+            },
+            ChunkEdit::SetSide {
+                pos,
+                axis: Axis::Y,
+                matter: Some(Matter::Lamp),
+            },
+            ChunkEdit::SetSide {
+                pos,
+                axis: Axis::X,
+                matter: None,
+            },
+            ChunkEdit::AddEdge {
+                pos: Pos::new(2, 1, 1),
+                edge: Edge::new(EdgeKind::Y, None),
+            },
+            ChunkEdit::RemoveEdge {
+                pos,
+                edge: Edge::new(EdgeKind::X, Some(Matter::Dirt)),
+            },
+            // removing an edge that does not exist changes nothing:
+            ChunkEdit::RemoveEdge {
+                pos,
+                edge: Edge::new(EdgeKind::Z, None),
+            },
+            ChunkEdit::Batch(vec![
+                ChunkEdit::SetCorner {
+                    pos,
+                    corner: Corner::Air,
+                },
+                ChunkEdit::SetCorner {
+                    pos,
+                    corner: Corner::Weak,
+                },
+                ChunkEdit::SetVoxel {
+                    pos,
+                    voxel: voxel(Matter::Lamp),
+                },
+            ]),
+        ]
+    }
+
+    #[test]
+    fn inverse_restores_chunk() {
+        for edit in all_edits() {
+            let original = test_chunk();
+            let mut chunk = original.clone();
+            let inverse = edit.apply(&mut chunk);
+            inverse.apply(&mut chunk);
+            assert_same_content(&chunk, &original);
+        }
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut chunk = test_chunk();
+        let mut history = EditHistory::<Chunk>::default();
+        let states: Vec<Chunk> = all_edits()
+            .into_iter()
+            .map(|edit| {
+                let before = chunk.clone();
+                history.apply(&mut chunk, edit);
+                before
+            })
+            .collect();
+        let last = chunk.clone();
+
+        for before in states.iter().rev() {
+            assert!(history.undo(&mut chunk).is_some());
+            assert_same_content(&chunk, before);
+        }
+        assert!(!history.can_undo());
+        assert!(history.undo(&mut chunk).is_none());
+
+        while history.redo(&mut chunk).is_some() {}
+        assert_same_content(&chunk, &last);
+
+        // a new edit clears the redo stack:
+        history.undo(&mut chunk);
+        history.apply(&mut chunk, all_edits().remove(0));
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn capacity_is_bounded() {
+        let mut chunk = Chunk::default();
+        let mut history = EditHistory::<Chunk>::new(3);
+        for z in 0..5 {
+            history.apply(
+                &mut chunk,
+                ChunkEdit::SetCorner {
+                    pos: Pos::new(0, 0, z),
+                    corner: Corner::Strong,
+                },
+            );
+        }
+        let mut undone = 0;
+        while history.undo(&mut chunk).is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, 3);
+        // the two oldest edits can not be undone anymore:
+        assert!(chunk.get_voxel(Pos::new(0, 0, 0)).corner.strong());
+        assert!(chunk.get_voxel(Pos::new(0, 0, 1)).corner.strong());
+        assert!(chunk.get_voxel(Pos::new(0, 0, 2)).corner.air());
+    }
+
+    #[test]
+    fn stroke_is_undone_in_one_step() {
+        let original = test_chunk();
+        let mut chunk = original.clone();
+        let mut history = EditHistory::<Chunk>::default();
+        history.apply(
+            &mut chunk,
+            ChunkEdit::SetCorner {
+                pos: Pos::new(5, 5, 5),
+                corner: Corner::Weak,
+            },
+        );
+        let before_stroke = chunk.clone();
+
+        history.begin_stroke();
+        for edit in all_edits() {
+            history.apply(&mut chunk, edit);
+        }
+        history.end_stroke();
+        let after_stroke = chunk.clone();
+
+        history.undo(&mut chunk);
+        assert_same_content(&chunk, &before_stroke);
+        history.redo(&mut chunk);
+        assert_same_content(&chunk, &after_stroke);
+        history.undo(&mut chunk);
+        history.undo(&mut chunk);
+        assert_same_content(&chunk, &original);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn stroke_starting_with_a_batch_is_undone_in_order() {
+        let original = test_chunk();
+        let mut chunk = original.clone();
+        let mut history = EditHistory::<Chunk>::default();
+        let pos = Pos::new(5, 5, 5);
+        let set = |corner| ChunkEdit::SetCorner { pos, corner };
+
+        history.begin_stroke();
+        history.apply(
+            &mut chunk,
+            ChunkEdit::Batch(vec![set(Corner::Weak), set(Corner::Strong)]),
+        );
+        history.apply(&mut chunk, set(Corner::Air));
+        history.apply(&mut chunk, set(Corner::Weak));
+        // undo ends the stroke on its own:
+        history.undo(&mut chunk);
+        assert_same_content(&chunk, &original);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn long_stroke_stays_flat() {
+        let mut chunk = Chunk::default();
+        let mut history = EditHistory::<Chunk>::default();
+        history.begin_stroke();
+        for step in 0..10_000 {
+            let pos = Pos::new((step % 32) as i8, (step / 32 % 32) as i8, 0);
+            history.apply(
+                &mut chunk,
+                ChunkEdit::SetCorner {
+                    pos,
+                    corner: Corner::Strong,
+                },
+            );
+        }
+        history.end_stroke();
+        history.undo(&mut chunk);
+        assert!(chunk.voxels.is_empty());
+    }
+}
@@ -3,7 +3,7 @@ use crate::chunk::{
     voxel::{Corner, Matter, Voxel},
 };
 
-use super::Chunk;
+use super::{Chunk, ChunkPos, CHUNK_SIZE};
 
 const D: Option<Matter> = Some(Matter::Dirt);
 const N: Option<Matter> = None;
@@ -45,7 +45,7 @@ pub fn solid_cube() -> Chunk {
             .into_iter()
             .map(|(x, y, z, v)| (Pos::new(x, y, z), v))
             .collect(),
-        edges: Default::default(),
+        ..Default::default()
     }
 }
 
@@ -69,7 +69,7 @@ pub fn solid_cube_weak_corner() -> Chunk {
             .into_iter()
             .map(|(x, y, z, v)| (Pos::new(x, y, z), v))
             .collect(),
-        edges: Default::default(),
+        ..Default::default()
     }
 }
 
@@ -93,7 +93,7 @@ pub fn solid_cube_3weak_corners() -> Chunk {
             .into_iter()
             .map(|(x, y, z, v)| (Pos::new(x, y, z), v))
             .collect(),
-        edges: Default::default(),
+        ..Default::default()
     }
 }
 
@@ -137,6 +137,31 @@ pub fn solid_cube_on_plane() -> Chunk {
             .into_iter()
             .map(|(x, y, z, v)| (Pos::new(x, y, z), v))
             .collect(),
-        edges: Default::default(),
+        ..Default::default()
     }
 }
+
+/// Generates the sections of a flat world: solid ground up to `ground` with a dirt surface on top.
+/// Sections above the ground are empty, sections below it are full.
+pub fn flat_ground(chunk_pos: ChunkPos, ground: isize) -> Chunk {
+    let size = CHUNK_SIZE as isize;
+    let section_min = chunk_pos.1 * size;
+    if section_min > ground {
+        return Chunk::default();
+    }
+    if section_min + size <= ground {
+        return Chunk::full();
+    }
+
+    let mut chunk = Chunk::default();
+    let top = (ground - section_min) as i8;
+    for x in 0..CHUNK_SIZE as i8 {
+        for z in 0..CHUNK_SIZE as i8 {
+            for y in 0..=top {
+                let y_side = if y == top { D } else { N };
+                chunk.set_voxel(Pos::new(x, y, z), v!(N, y_side, N, Strong));
+            }
+        }
+    }
+    chunk
+}
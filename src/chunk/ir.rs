@@ -1,19 +1,38 @@
 use bevy::{
     prelude::{Color, Gizmos, Mesh, Vec3},
-    render::{mesh::Indices, render_resource::PrimitiveTopology},
+    render::{
+        mesh::{Indices, MeshVertexAttribute},
+        render_resource::{PrimitiveTopology, VertexFormat},
+    },
 };
 
 use super::{
+    ao::VertexAo,
+    blend::{triangle_blend, MatterWeights, BLEND_SLOTS},
+    light::VertexLight,
     pos::{self, Pos},
-    voxel::Matter,
+    voxel::{Matter, Voxel},
     Chunk, Side,
 };
 
-#[derive(Debug, Clone)]
+/// Chooses how the faces of a chunk are built.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Mesher {
+    /// quads and triangles between the corners of the voxels, for the sides they own.
+    #[default]
+    Corners,
+    /// one vertex per cell and a quad for every crossed cell edge, see `dual`.
+    DualContouring,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct ChunkIR {
     pub quads: Vec<QuadIR>,
     pub triags: Vec<TriagIR>,
     pub edges: Vec<EdgeIR>,
+    /// faces of `Mesher::DualContouring`, their vertices do not lie on corners.
+    /// They are part of the mesh and the collider, but can not be picked.
+    pub dual_quads: Vec<DualQuadIR>,
 }
 
 #[derive(Debug, Clone)]
@@ -23,6 +42,12 @@ pub struct QuadIR {
     pub b: Pos,
     pub c: Pos,
     pub d: Pos,
+    /// ambient occlusion of a, b, c and d.
+    pub ao: [VertexAo; 4],
+    /// light of a, b, c and d.
+    pub light: [VertexLight; 4],
+    /// matter weights of a, b, c and d, see `blend`.
+    pub blend: [MatterWeights; 4],
 }
 
 pub fn calculate_triag_normal(a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
@@ -37,6 +62,37 @@ pub struct TriagIR {
     pub a: Pos,
     pub b: Pos,
     pub c: Pos,
+    /// ambient occlusion of a, b and c.
+    pub ao: [VertexAo; 3],
+    /// light of a, b and c.
+    pub light: [VertexLight; 3],
+    /// matter weights of a, b and c, see `blend`.
+    pub blend: [MatterWeights; 3],
+}
+
+/// A quad between the vertices of the 4 cells around a cell edge, not necessarily planar.
+#[derive(Debug, Clone)]
+pub struct DualQuadIR {
+    pub matter: Matter,
+    /// counterclockwise seen from outside of the surface.
+    pub corners: [Vec3; 4],
+    /// ambient occlusion of the corners.
+    pub ao: [VertexAo; 4],
+    /// light of the corners.
+    pub light: [VertexLight; 4],
+    /// matter weights of the corners, see `blend`.
+    pub blend: [MatterWeights; 4],
+}
+
+impl DualQuadIR {
+    /// The corner indices of the two triangles of the quad, without the ones that collapsed
+    /// because two cell vertices were clamped onto the same point.
+    pub fn triangles(&self) -> impl Iterator<Item = [usize; 3]> + '_ {
+        [[0, 1, 2], [0, 2, 3]].into_iter().filter(|[i, j, k]| {
+            let [a, b, c] = [*i, *j, *k].map(|i| self.corners[i]);
+            (b - a).cross(c - a).length_squared() >= f32::EPSILON
+        })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -47,47 +103,111 @@ pub struct EdgeIR {
 }
 
 impl ChunkIR {
+    /// Builds the faces of the chunk with the chosen mesher.
+    pub fn construct(chunk: &Chunk, mesher: Mesher) -> Self {
+        match mesher {
+            Mesher::Corners => ChunkIR::construct_from_chunk(chunk),
+            Mesher::DualContouring => ChunkIR::construct_dual(chunk),
+        }
+    }
+
     pub fn construct_from_chunk(chunk: &Chunk) -> Self {
-        let mut quads: Vec<QuadIR> = vec![];
-        let mut triags: Vec<TriagIR> = vec![];
-        let mut edges: Vec<EdgeIR> = vec![];
+        let mut ir = ChunkIR::default();
+        for (pos, voxel) in chunk.voxels.iter() {
+            ir.add_voxel_sides(chunk, *pos, voxel);
+        }
+        for pos in chunk.edges.keys() {
+            ir.add_voxel_edges(chunk, *pos);
+        }
+        ir
+    }
+
+    /// Only constructs the faces owned by voxels in the cube of `size` starting at `min`.
+    pub fn construct_from_region(chunk: &Chunk, min: Pos, size: i8) -> Self {
+        let mut ir = ChunkIR::default();
+        let range = |start: i8| start..=(start as i16 + size as i16 - 1).min(i8::MAX as i16) as i8;
+        for x in range(min.x) {
+            for y in range(min.y) {
+                for z in range(min.z) {
+                    let pos = Pos::new(x, y, z);
+                    if let Some(voxel) = chunk.voxels.get(&pos) {
+                        ir.add_voxel_sides(chunk, pos, voxel);
+                    }
+                    ir.add_voxel_edges(chunk, pos);
+                }
+            }
+        }
+        ir
+    }
+
+    fn add_voxel_sides(&mut self, chunk: &Chunk, pos: Pos, voxel: &Voxel) {
+        if voxel.corner.air() {
+            return;
+        }
 
         let mut add_side = |side: Side, matter: Matter| match side {
             super::Side::None => {}
             super::Side::Triag(a, b, c) => {
-                let triag = TriagIR { matter, a, b, c };
-                triags.push(triag);
+                let triag = TriagIR {
+                    matter,
+                    a,
+                    b,
+                    c,
+                    ao: chunk.face_ao([a, b, c]),
+                    light: chunk.face_light([a, b, c]),
+                    blend: chunk.face_matter(matter, [a, b, c]),
+                };
+                self.triags.push(triag);
             }
             super::Side::Quad(a, b, c, d) => {
-                let quad = QuadIR { matter, a, b, c, d };
-                quads.push(quad);
+                let quad = QuadIR {
+                    matter,
+                    a,
+                    b,
+                    c,
+                    d,
+                    ao: chunk.face_ao([a, b, c, d]),
+                    light: chunk.face_light([a, b, c, d]),
+                    blend: chunk.face_matter(matter, [a, b, c, d]),
+                };
+                self.quads.push(quad);
             }
         };
 
-        for (pos, voxel) in chunk.voxels.iter() {
-            if !voxel.corner.air() {
-                let corner_info = chunk.get_voxel_corner_info(*pos, voxel.corner);
-                // add x side:
-                if let Some(matter) = voxel.x_side {
-                    add_side(corner_info.x_side(), matter);
-                }
-                // add y side:
-                if let Some(matter) = voxel.y_side {
-                    add_side(corner_info.y_side(), matter);
-                }
-                // add z side:
-                if let Some(matter) = voxel.z_side {
-                    add_side(corner_info.z_side(), matter);
-                }
+        let corner_info = chunk.get_voxel_corner_info(pos, voxel.corner);
+        // add x side:
+        if let Some(matter) = voxel.x_side {
+            add_side(corner_info.x_side(), matter);
+        }
+        // add y side:
+        if let Some(matter) = voxel.y_side {
+            add_side(corner_info.y_side(), matter);
+        }
+        // add z side:
+        if let Some(matter) = voxel.z_side {
+            add_side(corner_info.z_side(), matter);
+        }
+        // add the diagonal faces inside of the cube:
+        if let Some(matter) = voxel.inner {
+            for side in corner_info.inner_sides() {
+                add_side(side, matter);
             }
         }
+    }
 
-        // todo!("add edges");
-
-        Self {
-            quads,
-            triags,
-            edges,
+    /// A beam for every edge stored at `pos` that is made of some matter.
+    fn add_voxel_edges(&mut self, chunk: &Chunk, pos: Pos) {
+        let Some(edges) = chunk.edges.get(&pos) else {
+            return;
+        };
+        for edge in edges {
+            if let Some(matter) = edge.matter() {
+                self.edges.push(EdgeIR {
+                    matter,
+                    a: pos,
+                    b: pos + edge.kind().delta(),
+                });
+            }
         }
     }
 
@@ -117,6 +237,12 @@ impl ChunkIR {
             draw_triangle(a, b, c);
         }
 
+        for quad in self.dual_quads.iter() {
+            let [a, b, c, d] = quad.corners;
+            draw_triangle(a, b, c);
+            draw_triangle(a, c, d);
+        }
+
         for edge in self.edges.iter() {
             let a: Vec3 = edge.a.into();
             let b: Vec3 = edge.b.into();
@@ -125,39 +251,16 @@ impl ChunkIR {
     }
 
     pub fn construct_mesh(&self) -> Mesh {
-        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        self.construct_mesh_buffers().into_mesh()
+    }
 
-        let mut verts: Vec<[f32; 3]> = vec![];
-        let mut normals: Vec<[f32; 3]> = vec![];
-        let mut uvs: Vec<[f32; 2]> = vec![];
-        let mut indices: Vec<u32> = vec![];
+    pub fn construct_mesh_buffers(&self) -> MeshBuffers {
+        let mut buffers = MeshBuffers::default();
 
         // const TO_X: [f32; 3] = [1.0, 0.0, 0.0];
         // const TO_Y: [f32; 3] = [0.0, 1.0, 0.0];
         // const TO_Z: [f32; 3] = [0.0, 0.0, 1.0];
 
-        let mut add_triangle = |a: [f32; 3],
-                                b: [f32; 3],
-                                c: [f32; 3],
-                                a_uv: [f32; 2],
-                                b_uv: [f32; 2],
-                                c_uv: [f32; 2],
-                                normal: [f32; 3]| {
-            let i = verts.len() as u32;
-            verts.push(a);
-            verts.push(b);
-            verts.push(c);
-            normals.push(normal);
-            normals.push(normal);
-            normals.push(normal);
-            uvs.push(a_uv);
-            uvs.push(b_uv);
-            uvs.push(c_uv);
-            indices.push(i);
-            indices.push(i + 1);
-            indices.push(i + 2);
-        };
-
         for quad in self.quads.iter() {
             let a: Vec3 = quad.a.into();
             let b: Vec3 = quad.b.into();
@@ -185,12 +288,41 @@ impl ChunkIR {
             let c: [f32; 3] = c.into();
             let d: [f32; 3] = d.into();
 
+            let front = |i: usize| vertex_color(quad.ao[i].front, quad.light[i].front);
+            let back = |i: usize| vertex_color(quad.ao[i].back, quad.light[i].back);
+
             // draw double sided triangles:
 
-            add_triangle(a, b, c, a_uv, b_uv, c_uv, normal);
-            add_triangle(a, c, b, a_uv, c_uv, b_uv, neg_normal);
-            add_triangle(a, c, d, a_uv, c_uv, d_uv, normal);
-            add_triangle(a, d, c, a_uv, d_uv, c_uv, neg_normal);
+            let [a_blend, b_blend, c_blend, d_blend] = quad.blend;
+
+            buffers.add_triangle(
+                [a, b, c],
+                [a_uv, b_uv, c_uv],
+                normal,
+                [front(0), front(1), front(2)],
+                [a_blend, b_blend, c_blend],
+            );
+            buffers.add_triangle(
+                [a, c, b],
+                [a_uv, c_uv, b_uv],
+                neg_normal,
+                [back(0), back(2), back(1)],
+                [a_blend, c_blend, b_blend],
+            );
+            buffers.add_triangle(
+                [a, c, d],
+                [a_uv, c_uv, d_uv],
+                normal,
+                [front(0), front(2), front(3)],
+                [a_blend, c_blend, d_blend],
+            );
+            buffers.add_triangle(
+                [a, d, c],
+                [a_uv, d_uv, c_uv],
+                neg_normal,
+                [back(0), back(3), back(2)],
+                [a_blend, d_blend, c_blend],
+            );
         }
 
         for triag in self.triags.iter() {
@@ -209,16 +341,171 @@ impl ChunkIR {
             let a_uv = [0.0, 0.0];
             let b_uv = [0.0, 1.0];
             let c_uv = [1.0, 1.0];
+            let front = |i: usize| vertex_color(triag.ao[i].front, triag.light[i].front);
+            let back = |i: usize| vertex_color(triag.ao[i].back, triag.light[i].back);
+            let [a_blend, b_blend, c_blend] = triag.blend;
             // add two triangles to achieve double sided look
-            add_triangle(a, b, c, a_uv, b_uv, c_uv, normal);
-            add_triangle(a, c, b, a_uv, c_uv, b_uv, neg_normal);
+            buffers.add_triangle(
+                [a, b, c],
+                [a_uv, b_uv, c_uv],
+                normal,
+                [front(0), front(1), front(2)],
+                [a_blend, b_blend, c_blend],
+            );
+            buffers.add_triangle(
+                [a, c, b],
+                [a_uv, c_uv, b_uv],
+                neg_normal,
+                [back(0), back(2), back(1)],
+                [a_blend, c_blend, b_blend],
+            );
         }
 
-        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, verts);
-        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
-        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
-        mesh.set_indices(Some(Indices::U32(indices)));
+        for quad in self.dual_quads.iter() {
+            let uvs = [[0.0, 0.0], [0.0, 1.0], [1.0, 1.0], [1.0, 0.0]];
+            let front = |i: usize| vertex_color(quad.ao[i].front, quad.light[i].front);
+            let back = |i: usize| vertex_color(quad.ao[i].back, quad.light[i].back);
+            // dual quads are not planar, so both halves get their own normal:
+            for [i, j, k] in quad.triangles() {
+                let [a, b, c] = [i, j, k].map(|i| quad.corners[i]);
+                let normal = calculate_triag_normal(a, b, c);
+                let [a, b, c]: [[f32; 3]; 3] = [a.into(), b.into(), c.into()];
+                buffers.add_triangle(
+                    [a, b, c],
+                    [uvs[i], uvs[j], uvs[k]],
+                    normal.into(),
+                    [front(i), front(j), front(k)],
+                    [i, j, k].map(|i| quad.blend[i]),
+                );
+                buffers.add_triangle(
+                    [a, c, b],
+                    [uvs[i], uvs[k], uvs[j]],
+                    (-normal).into(),
+                    [back(i), back(k), back(j)],
+                    [i, k, j].map(|i| quad.blend[i]),
+                );
+            }
+        }
+
+        buffers
+    }
+
+    /// Number of triangles of all faces, each seen from one side.
+    pub fn triangle_count(&self) -> usize {
+        self.quads.len() * 2
+            + self.triags.len()
+            + self
+                .dual_quads
+                .iter()
+                .map(|quad| quad.triangles().count())
+                .sum::<usize>()
+    }
+
+    /// Appends all faces of `other` to this ChunkIR.
+    pub fn extend(&mut self, other: &ChunkIR) {
+        self.quads.extend(other.quads.iter().cloned());
+        self.triags.extend(other.triags.iter().cloned());
+        self.edges.extend(other.edges.iter().cloned());
+        self.dual_quads.extend(other.dual_quads.iter().cloned());
+    }
+
+    /// Component-wise minimum and maximum of all face and edge corners, None if the ChunkIR is empty.
+    /// Vertices of dual quads are rounded outwards.
+    pub fn bounds(&self) -> Option<(Pos, Pos)> {
+        let corners = self
+            .quads
+            .iter()
+            .flat_map(|q| [q.a, q.b, q.c, q.d])
+            .chain(self.triags.iter().flat_map(|t| [t.a, t.b, t.c]))
+            .chain(self.edges.iter().flat_map(|e| [e.a, e.b]))
+            .chain(
+                self.dual_quads
+                    .iter()
+                    .flat_map(|q| q.corners)
+                    .flat_map(|v| [v.floor(), v.ceil()])
+                    .map(|v| Pos::new(v.x as i8, v.y as i8, v.z as i8)),
+            );
+        corners.fold(None, |bounds, p| match bounds {
+            None => Some((p, p)),
+            Some((min, max)) => Some((
+                Pos::new(min.x.min(p.x), min.y.min(p.y), min.z.min(p.z)),
+                Pos::new(max.x.max(p.x), max.y.max(p.y), max.z.max(p.z)),
+            )),
+        })
+    }
+}
+
+/// Ambient occlusion, sky light and block light go into the r, g and b channel of the vertex colour.
+fn vertex_color(ao: f32, light: [f32; 2]) -> [f32; 4] {
+    [ao, light[0], light[1], 1.0]
+}
+
+/// Indices of the matters a triangle blends, the same for all of its vertices, see `blend::triangle_blend`.
+pub const ATTRIBUTE_MATTERS: MeshVertexAttribute =
+    MeshVertexAttribute::new("Vertex_Matters", 0x5c0d_35a4, VertexFormat::Uint32);
+
+/// Weights of the matters of `ATTRIBUTE_MATTERS` at the vertex.
+pub const ATTRIBUTE_MATTER_WEIGHTS: MeshVertexAttribute =
+    MeshVertexAttribute::new("Vertex_MatterWeights", 0x5c0d_35a5, VertexFormat::Float32x4);
+
+/// Vertex data of a mesh before it is uploaded into a bevy `Mesh`.
+/// Buffers of different parts of a chunk can be concatenated with `extend`.
+#[derive(Debug, Clone, Default)]
+pub struct MeshBuffers {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    /// 0..1 across every face, `VoxelAoMaterial` projects its textures in world space instead.
+    pub uvs: Vec<[f32; 2]>,
+    /// ambient occlusion, sky light and block light, see `vertex_color`.
+    pub colors: Vec<[f32; 4]>,
+    /// see `ATTRIBUTE_MATTERS`.
+    pub matters: Vec<u32>,
+    /// see `ATTRIBUTE_MATTER_WEIGHTS`.
+    pub matter_weights: Vec<[f32; BLEND_SLOTS]>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshBuffers {
+    pub fn add_triangle(
+        &mut self,
+        verts: [[f32; 3]; 3],
+        uvs: [[f32; 2]; 3],
+        normal: [f32; 3],
+        colors: [[f32; 4]; 3],
+        blend: [MatterWeights; 3],
+    ) {
+        let i = self.positions.len() as u32;
+        let (matters, matter_weights) = triangle_blend(blend);
+        self.positions.extend(verts);
+        self.normals.extend([normal; 3]);
+        self.uvs.extend(uvs);
+        self.colors.extend(colors);
+        self.matters.extend([matters; 3]);
+        self.matter_weights.extend(matter_weights);
+        self.indices.extend([i, i + 1, i + 2]);
+    }
+
+    pub fn extend(&mut self, other: &MeshBuffers) {
+        let offset = self.positions.len() as u32;
+        self.positions.extend_from_slice(&other.positions);
+        self.normals.extend_from_slice(&other.normals);
+        self.uvs.extend_from_slice(&other.uvs);
+        self.colors.extend_from_slice(&other.colors);
+        self.matters.extend_from_slice(&other.matters);
+        self.matter_weights.extend_from_slice(&other.matter_weights);
+        self.indices
+            .extend(other.indices.iter().map(|i| i + offset));
+    }
 
+    pub fn into_mesh(self) -> Mesh {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, self.positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, self.uvs);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, self.normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, self.colors);
+        mesh.insert_attribute(ATTRIBUTE_MATTERS, self.matters);
+        mesh.insert_attribute(ATTRIBUTE_MATTER_WEIGHTS, self.matter_weights);
+        mesh.set_indices(Some(Indices::U32(self.indices)));
         mesh
     }
 }
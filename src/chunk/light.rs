@@ -0,0 +1,679 @@
+//! Sky light and block light of the corners of a ChunkWorld.
+//!
+//! Sky light shines straight down from the top of every 256 voxels high column without losing
+//! strength, block light is emitted by voxels with a light emitting `Matter`. From there both
+//! flood fill through all corners that are not Strong and lose one level per step.
+
+use std::collections::VecDeque;
+
+use bevy::{prelude::Vec3, utils::HashMap};
+
+use super::{
+    ir::calculate_triag_normal, pos::Pos, section::SECTIONS_PER_COLUMN, voxel::Voxel, Chunk,
+    ChunkPos, ChunkWorld, CHUNK_SIZE,
+};
+
+/// Light level of the open sky and the highest level of both channels.
+pub const MAX_LIGHT: u8 = 15;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Light {
+    pub sky: u8,
+    pub block: u8,
+}
+
+impl Light {
+    /// Light of a corner under the open sky.
+    pub const SKY: Light = Light {
+        sky: MAX_LIGHT,
+        block: 0,
+    };
+}
+
+/// Global voxel coordinates, spanning all sections of a ChunkWorld.
+pub type WorldPos = (isize, isize, isize);
+
+pub fn to_world_pos(chunk_pos: ChunkPos, pos: Pos) -> WorldPos {
+    let size = CHUNK_SIZE as isize;
+    (
+        chunk_pos.0 * size + pos.x as isize,
+        chunk_pos.1 * size + pos.y as isize,
+        chunk_pos.2 * size + pos.z as isize,
+    )
+}
+
+/// The section a world position lies in and its position inside of that section.
+pub fn from_world_pos(world_pos: WorldPos) -> (ChunkPos, Pos) {
+    let size = CHUNK_SIZE as isize;
+    let chunk_pos = (
+        world_pos.0.div_euclid(size),
+        world_pos.1.div_euclid(size),
+        world_pos.2.div_euclid(size),
+    );
+    let pos = Pos::new(
+        world_pos.0.rem_euclid(size) as i8,
+        world_pos.1.rem_euclid(size) as i8,
+        world_pos.2.rem_euclid(size) as i8,
+    );
+    (chunk_pos, pos)
+}
+
+const DOWN: WorldPos = (0, -1, 0);
+
+const NEIGHBORS: [WorldPos; 6] = [
+    (-1, 0, 0),
+    (1, 0, 0),
+    DOWN,
+    (0, 1, 0),
+    (0, 0, -1),
+    (0, 0, 1),
+];
+
+fn offset(world_pos: WorldPos, d: WorldPos) -> WorldPos {
+    (world_pos.0 + d.0, world_pos.1 + d.1, world_pos.2 + d.2)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Channel {
+    Sky,
+    Block,
+}
+
+impl Channel {
+    fn get(self, light: Light) -> u8 {
+        match self {
+            Channel::Sky => light.sky,
+            Channel::Block => light.block,
+        }
+    }
+
+    fn set(self, light: &mut Light, level: u8) {
+        match self {
+            Channel::Sky => light.sky = level,
+            Channel::Block => light.block = level,
+        }
+    }
+}
+
+/// Smoothed light of one vertex of a face, normalized to 0.0..=1.0.
+/// Like `ao::VertexAo`, both sides of the double sided face get their own value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VertexLight {
+    /// sky and block light on the side the face normal points to.
+    pub front: [f32; 2],
+    pub back: [f32; 2],
+}
+
+impl Voxel {
+    /// Block light emitted by the voxel, the brightest emission of its matters.
+    pub fn emission(&self) -> u8 {
+        [self.x_side, self.y_side, self.z_side, self.inner]
+            .into_iter()
+            .flatten()
+            .map(|matter| matter.emission())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+impl Chunk {
+    /// Light of a corner inside the chunk, or in the shell around it copied by `ChunkWorld::sync_border`.
+    pub fn get_light(&self, pos: Pos) -> Light {
+        if Chunk::in_bounds(pos) {
+            self.light.get(&pos).copied().unwrap_or(self.light_fill)
+        } else {
+            self.border_light.get(&pos).copied().unwrap_or_default()
+        }
+    }
+
+    /// Sets the light of a corner inside the chunk. Marks the voxel dirty if the light changed,
+    /// so the mesh picks it up.
+    pub fn set_light(&mut self, pos: Pos, light: Light) {
+        let old = if light == self.light_fill {
+            self.light.remove(&pos)
+        } else {
+            self.light.insert(pos, light)
+        };
+        if old.unwrap_or(self.light_fill) != light {
+            self.dirty.insert(pos);
+        }
+    }
+
+    /// Makes the most common light the `light_fill`, so only the exceptions are stored.
+    pub fn compact_light(&mut self) {
+        let size = CHUNK_SIZE as i8;
+        let volume = (CHUNK_SIZE as usize).pow(3);
+        let mut counts: HashMap<Light, usize> = HashMap::new();
+        for light in self.light.values() {
+            *counts.entry(*light).or_default() += 1;
+        }
+        *counts.entry(self.light_fill).or_default() += volume - self.light.len();
+        let fill = counts
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+            .map(|(light, _)| light)
+            .expect("light_fill is always counted");
+        if fill == self.light_fill {
+            return;
+        }
+
+        let mut light = HashMap::new();
+        for x in 0..size {
+            for y in 0..size {
+                for z in 0..size {
+                    let pos = Pos::new(x, y, z);
+                    let value = self.get_light(pos);
+                    if value != fill {
+                        light.insert(pos, value);
+                    }
+                }
+            }
+        }
+        self.light = light;
+        self.light_fill = fill;
+    }
+
+    /// Average light of the corners around the vertex that lie in front of the plane through it
+    /// (see `vertex_ao`). Strong corners carry no light and are left out.
+    pub fn vertex_light(&self, vertex: Pos, normal: Vec3) -> [f32; 2] {
+        let mut sum = [0u32; 2];
+        let mut count = 0;
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let offset = Pos::new(dx, dy, dz);
+                    if normal.dot(offset.into()) <= 0.0 {
+                        continue;
+                    }
+                    let pos = vertex + offset;
+                    if self.get_voxel_corner(&pos).strong() {
+                        continue;
+                    }
+                    let light = self.get_light(pos);
+                    sum[0] += light.sky as u32;
+                    sum[1] += light.block as u32;
+                    count += 1;
+                }
+            }
+        }
+        if count == 0 {
+            return [0.0; 2];
+        }
+        sum.map(|s| s as f32 / (count * MAX_LIGHT as u32) as f32)
+    }
+
+    /// Light of all corners of a planar face, for both of its sides.
+    pub fn face_light<const N: usize>(&self, corners: [Pos; N]) -> [VertexLight; N] {
+        let normal =
+            calculate_triag_normal(corners[0].into(), corners[1].into(), corners[2].into());
+        corners.map(|vertex| VertexLight {
+            front: self.vertex_light(vertex, normal),
+            back: self.vertex_light(vertex, -normal),
+        })
+    }
+
+    /// Lights a chunk that is not part of a ChunkWorld, as if it was the topmost section
+    /// of its column under the open sky.
+    pub fn compute_light(&mut self) {
+        self.in_standalone_world(|world| world.light_column(0, 0));
+    }
+
+    /// Updates the light of a chunk lit by `compute_light` after it was modified.
+    pub fn update_light(&mut self) -> bool {
+        self.in_standalone_world(|world| world.update_light())
+    }
+
+    fn in_standalone_world<R>(&mut self, f: impl FnOnce(&mut ChunkWorld) -> R) -> R {
+        let chunk_pos = (0, SECTIONS_PER_COLUMN - 1, 0);
+        let mut world = ChunkWorld::default();
+        world.chunks.insert(chunk_pos, std::mem::take(self));
+        let result = f(&mut world);
+        *self = world.chunks.remove(&chunk_pos).expect("inserted above");
+        result
+    }
+}
+
+impl ChunkWorld {
+    /// Light at a world position. Everything above the columns is lit by the open sky,
+    /// sections that are not loaded are dark.
+    pub fn light_at(&self, world_pos: WorldPos) -> Light {
+        let (chunk_pos, pos) = from_world_pos(world_pos);
+        match self.chunks.get(&chunk_pos) {
+            Some(chunk) => chunk.get_light(pos),
+            None if chunk_pos.1 >= SECTIONS_PER_COLUMN => Light::SKY,
+            None => Light::default(),
+        }
+    }
+
+    fn set_light_at(&mut self, world_pos: WorldPos, light: Light) {
+        let (chunk_pos, pos) = from_world_pos(world_pos);
+        if let Some(chunk) = self.chunks.get_mut(&chunk_pos) {
+            chunk.set_light(pos, light);
+        }
+    }
+
+    /// Light spreads through all corners of loaded sections that are not Strong.
+    fn transmits(&self, world_pos: WorldPos) -> bool {
+        let (chunk_pos, pos) = from_world_pos(world_pos);
+        self.chunks
+            .get(&chunk_pos)
+            .is_some_and(|chunk| !chunk.get_voxel_corner(&pos).strong())
+    }
+
+    fn emission_at(&self, world_pos: WorldPos) -> u8 {
+        let (chunk_pos, pos) = from_world_pos(world_pos);
+        self.chunks
+            .get(&chunk_pos)
+            .map(|chunk| chunk.get_voxel(pos).emission())
+            .unwrap_or(0)
+    }
+
+    /// Computes the light of the column at x/z from scratch, including the light that flows in from
+    /// and out into the neighbouring columns. Meant to be called when a column is loaded:
+    /// the sections of the column itself are not marked dirty, they are meshed for the first time anyway.
+    pub fn light_column(&mut self, x: isize, z: isize) {
+        let size = CHUNK_SIZE as isize;
+        let top = SECTIONS_PER_COLUMN * size;
+        for y in 0..SECTIONS_PER_COLUMN {
+            if let Some(chunk) = self.chunks.get_mut(&(x, y, z)) {
+                chunk.light.clear();
+                chunk.light_fill = Light::default();
+                chunk.light_dirty.clear();
+            }
+        }
+
+        // sky light straight down until the first Strong corner.
+        // heights holds the lowest y that is lit by the open sky, per x/z in the column.
+        let mut heights = vec![top; (size * size) as usize];
+        for sy in (0..SECTIONS_PER_COLUMN).rev() {
+            let Some(chunk) = self.chunks.get_mut(&(x, sy, z)) else {
+                break;
+            };
+            let section_top = (sy + 1) * size;
+            if chunk.is_empty() && heights.iter().all(|h| *h == section_top) {
+                chunk.light_fill = Light::SKY;
+                heights.fill(sy * size);
+                continue;
+            }
+            for lx in 0..size {
+                for lz in 0..size {
+                    let height = &mut heights[(lx * size + lz) as usize];
+                    if *height != section_top {
+                        continue;
+                    }
+                    for ly in (0..size).rev() {
+                        let pos = Pos::new(lx as i8, ly as i8, lz as i8);
+                        if chunk.get_voxel_corner(&pos).strong() {
+                            break;
+                        }
+                        chunk.light.insert(pos, Light::SKY);
+                        *height = sy * size + ly;
+                    }
+                }
+            }
+        }
+
+        let mut queue: VecDeque<WorldPos> = VecDeque::new();
+        let world_x = x * size;
+        let world_z = z * size;
+        for lx in 0..size {
+            for lz in 0..size {
+                let height = heights[(lx * size + lz) as usize];
+                for (dx, dz) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                    let (nx, nz) = (lx + dx, lz + dz);
+                    if (0..size).contains(&nx) && (0..size).contains(&nz) {
+                        // sky lit corners next to shadowed ones spread sideways:
+                        let neighbor_height = heights[(nx * size + nz) as usize];
+                        for y in height..neighbor_height {
+                            queue.push_back((world_x + lx, y, world_z + lz));
+                        }
+                        continue;
+                    }
+                    // along the border of the column light flows in both directions:
+                    for y in 0..top {
+                        let inside = (world_x + lx, y, world_z + lz);
+                        let outside = (world_x + nx, y, world_z + nz);
+                        let outside_light = self.light_at(outside);
+                        if outside_light.sky > 1 || outside_light.block > 1 {
+                            queue.push_back(outside);
+                        }
+                        if y >= height && outside_light.sky < MAX_LIGHT - 1 {
+                            queue.push_back(inside);
+                        }
+                    }
+                }
+            }
+        }
+
+        // block light of all emitters:
+        for y in 0..SECTIONS_PER_COLUMN {
+            let Some(chunk) = self.chunks.get_mut(&(x, y, z)) else {
+                continue;
+            };
+            let emitters: Vec<(Pos, u8)> = chunk
+                .voxels
+                .iter()
+                .map(|(pos, voxel)| (*pos, voxel.emission()))
+                .filter(|(_, emission)| *emission > 0)
+                .collect();
+            for (pos, emission) in emitters {
+                let mut light = chunk.get_light(pos);
+                light.block = emission;
+                chunk.light.insert(pos, light);
+                queue.push_back(to_world_pos((x, y, z), pos));
+            }
+        }
+
+        self.spread_light(queue);
+
+        for y in 0..SECTIONS_PER_COLUMN {
+            if let Some(chunk) = self.chunks.get_mut(&(x, y, z)) {
+                chunk.compact_light();
+            }
+        }
+    }
+
+    /// Updates the light around all voxels modified since the last call, see `Chunk::light_dirty`.
+    /// Light is removed around them and flooded back in from the surrounding light and emitters,
+    /// crossing section borders. Returns false if nothing was modified.
+    pub fn update_light(&mut self) -> bool {
+        let mut changed: Vec<WorldPos> = vec![];
+        for (chunk_pos, chunk) in self.chunks.iter_mut() {
+            changed.extend(
+                chunk
+                    .light_dirty
+                    .drain()
+                    .map(|pos| to_world_pos(*chunk_pos, pos)),
+            );
+        }
+        if changed.is_empty() {
+            return false;
+        }
+
+        let mut relight: VecDeque<WorldPos> = VecDeque::new();
+        for channel in [Channel::Sky, Channel::Block] {
+            let mut removal: VecDeque<(WorldPos, u8)> = VecDeque::new();
+            for world_pos in changed.iter() {
+                let mut light = self.light_at(*world_pos);
+                let level = channel.get(light);
+                if level == 0 {
+                    continue;
+                }
+                channel.set(&mut light, 0);
+                self.set_light_at(*world_pos, light);
+                removal.push_back((*world_pos, level));
+            }
+            self.remove_light(channel, removal, &mut relight);
+        }
+
+        for world_pos in changed {
+            // light of the surroundings can flow into modified corners that are no longer Strong:
+            for d in NEIGHBORS {
+                relight.push_back(offset(world_pos, d));
+            }
+            let emission = self.emission_at(world_pos);
+            let mut light = self.light_at(world_pos);
+            if emission > light.block {
+                light.block = emission;
+                self.set_light_at(world_pos, light);
+                relight.push_back(world_pos);
+            }
+        }
+        self.spread_light(relight);
+        true
+    }
+
+    /// Darkens everything that got its light from the queued corners, which had the given level before.
+    /// Brighter corners at the edge of the darkened area are queued in `relight`, to flood it again.
+    fn remove_light(
+        &mut self,
+        channel: Channel,
+        mut queue: VecDeque<(WorldPos, u8)>,
+        relight: &mut VecDeque<WorldPos>,
+    ) {
+        while let Some((world_pos, level)) = queue.pop_front() {
+            for d in NEIGHBORS {
+                let neighbor = offset(world_pos, d);
+                if !self.chunks.contains_key(&from_world_pos(neighbor).0) {
+                    continue;
+                }
+                let mut light = self.light_at(neighbor);
+                let neighbor_level = channel.get(light);
+                if neighbor_level == 0 {
+                    continue;
+                }
+                let lit_from_here = neighbor_level < level
+                    || (channel == Channel::Sky && d == DOWN && level == MAX_LIGHT);
+                if !lit_from_here {
+                    relight.push_back(neighbor);
+                    continue;
+                }
+                // emitters keep their own light
+                let emission = match channel {
+                    Channel::Sky => 0,
+                    Channel::Block => self.emission_at(neighbor),
+                };
+                channel.set(&mut light, emission);
+                self.set_light_at(neighbor, light);
+                queue.push_back((neighbor, neighbor_level));
+                if emission > 0 {
+                    relight.push_back(neighbor);
+                }
+            }
+        }
+    }
+
+    /// Breadth first flood fill from the queued corners into their neighbours.
+    fn spread_light(&mut self, mut queue: VecDeque<WorldPos>) {
+        while let Some(world_pos) = queue.pop_front() {
+            let light = self.light_at(world_pos);
+            if light == Light::default() {
+                continue;
+            }
+            for d in NEIGHBORS {
+                let neighbor = offset(world_pos, d);
+                if !self.transmits(neighbor) {
+                    continue;
+                }
+                let old = self.light_at(neighbor);
+                let sky = if d == DOWN && light.sky == MAX_LIGHT {
+                    MAX_LIGHT
+                } else {
+                    light.sky.saturating_sub(1)
+                };
+                let new = Light {
+                    sky: old.sky.max(sky),
+                    block: old.block.max(light.block.saturating_sub(1)),
+                };
+                if new != old {
+                    self.set_light_at(neighbor, new);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{
+        examples::flat_ground,
+        voxel::{Corner, Matter},
+    };
+
+    const GROUND: isize = 8;
+
+    fn lamp() -> Voxel {
+        Voxel {
+            inner: Some(Matter::Lamp),
+            ..Voxel::new(None, None, None, Corner::Air)
+        }
+    }
+
+    /// Flat ground in all sections of the columns, lit one column after the other.
+    fn lit_world(columns: &[(isize, isize)]) -> ChunkWorld {
+        let mut world = ChunkWorld::default();
+        for (x, z) in columns {
+            for y in 0..SECTIONS_PER_COLUMN {
+                world
+                    .chunks
+                    .insert((*x, y, *z), flat_ground((*x, y, *z), GROUND));
+            }
+        }
+        for (x, z) in columns {
+            world.light_column(*x, *z);
+        }
+        world
+    }
+
+    fn set_voxel(world: &mut ChunkWorld, world_pos: WorldPos, voxel: Voxel) {
+        let (chunk_pos, pos) = from_world_pos(world_pos);
+        world
+            .chunks
+            .get_mut(&chunk_pos)
+            .unwrap()
+            .set_voxel(pos, voxel);
+    }
+
+    /// The same voxels lit from scratch.
+    fn relit(world: &ChunkWorld, columns: &[(isize, isize)]) -> ChunkWorld {
+        let mut fresh = ChunkWorld::default();
+        for (chunk_pos, chunk) in world.chunks.iter() {
+            fresh.chunks.insert(
+                *chunk_pos,
+                Chunk {
+                    voxels: chunk.voxels.clone(),
+                    edges: chunk.edges.clone(),
+                    fill: chunk.fill,
+                    ..Default::default()
+                },
+            );
+        }
+        for (x, z) in columns {
+            fresh.light_column(*x, *z);
+        }
+        fresh
+    }
+
+    fn assert_same_light(a: &ChunkWorld, b: &ChunkWorld, min: WorldPos, max: WorldPos) {
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                for z in min.2..=max.2 {
+                    assert_eq!(a.light_at((x, y, z)), b.light_at((x, y, z)), "{x} {y} {z}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sky_light_under_overhang() {
+        let mut world = lit_world(&[(0, 0)]);
+        // a roof at y = 20 over x = 0..=10, spanning the whole column along z:
+        for x in 0..=10 {
+            for z in 0..CHUNK_SIZE as isize {
+                set_voxel(
+                    &mut world,
+                    (x, 20, z),
+                    Voxel::new(None, None, None, Corner::Strong),
+                );
+            }
+        }
+        world.update_light();
+
+        assert_eq!(world.light_at((5, 21, 5)).sky, MAX_LIGHT);
+        assert_eq!(world.light_at((5, 20, 5)).sky, 0);
+        // straight down next to the roof, and one level less per step sideways under it:
+        assert_eq!(world.light_at((11, GROUND + 1, 5)).sky, MAX_LIGHT);
+        assert_eq!(world.light_at((10, 15, 5)).sky, MAX_LIGHT - 1);
+        assert_eq!(world.light_at((2, 15, 5)).sky, MAX_LIGHT - 9);
+        // the ground is dark:
+        assert_eq!(world.light_at((2, GROUND, 5)), Light::default());
+        assert_same_light(&world, &relit(&world, &[(0, 0)]), (0, 8, 0), (31, 31, 31));
+    }
+
+    #[test]
+    fn lamp_light_falls_off() {
+        let mut world = lit_world(&[(0, 0)]);
+        set_voxel(&mut world, (16, 20, 16), lamp());
+        world.update_light();
+
+        let emission = Matter::Lamp.emission();
+        assert_eq!(world.light_at((16, 20, 16)).block, emission);
+        for d in 1..=emission as isize {
+            let expected = emission - d as u8;
+            assert_eq!(world.light_at((16 + d, 20, 16)).block, expected);
+            assert_eq!(world.light_at((16, 20 + d, 16)).block, expected);
+            assert_eq!(
+                world.light_at((16 - d / 2, 20, 16 + d - d / 2)).block,
+                expected
+            );
+        }
+        // the ground blocks it:
+        assert_eq!(world.light_at((16, GROUND, 16)).block, 0);
+    }
+
+    #[test]
+    fn light_crosses_section_borders_incrementally() {
+        let columns = [(0, 0), (1, 0)];
+        let mut world = lit_world(&columns);
+        let emission = Matter::Lamp.emission();
+        let (min, max) = ((16, GROUND, 0), (47, 40, 31));
+
+        // a lamp next to the border of the column and of the section above:
+        let lamp_pos = (31, 31, 16);
+        set_voxel(&mut world, lamp_pos, lamp());
+        assert!(world.update_light());
+        assert_eq!(world.light_at((33, 31, 16)).block, emission - 2);
+        assert_eq!(world.light_at((31, 33, 16)).block, emission - 2);
+        assert_same_light(&world, &relit(&world, &columns), min, max);
+
+        // a Strong corner on the other side of the border, the light flows around it:
+        let wall = Voxel::new(None, None, None, Corner::Strong);
+        set_voxel(&mut world, (32, 31, 16), wall);
+        world.update_light();
+        assert_eq!(world.light_at((33, 31, 16)).block, emission - 4);
+        assert_same_light(&world, &relit(&world, &columns), min, max);
+
+        // removing it lets the light back in:
+        set_voxel(&mut world, (32, 31, 16), Voxel::default());
+        world.update_light();
+        assert_eq!(world.light_at((33, 31, 16)).block, emission - 2);
+        assert_same_light(&world, &relit(&world, &columns), min, max);
+
+        // removing the lamp darkens both sides:
+        set_voxel(&mut world, lamp_pos, Voxel::default());
+        world.update_light();
+        assert_eq!(world.light_at((33, 31, 16)).block, 0);
+        assert_eq!(world.light_at((31, 33, 16)).block, 0);
+        assert_same_light(&world, &relit(&world, &columns), min, max);
+        assert!(!world.update_light());
+    }
+
+    #[test]
+    fn empty_sections_are_filled_with_sky_light() {
+        let mut world = ChunkWorld::default();
+        for y in 0..SECTIONS_PER_COLUMN {
+            world.chunks.insert((0, y, 0), Chunk::default());
+        }
+        world.light_column(0, 0);
+        for y in 0..SECTIONS_PER_COLUMN {
+            let chunk = &world.chunks[&(0, y, 0)];
+            assert_eq!(chunk.light_fill, Light::SKY);
+            assert!(chunk.light.is_empty());
+        }
+
+        // above the ground everything is open sky, the ground is dark:
+        let world = lit_world(&[(0, 0)]);
+        let size = CHUNK_SIZE as isize;
+        for y in 0..SECTIONS_PER_COLUMN {
+            let chunk = &world.chunks[&(0, y, 0)];
+            if y * size > GROUND {
+                assert_eq!(chunk.light_fill, Light::SKY);
+                assert!(chunk.light.is_empty());
+            }
+        }
+        assert_eq!(world.light_at((5, GROUND + 1, 5)), Light::SKY);
+        assert_eq!(world.light_at((5, GROUND - 3, 5)), Light::default());
+    }
+}
@@ -0,0 +1,265 @@
+use bevy::utils::{HashMap, HashSet};
+
+use super::{
+    ao::VertexAo,
+    blend::MatterWeights,
+    ir::{ChunkIR, QuadIR},
+    light::{Light, VertexLight},
+    pos::{Axis, Pos},
+    voxel::{Corner, Matter, Voxel},
+    Chunk, CHUNK_SIZE,
+};
+
+/// Highest level of detail index. Level `n` is downsampled by `2^n`.
+pub const MAX_LOD: u8 = 3;
+
+/// How many voxels of the full resolution chunk one voxel of the level covers along each axis.
+pub fn lod_factor(lod: u8) -> i8 {
+    1 << lod.min(MAX_LOD)
+}
+
+/// Downsamples the chunk by `factor`, the result has a size of `CHUNK_SIZE / factor`.
+///
+/// Every coarse voxel covers the block of `factor³` voxels centered around its scaled position.
+/// Corner strength and side matter are chosen by majority vote over that block.
+/// A side is a plane, so it is voted on by the `factor²` lines through the block along its axis:
+/// the coarse side is set if at least half of them cross a side on that axis. Features thinner than
+/// the block, like a single pole, do not grow to the size of a whole coarse face.
+/// Ties of both votes are won by the solid option, so flat surfaces do not vanish.
+pub fn downsample(chunk: &Chunk, factor: i8) -> Chunk {
+    let size = CHUNK_SIZE as i8;
+    let n = size / factor;
+    let half = factor / 2;
+    let mut lod = Chunk {
+        fill: chunk.fill,
+        light_fill: chunk.light_fill,
+        ..Default::default()
+    };
+
+    for cx in 0..=n {
+        for cy in 0..=n {
+            for cz in 0..=n {
+                let coarse = Pos::new(cx, cy, cz);
+                let mut corner_votes = [0usize; 3];
+                let mut side_votes: [HashMap<Matter, usize>; 3] = Default::default();
+                // the lines along each axis that cross a side on that axis, by their other two coordinates:
+                let mut side_lines: [HashSet<(i8, i8)>; 3] = Default::default();
+
+                // block of fine voxels, clamped to the chunk and its +x/+y/+z border layer:
+                let range = |c: i8| {
+                    let start = c as i16 * factor as i16 - half as i16;
+                    let end = (start + factor as i16).min(size as i16 + 1);
+                    start.max(0) as i8..end as i8
+                };
+                for x in range(cx) {
+                    for y in range(cy) {
+                        for z in range(cz) {
+                            let pos = Pos::new(x, y, z);
+                            let corner = chunk.get_voxel_corner(&pos);
+                            corner_votes[corner as usize] += 1;
+                            if !Chunk::in_bounds(pos) {
+                                continue;
+                            }
+                            let voxel = chunk.get_voxel(pos);
+                            let lines = [(y, z), (x, z), (x, y)];
+                            for (i, axis) in [Axis::X, Axis::Y, Axis::Z].into_iter().enumerate() {
+                                if let Some(matter) = voxel.side(axis) {
+                                    *side_votes[i].entry(matter).or_default() += 1;
+                                    side_lines[i].insert(lines[i]);
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // max_by_key returns the last maximum, so ties go to the stronger corner
+                let corner = [Corner::Air, Corner::Weak, Corner::Strong]
+                    .into_iter()
+                    .max_by_key(|c| corner_votes[*c as usize])
+                    .expect("not empty");
+
+                // light is not averaged, it is sampled at the scaled position:
+                let light = chunk.get_light(Pos::new(cx * factor, cy * factor, cz * factor));
+
+                if cx == n || cy == n || cz == n {
+                    if !corner.air() {
+                        lod.border.insert(coarse, corner);
+                    }
+                    if light != Light::default() {
+                        lod.border_light.insert(coarse, light);
+                    }
+                    continue;
+                }
+                if light != lod.light_fill {
+                    lod.light.insert(coarse, light);
+                }
+
+                let [lx, ly, lz] = [cx, cy, cz].map(|c| range(c).len());
+                let lines = [ly * lz, lx * lz, lx * ly];
+                let [x_side, y_side, z_side] = [0, 1, 2].map(|i| {
+                    if side_lines[i].len() * 2 < lines[i] {
+                        return None;
+                    }
+                    std::mem::take(&mut side_votes[i])
+                        .into_iter()
+                        .max_by_key(|(matter, count)| (*count, *matter as u8))
+                        .map(|(matter, _)| matter)
+                });
+                let voxel = Voxel {
+                    x_side,
+                    y_side,
+                    z_side,
+                    corner,
+                    ..Default::default()
+                };
+                if voxel != lod.get_voxel(coarse) {
+                    lod.voxels.insert(coarse, voxel);
+                }
+            }
+        }
+    }
+    lod
+}
+
+impl ChunkIR {
+    /// ChunkIR of the chunk at the given level of detail, in coordinates of that level.
+    /// The mesh has to be scaled by `lod_factor(lod)`.
+    pub fn construct_lod(chunk: &Chunk, lod: u8) -> Self {
+        if lod == 0 {
+            return ChunkIR::construct_from_chunk(chunk);
+        }
+        let factor = lod_factor(lod);
+        let mut ir = ChunkIR::construct_from_chunk(&downsample(chunk, factor));
+        ir.add_skirts(CHUNK_SIZE as i8 / factor);
+        ir
+    }
+
+    /// Hangs a one voxel deep quad below every face edge that lies on the vertical chunk borders
+    /// of a chunk with `size`. Neighbouring chunks with a different level of detail
+    /// do not line up exactly, the skirts cover the cracks between them.
+    pub fn add_skirts(&mut self, size: i8) {
+        let on_border = |a: Pos, b: Pos| {
+            let x_border = a.x == b.x && (a.x == 0 || a.x == size);
+            let z_border = a.z == b.z && (a.z == 0 || a.z == size);
+            let vertical = a.x == b.x && a.z == b.z;
+            (x_border || z_border) && !vertical
+        };
+
+        let mut skirts: Vec<QuadIR> = vec![];
+        let mut add_skirts = |corners: &[Pos],
+                              ao: &[VertexAo],
+                              light: &[VertexLight],
+                              blend: &[MatterWeights],
+                              matter: Matter| {
+            for i in 0..corners.len() {
+                let j = (i + 1) % corners.len();
+                let a = corners[i];
+                let b = corners[j];
+                if !on_border(a, b) {
+                    continue;
+                }
+                let down = Pos::new(0, 1, 0);
+                skirts.push(QuadIR {
+                    matter,
+                    a,
+                    b,
+                    c: b - down,
+                    d: a - down,
+                    ao: [ao[i], ao[j], ao[j], ao[i]],
+                    light: [light[i], light[j], light[j], light[i]],
+                    blend: [blend[i], blend[j], blend[j], blend[i]],
+                });
+            }
+        };
+
+        for quad in self.quads.iter() {
+            add_skirts(
+                &[quad.a, quad.b, quad.c, quad.d],
+                &quad.ao,
+                &quad.light,
+                &quad.blend,
+                quad.matter,
+            );
+        }
+        for triag in self.triags.iter() {
+            add_skirts(
+                &[triag.a, triag.b, triag.c],
+                &triag.ao,
+                &triag.light,
+                &triag.blend,
+                triag.matter,
+            );
+        }
+        self.quads.extend(skirts);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{examples::flat_ground, ChunkWorld};
+
+    /// Flat ground at the origin, with its border synced from the sections around it.
+    fn flat_section(ground: isize) -> Chunk {
+        let mut world = ChunkWorld::default();
+        for x in -1..=1 {
+            for y in -1..=1 {
+                for z in -1..=1 {
+                    world
+                        .chunks
+                        .insert((x, y, z), flat_ground((x, y, z), ground));
+                }
+            }
+        }
+        world.sync_border((0, 0, 0));
+        world.chunks.remove(&(0, 0, 0)).unwrap()
+    }
+
+    #[test]
+    fn flat_ground_stays_flat() {
+        let lod = downsample(&flat_section(8), 2);
+        for x in 0..16 {
+            for z in 0..16 {
+                for y in 0..16 {
+                    let voxel = lod.get_voxel(Pos::new(x, y, z));
+                    let corner = if y <= 4 { Corner::Strong } else { Corner::Air };
+                    assert_eq!(voxel.corner, corner, "{x} {y} {z}");
+                    let y_side = (y == 4).then_some(Matter::Dirt);
+                    assert_eq!(voxel.y_side, y_side, "{x} {y} {z}");
+                    assert_eq!((voxel.x_side, voxel.z_side), (None, None));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn thin_features_do_not_grow() {
+        let mut chunk = Chunk::default();
+        let pole = Voxel {
+            x_side: Some(Matter::Wood),
+            corner: Corner::Strong,
+            ..Default::default()
+        };
+        for x in 16..20 {
+            chunk.set_voxel(Pos::new(x, 20, 20), pole);
+        }
+        let lod = downsample(&chunk, 2);
+        assert!(lod.voxels.values().all(|v| v.x_side.is_none()));
+    }
+
+    #[test]
+    fn skirts_hang_below_border_edges() {
+        let ir = ChunkIR::construct_lod(&flat_section(8), 1);
+        let (skirts, faces): (Vec<_>, Vec<_>) = ir.quads.iter().partition(|q| q.a.y != q.d.y);
+        assert_eq!(faces.len(), 16 * 16);
+        // one skirt for each of the 16 face edges on the 4 vertical borders:
+        assert_eq!(skirts.len(), 4 * 16);
+        for skirt in skirts {
+            assert_eq!(skirt.a - skirt.d, Pos::new(0, 1, 0));
+            assert_eq!(skirt.b - skirt.c, Pos::new(0, 1, 0));
+            let on_x = skirt.a.x == skirt.b.x && [0, 16].contains(&skirt.a.x);
+            let on_z = skirt.a.z == skirt.b.z && [0, 16].contains(&skirt.a.z);
+            assert!(on_x || on_z, "{skirt:?}");
+        }
+    }
+}
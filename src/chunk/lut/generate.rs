@@ -0,0 +1,172 @@
+//! Derives the lookup tables of `lut` from the convex hulls of the Strong corners of a cube.
+
+use std::io::{self, Write};
+
+use crate::chunk::{pos::Axis, voxel::Corner};
+
+use super::{side_from_index, FacePlane, SIDE_CONFIGURATIONS};
+
+/// A face of the convex hull of some cube corners.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Facet {
+    /// points out of the hull, not normalized.
+    pub normal: [i32; 3],
+    /// indices into `Corners::to_array`, counterclockwise seen from outside of the hull.
+    pub corners: Vec<u8>,
+}
+
+impl Facet {
+    /// The cube plane the facet lies on, or Inner.
+    pub fn plane(&self) -> FacePlane {
+        let axes = [Axis::X, Axis::Y, Axis::Z];
+        match self.normal {
+            [x, 0, 0] if x != 0 => plane_of(axes[0], x),
+            [0, y, 0] if y != 0 => plane_of(axes[1], y),
+            [0, 0, z] if z != 0 => plane_of(axes[2], z),
+            _ => FacePlane::Inner,
+        }
+    }
+}
+
+/// Axis aligned facets of a hull inside of the unit cube always lie on one of its planes.
+fn plane_of(axis: Axis, direction: i32) -> FacePlane {
+    match direction < 0 {
+        true => FacePlane::Near(axis),
+        false => FacePlane::Far(axis),
+    }
+}
+
+/// The offset of a cube corner from `o`, see `Corners::OFFSETS`.
+pub fn offset(corner: u8) -> [i32; 3] {
+    [0, 1, 2].map(|bit| (corner >> bit & 1) as i32)
+}
+
+fn sub(a: [i32; 3], b: [i32; 3]) -> [i32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [i32; 3], b: [i32; 3]) -> i32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub(super) fn cross(a: [i32; 3], b: [i32; 3]) -> [i32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// The facets of the convex hull of the cube corners whose bits are set in `corners`.
+/// Empty if the corners do not span a volume.
+pub fn hull_facets(corners: u8) -> Vec<Facet> {
+    let points: Vec<u8> = (0..8).filter(|c| corners & (1 << c) != 0).collect();
+    let mut facets: Vec<Facet> = Vec::new();
+    // every facet is spanned by 3 of its corners, so trying all planes through 3 corners finds all of them:
+    for (i, a) in points.iter().enumerate() {
+        for (j, b) in points.iter().enumerate().skip(i + 1) {
+            for c in points.iter().skip(j + 1) {
+                let a = offset(*a);
+                let normal = cross(sub(offset(*b), a), sub(offset(*c), a));
+                if normal == [0; 3] {
+                    continue;
+                }
+                let side = |p: u8| dot(normal, sub(offset(p), a)).signum();
+                let above = points.iter().any(|p| side(*p) > 0);
+                let below = points.iter().any(|p| side(*p) < 0);
+                let normal = match (above, below) {
+                    (true, true) => continue,
+                    (false, false) => return Vec::new(),
+                    (false, true) => normal,
+                    (true, false) => normal.map(|n| -n),
+                };
+                let on_plane: Vec<u8> = points.iter().copied().filter(|p| side(*p) == 0).collect();
+                if facets.iter().any(|facet| {
+                    let mut corners = facet.corners.clone();
+                    corners.sort();
+                    corners == on_plane
+                }) {
+                    continue;
+                }
+                facets.push(Facet {
+                    normal,
+                    corners: counterclockwise(on_plane, normal),
+                });
+            }
+        }
+    }
+    facets
+}
+
+/// Sorts the corners of a convex polygon counterclockwise around `normal`.
+fn counterclockwise(mut corners: Vec<u8>, normal: [i32; 3]) -> Vec<u8> {
+    let n = corners.len() as i32;
+    let sum = corners
+        .iter()
+        .fold([0; 3], |sum, c| [0, 1, 2].map(|i| sum[i] + offset(*c)[i]));
+    // relative to the center, scaled by the number of corners to stay in integers:
+    let relative = |c: u8| sub(offset(c).map(|v| v * n), sum);
+    let e1 = relative(corners[0]);
+    let e2 = cross(normal, e1);
+    let angle = |c: &u8| {
+        let r = relative(*c);
+        (dot(r, e2) as f32).atan2(dot(r, e1) as f32)
+    };
+    corners.sort_by(|a, b| angle(a).total_cmp(&angle(b)));
+    corners
+}
+
+/// The face of a cube plane with the `square` of corners ordered like `lut::plane_corners`,
+/// as indices into the square, counterclockwise seen from the positive end of the axis.
+/// Empty if the plane has no face.
+pub fn side_face(square: [Corner; 4]) -> Vec<u8> {
+    if square.iter().any(|c| c.air()) {
+        return Vec::new();
+    }
+    let face: Vec<u8> = [0, 1, 3, 2]
+        .into_iter()
+        .filter(|s| square[*s as usize].strong())
+        .collect();
+    match face.len() < 3 {
+        true => Vec::new(),
+        false => face,
+    }
+}
+
+/// The faces inside of a cube without Air corners, for its `Corners::configuration`.
+pub fn inner_faces(configuration: u8) -> Vec<Vec<u8>> {
+    hull_facets(configuration)
+        .into_iter()
+        .filter(|facet| facet.plane() == FacePlane::Inner)
+        .map(|facet| facet.corners)
+        .collect()
+}
+
+/// Writes the source of the `lut::table` module.
+pub fn write_tables(out: &mut impl Write) -> io::Result<()> {
+    writeln!(
+        out,
+        "//! Generated by `cargo run --bin lut -- generate`, see `lut::generate`. Do not edit."
+    )?;
+    writeln!(out)?;
+    writeln!(out, "#[rustfmt::skip]")?;
+    writeln!(
+        out,
+        "pub(super) static SIDES: [&[u8]; {SIDE_CONFIGURATIONS}] = ["
+    )?;
+    for index in 0..SIDE_CONFIGURATIONS {
+        writeln!(out, "    &{:?},", side_face(side_from_index(index)))?;
+    }
+    writeln!(out, "];")?;
+    writeln!(out)?;
+    writeln!(out, "#[rustfmt::skip]")?;
+    writeln!(out, "pub(super) static INNER: [&[&[u8]]; 256] = [")?;
+    for configuration in 0..=255 {
+        let faces: Vec<String> = inner_faces(configuration)
+            .iter()
+            .map(|face| format!("&{face:?}"))
+            .collect();
+        writeln!(out, "    &[{}], // {configuration:08b}", faces.join(", "))?;
+    }
+    writeln!(out, "];")
+}
@@ -0,0 +1,244 @@
+//! Lookup tables for the faces of a voxel cube in all 3^8 configurations of its Air, Weak and Strong corners.
+//!
+//! The solid part of a cube is the convex hull of its Strong corners, Weak corners are cut off from it.
+//! A cube with an Air corner is open: it has no inner faces, and a face on one of the 6 cube planes
+//! only exists if none of the 4 corners of the plane is Air and at least 3 of them are Strong.
+//!
+//! Faces on the cube planes only depend on the 4 corners of their plane, and only cubes without Air corners
+//! have inner faces, so the table is split into `SIDES` for the 3^4 configurations of a plane
+//! and `INNER` for the 2^8 configurations of Weak and Strong corners, see `Corners::configuration`.
+//! `cube_faces` puts them together for any of the 3^8 configurations.
+//!
+//! Both tables are written by `generate::write_tables` and checked against the convex hulls of all
+//! 3^8 configurations by `verify::verify`, run `cargo run --bin lut -- generate` or `-- verify`.
+
+pub mod generate;
+mod table;
+pub mod verify;
+
+use super::{
+    pos::{Axis, Pos},
+    voxel::Corner,
+    Corners, Side,
+};
+
+/// Number of cube configurations, each of the 8 corners is Air, Weak or Strong.
+pub const CUBE_CONFIGURATIONS: usize = 6561;
+
+/// Number of configurations of the 4 corners of a cube plane, the entries of `SIDES`.
+pub const SIDE_CONFIGURATIONS: usize = 81;
+
+/// Where a face of a cube lies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FacePlane {
+    /// the plane of the axis through the corner `o`, its faces are the sides of the voxel owning the cube.
+    Near(Axis),
+    /// the opposite plane, its faces are the sides of the neighbouring voxel along the axis.
+    Far(Axis),
+    /// inside of the cube, like the diagonal face cutting off a Weak corner.
+    Inner,
+}
+
+/// A convex polygon of 3 or 4 corners of a cube, as indices into `Corners::to_array`,
+/// counterclockwise seen from the side it faces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Polygon {
+    len: u8,
+    corners: [u8; 4],
+}
+
+impl Polygon {
+    /// Panics if there are not 3 or 4 corners.
+    pub fn new(corners: &[u8]) -> Self {
+        assert!(
+            (3..=4).contains(&corners.len()),
+            "a polygon has 3 or 4 corners"
+        );
+        let mut polygon = Polygon {
+            len: corners.len() as u8,
+            corners: [0; 4],
+        };
+        polygon.corners[..corners.len()].copy_from_slice(corners);
+        polygon
+    }
+
+    pub fn corners(&self) -> &[u8] {
+        &self.corners[..self.len as usize]
+    }
+
+    /// The same polygon facing the other way.
+    pub fn reversed(&self) -> Polygon {
+        let mut corners = self.corners;
+        corners[..self.len as usize].reverse();
+        Polygon {
+            len: self.len,
+            corners,
+        }
+    }
+
+    /// The polygon at the positions of the cube corners.
+    /// Polygons only touch corners that are not Air, so only their positions have to be known.
+    pub fn to_side(&self, pos: &Corners<Option<Pos>>) -> Side {
+        let pos = pos.to_array();
+        let pos = |corner: u8| pos[corner as usize].expect("polygons do not touch Air corners");
+        match *self.corners() {
+            [a, b, c] => Side::Triag(pos(a), pos(b), pos(c)),
+            [a, b, c, d] => Side::Quad(pos(a), pos(b), pos(c), pos(d)),
+            _ => unreachable!("a polygon has 3 or 4 corners"),
+        }
+    }
+}
+
+/// A face of a cube configuration, facing out of the solid part of the cube.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CubeFace {
+    pub plane: FacePlane,
+    pub polygon: Polygon,
+}
+
+/// The index of a cube configuration in `0..CUBE_CONFIGURATIONS`:
+/// corner `i` of `Corners::to_array` is the `i`-th ternary digit, with Air 0, Weak 1 and Strong 2.
+pub fn cube_index(cor: &Corners<Corner>) -> usize {
+    cor.to_array()
+        .iter()
+        .rev()
+        .fold(0, |index, corner| index * 3 + digit(*corner))
+}
+
+/// The inverse of `cube_index`, panics if the index is out of range.
+pub fn cube_from_index(index: usize) -> Corners<Corner> {
+    assert!(index < CUBE_CONFIGURATIONS, "no cube configuration {index}");
+    let mut rest = index;
+    Corners::from_array([(); 8].map(|_| {
+        let corner = [Corner::Air, Corner::Weak, Corner::Strong][rest % 3];
+        rest /= 3;
+        corner
+    }))
+}
+
+fn digit(corner: Corner) -> usize {
+    match corner {
+        Corner::Air => 0,
+        Corner::Weak => 1,
+        Corner::Strong => 2,
+    }
+}
+
+fn axis_index(axis: Axis) -> usize {
+    match axis {
+        Axis::X => 0,
+        Axis::Y => 1,
+        Axis::Z => 2,
+    }
+}
+
+/// The cube corners of the plane orthogonal to `axis`, ordered by `u + 2v` where `u` is the next axis
+/// after `axis` and `v` the one after it, so `u × v` points along `axis`.
+/// `far` selects the plane on the positive side of the cube.
+pub fn plane_corners(axis: Axis, far: bool) -> [u8; 4] {
+    let i = axis_index(axis);
+    let bit = |axis: usize| 1u8 << axis;
+    let base = if far { bit(i) } else { 0 };
+    let (u, v) = (bit((i + 1) % 3), bit((i + 2) % 3));
+    [base, base | u, base | v, base | u | v]
+}
+
+/// The face on the plane of `axis` through `o`, counterclockwise seen from the positive end of the axis.
+/// Like `SIDES`, it only depends on the 4 corners of the plane.
+pub fn side(axis: Axis, cor: &Corners<Corner>) -> Option<Polygon> {
+    plane_face(plane_corners(axis, false), cor)
+}
+
+/// The index of the configuration of the plane of `axis` through `o` in `0..SIDE_CONFIGURATIONS`,
+/// which `side` looks up: corner `i` of `plane_corners` is the `i`-th ternary digit like in `cube_index`.
+pub fn side_index(axis: Axis, cor: &Corners<Corner>) -> usize {
+    plane_index(plane_corners(axis, false), cor)
+}
+
+/// The corners of a plane configuration in the order of `plane_corners`, the inverse of `side_index`.
+/// Panics if the index is out of range.
+pub fn side_from_index(index: usize) -> [Corner; 4] {
+    assert!(index < SIDE_CONFIGURATIONS, "no side configuration {index}");
+    let mut rest = index;
+    [(); 4].map(|_| {
+        let corner = [Corner::Air, Corner::Weak, Corner::Strong][rest % 3];
+        rest /= 3;
+        corner
+    })
+}
+
+fn plane_index(plane: [u8; 4], cor: &Corners<Corner>) -> usize {
+    let cor = cor.to_array();
+    plane
+        .iter()
+        .rev()
+        .fold(0, |index, corner| index * 3 + digit(cor[*corner as usize]))
+}
+
+fn plane_face(plane: [u8; 4], cor: &Corners<Corner>) -> Option<Polygon> {
+    let face = table::SIDES[plane_index(plane, cor)];
+    if face.is_empty() {
+        return None;
+    }
+    let mut corners = [0; 4];
+    for (corner, s) in corners.iter_mut().zip(face) {
+        *corner = plane[*s as usize];
+    }
+    Some(Polygon {
+        len: face.len() as u8,
+        corners,
+    })
+}
+
+/// The faces inside of the cube, facing out of its solid part. Empty if any corner is Air.
+pub fn inner(cor: &Corners<Corner>) -> impl Iterator<Item = Polygon> {
+    let faces: &[&[u8]] = match cor.each_ref().into_iter().any(|c| c.air()) {
+        true => &[],
+        false => table::INNER[cor.configuration() as usize],
+    };
+    faces.iter().map(|face| Polygon::new(face))
+}
+
+/// All faces of the cube configuration, facing out of its solid part:
+/// the faces of the near planes face towards negative coordinates, the ones of the far planes
+/// towards positive coordinates.
+pub fn cube_faces(cor: &Corners<Corner>) -> Vec<CubeFace> {
+    let mut faces = Vec::new();
+    for axis in [Axis::X, Axis::Y, Axis::Z] {
+        if let Some(polygon) = plane_face(plane_corners(axis, false), cor) {
+            faces.push(CubeFace {
+                plane: FacePlane::Near(axis),
+                polygon: polygon.reversed(),
+            });
+        }
+        if let Some(polygon) = plane_face(plane_corners(axis, true), cor) {
+            faces.push(CubeFace {
+                plane: FacePlane::Far(axis),
+                polygon,
+            });
+        }
+    }
+    faces.extend(inner(cor).map(|polygon| CubeFace {
+        plane: FacePlane::Inner,
+        polygon,
+    }));
+    faces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables_are_consistent() {
+        assert_eq!(verify::verify(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn tables_are_up_to_date() {
+        let mut generated = vec![];
+        generate::write_tables(&mut generated).unwrap();
+        // regenerate with `cargo run --bin lut -- generate > src/chunk/lut/table.rs`:
+        assert!(generated == include_bytes!("table.rs"), "table.rs is stale");
+    }
+}
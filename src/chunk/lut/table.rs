@@ -0,0 +1,346 @@
+//! Generated by `cargo run --bin lut -- generate`, see `lut::generate`. Do not edit.
+
+#[rustfmt::skip]
+pub(super) static SIDES: [&[u8]; 81] = [
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[0, 1, 2],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[0, 1, 3],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[0, 3, 2],
+    &[],
+    &[1, 3, 2],
+    &[0, 1, 3, 2],
+];
+
+#[rustfmt::skip]
+pub(super) static INNER: [&[&[u8]]; 256] = [
+    &[], // 00000000
+    &[], // 00000001
+    &[], // 00000010
+    &[], // 00000011
+    &[], // 00000100
+    &[], // 00000101
+    &[], // 00000110
+    &[], // 00000111
+    &[], // 00001000
+    &[], // 00001001
+    &[], // 00001010
+    &[], // 00001011
+    &[], // 00001100
+    &[], // 00001101
+    &[], // 00001110
+    &[], // 00001111
+    &[], // 00010000
+    &[], // 00010001
+    &[], // 00010010
+    &[], // 00010011
+    &[], // 00010100
+    &[], // 00010101
+    &[], // 00010110
+    &[&[4, 1, 2]], // 00010111
+    &[], // 00011000
+    &[], // 00011001
+    &[], // 00011010
+    &[&[3, 0, 4], &[4, 1, 3]], // 00011011
+    &[], // 00011100
+    &[&[4, 0, 3], &[3, 2, 4]], // 00011101
+    &[&[2, 1, 4], &[4, 1, 3], &[3, 2, 4]], // 00011110
+    &[&[4, 1, 3], &[3, 2, 4]], // 00011111
+    &[], // 00100000
+    &[], // 00100001
+    &[], // 00100010
+    &[], // 00100011
+    &[], // 00100100
+    &[], // 00100101
+    &[], // 00100110
+    &[&[2, 0, 5], &[5, 1, 2]], // 00100111
+    &[], // 00101000
+    &[], // 00101001
+    &[], // 00101010
+    &[&[3, 0, 5]], // 00101011
+    &[], // 00101100
+    &[&[2, 0, 5], &[5, 0, 3], &[3, 2, 5]], // 00101101
+    &[&[2, 1, 5], &[3, 2, 5]], // 00101110
+    &[&[2, 0, 5], &[3, 2, 5]], // 00101111
+    &[], // 00110000
+    &[], // 00110001
+    &[], // 00110010
+    &[], // 00110011
+    &[], // 00110100
+    &[&[5, 0, 2], &[5, 2, 4]], // 00110101
+    &[&[2, 1, 4], &[5, 1, 2], &[5, 2, 4]], // 00110110
+    &[&[5, 1, 2], &[5, 2, 4]], // 00110111
+    &[], // 00111000
+    &[&[3, 0, 4], &[5, 0, 3], &[5, 3, 4]], // 00111001
+    &[&[3, 1, 4], &[5, 3, 4]], // 00111010
+    &[&[3, 0, 4], &[5, 3, 4]], // 00111011
+    &[], // 00111100
+    &[&[5, 0, 3], &[3, 2, 4, 5]], // 00111101
+    &[&[2, 1, 4], &[3, 2, 4, 5]], // 00111110
+    &[&[3, 2, 4, 5]], // 00111111
+    &[], // 01000000
+    &[], // 01000001
+    &[], // 01000010
+    &[], // 01000011
+    &[], // 01000100
+    &[], // 01000101
+    &[], // 01000110
+    &[&[6, 0, 1], &[6, 1, 2]], // 01000111
+    &[], // 01001000
+    &[], // 01001001
+    &[], // 01001010
+    &[&[6, 0, 1], &[3, 0, 6], &[6, 1, 3]], // 01001011
+    &[], // 01001100
+    &[&[6, 0, 3]], // 01001101
+    &[&[2, 1, 6], &[6, 1, 3]], // 01001110
+    &[&[6, 0, 1], &[6, 1, 3]], // 01001111
+    &[], // 01010000
+    &[], // 01010001
+    &[], // 01010010
+    &[&[1, 0, 6], &[4, 1, 6]], // 01010011
+    &[], // 01010100
+    &[], // 01010101
+    &[&[2, 1, 4], &[6, 1, 2], &[4, 1, 6]], // 01010110
+    &[&[6, 1, 2], &[4, 1, 6]], // 01010111
+    &[], // 01011000
+    &[&[4, 0, 3], &[3, 0, 6], &[4, 3, 6]], // 01011001
+    &[], // 01011010
+    &[&[3, 0, 6], &[4, 1, 3, 6]], // 01011011
+    &[&[4, 2, 3], &[4, 3, 6]], // 01011100
+    &[&[4, 0, 3], &[4, 3, 6]], // 01011101
+    &[&[2, 1, 4], &[4, 1, 3, 6]], // 01011110
+    &[&[4, 1, 3, 6]], // 01011111
+    &[], // 01100000
+    &[], // 01100001
+    &[], // 01100010
+    &[&[1, 0, 6], &[6, 0, 5], &[5, 1, 6]], // 01100011
+    &[], // 01100100
+    &[&[5, 0, 2], &[6, 0, 5], &[5, 2, 6]], // 01100101
+    &[], // 01100110
+    &[&[6, 0, 5], &[5, 1, 2, 6]], // 01100111
+    &[], // 01101000
+    &[&[5, 0, 3], &[3, 0, 6], &[6, 0, 5], &[5, 3, 6]], // 01101001
+    &[&[3, 1, 6], &[6, 1, 5], &[5, 3, 6]], // 01101010
+    &[&[3, 0, 6], &[6, 0, 5], &[5, 3, 6]], // 01101011
+    &[&[5, 2, 3], &[6, 2, 5], &[5, 3, 6]], // 01101100
+    &[&[5, 0, 3], &[6, 0, 5], &[5, 3, 6]], // 01101101
+    &[&[2, 1, 5, 6], &[5, 3, 6]], // 01101110
+    &[&[6, 0, 5], &[5, 3, 6]], // 01101111
+    &[], // 01110000
+    &[&[5, 0, 6]], // 01110001
+    &[&[6, 1, 4], &[5, 1, 6]], // 01110010
+    &[&[1, 0, 6], &[5, 1, 6]], // 01110011
+    &[&[4, 2, 5], &[5, 2, 6]], // 01110100
+    &[&[5, 0, 2], &[5, 2, 6]], // 01110101
+    &[&[2, 1, 4], &[5, 1, 2, 6]], // 01110110
+    &[&[5, 1, 2, 6]], // 01110111
+    &[&[4, 3, 5], &[6, 3, 4], &[5, 3, 6]], // 01111000
+    &[&[5, 0, 3], &[3, 0, 6], &[5, 3, 6]], // 01111001
+    &[&[3, 1, 4, 6], &[5, 3, 6]], // 01111010
+    &[&[3, 0, 6], &[5, 3, 6]], // 01111011
+    &[&[4, 2, 3, 5], &[5, 3, 6]], // 01111100
+    &[&[5, 0, 3], &[5, 3, 6]], // 01111101
+    &[&[2, 1, 4], &[5, 3, 6]], // 01111110
+    &[&[5, 3, 6]], // 01111111
+    &[], // 10000000
+    &[], // 10000001
+    &[], // 10000010
+    &[], // 10000011
+    &[], // 10000100
+    &[], // 10000101
+    &[], // 10000110
+    &[&[7, 0, 1], &[2, 0, 7], &[7, 1, 2]], // 10000111
+    &[], // 10001000
+    &[], // 10001001
+    &[], // 10001010
+    &[&[7, 0, 1], &[3, 0, 7]], // 10001011
+    &[], // 10001100
+    &[&[2, 0, 7], &[7, 0, 3]], // 10001101
+    &[&[2, 1, 7]], // 10001110
+    &[&[7, 0, 1], &[2, 0, 7]], // 10001111
+    &[], // 10010000
+    &[], // 10010001
+    &[], // 10010010
+    &[&[1, 0, 7], &[7, 0, 4], &[4, 1, 7]], // 10010011
+    &[], // 10010100
+    &[&[7, 0, 2], &[4, 0, 7], &[7, 2, 4]], // 10010101
+    &[&[2, 1, 4], &[7, 1, 2], &[4, 1, 7], &[7, 2, 4]], // 10010110
+    &[&[7, 1, 2], &[4, 1, 7], &[7, 2, 4]], // 10010111
+    &[], // 10011000
+    &[], // 10011001
+    &[&[3, 1, 4], &[4, 1, 7], &[7, 3, 4]], // 10011010
+    &[&[3, 0, 4, 7], &[4, 1, 7]], // 10011011
+    &[&[4, 2, 3], &[7, 2, 4], &[4, 3, 7]], // 10011100
+    &[&[4, 0, 3, 7], &[7, 2, 4]], // 10011101
+    &[&[2, 1, 4], &[4, 1, 7], &[7, 2, 4]], // 10011110
+    &[&[4, 1, 7], &[7, 2, 4]], // 10011111
+    &[], // 10100000
+    &[], // 10100001
+    &[], // 10100010
+    &[&[1, 0, 7], &[7, 0, 5]], // 10100011
+    &[], // 10100100
+    &[], // 10100101
+    &[&[2, 1, 5], &[7, 1, 2], &[7, 2, 5]], // 10100110
+    &[&[2, 0, 5, 7], &[7, 1, 2]], // 10100111
+    &[], // 10101000
+    &[&[5, 0, 3], &[3, 0, 7], &[7, 0, 5]], // 10101001
+    &[], // 10101010
+    &[&[3, 0, 7], &[7, 0, 5]], // 10101011
+    &[&[5, 2, 3], &[7, 2, 5]], // 10101100
+    &[&[2, 0, 5, 7], &[5, 0, 3]], // 10101101
+    &[&[2, 1, 5], &[7, 2, 5]], // 10101110
+    &[&[2, 0, 5, 7]], // 10101111
+    &[], // 10110000
+    &[&[7, 0, 4], &[5, 0, 7]], // 10110001
+    &[&[7, 1, 4]], // 10110010
+    &[&[1, 0, 7], &[7, 0, 4]], // 10110011
+    &[&[4, 2, 5], &[7, 2, 4], &[5, 2, 7]], // 10110100
+    &[&[5, 0, 2, 7], &[7, 2, 4]], // 10110101
+    &[&[2, 1, 4], &[7, 1, 2], &[7, 2, 4]], // 10110110
+    &[&[7, 1, 2], &[7, 2, 4]], // 10110111
+    &[&[4, 3, 5], &[7, 3, 4]], // 10111000
+    &[&[3, 0, 4, 7], &[5, 0, 3]], // 10111001
+    &[&[3, 1, 4], &[7, 3, 4]], // 10111010
+    &[&[3, 0, 4, 7]], // 10111011
+    &[&[4, 2, 3, 5], &[7, 2, 4]], // 10111100
+    &[&[5, 0, 3], &[7, 2, 4]], // 10111101
+    &[&[2, 1, 4], &[7, 2, 4]], // 10111110
+    &[&[7, 2, 4]], // 10111111
+    &[], // 11000000
+    &[], // 11000001
+    &[], // 11000010
+    &[], // 11000011
+    &[], // 11000100
+    &[&[7, 0, 2], &[6, 0, 7]], // 11000101
+    &[&[2, 1, 6], &[7, 1, 2], &[6, 1, 7]], // 11000110
+    &[&[6, 0, 1, 7], &[7, 1, 2]], // 11000111
+    &[], // 11001000
+    &[&[3, 0, 6], &[7, 0, 3], &[6, 0, 7]], // 11001001
+    &[&[3, 1, 6], &[6, 1, 7]], // 11001010
+    &[&[6, 0, 1, 7], &[3, 0, 6]], // 11001011
+    &[], // 11001100
+    &[&[7, 0, 3], &[6, 0, 7]], // 11001101
+    &[&[2, 1, 6], &[6, 1, 7]], // 11001110
+    &[&[6, 0, 1, 7]], // 11001111
+    &[], // 11010000
+    &[&[4, 0, 7], &[7, 0, 6]], // 11010001
+    &[&[6, 1, 4], &[4, 1, 7], &[7, 1, 6]], // 11010010
+    &[&[1, 0, 6, 7], &[4, 1, 7]], // 11010011
+    &[&[4, 2, 7]], // 11010100
+    &[&[7, 0, 2], &[4, 0, 7]], // 11010101
+    &[&[2, 1, 4], &[7, 1, 2], &[4, 1, 7]], // 11010110
+    &[&[7, 1, 2], &[4, 1, 7]], // 11010111
+    &[&[6, 3, 4], &[4, 3, 7]], // 11011000
+    &[&[4, 0, 3, 7], &[3, 0, 6]], // 11011001
+    &[&[3, 1, 4, 6], &[4, 1, 7]], // 11011010
+    &[&[3, 0, 6], &[4, 1, 7]], // 11011011
+    &[&[4, 2, 3], &[4, 3, 7]], // 11011100
+    &[&[4, 0, 3, 7]], // 11011101
+    &[&[2, 1, 4], &[4, 1, 7]], // 11011110
+    &[&[4, 1, 7]], // 11011111
+    &[], // 11100000
+    &[&[6, 0, 5], &[5, 0, 7], &[7, 0, 6]], // 11100001
+    &[&[6, 1, 5], &[7, 1, 6]], // 11100010
+    &[&[1, 0, 6, 7], &[6, 0, 5]], // 11100011
+    &[&[6, 2, 5], &[5, 2, 7]], // 11100100
+    &[&[5, 0, 2, 7], &[6, 0, 5]], // 11100101
+    &[&[2, 1, 5, 6], &[7, 1, 2]], // 11100110
+    &[&[6, 0, 5], &[7, 1, 2]], // 11100111
+    &[&[6, 3, 5]], // 11101000
+    &[&[5, 0, 3], &[3, 0, 6], &[6, 0, 5]], // 11101001
+    &[&[3, 1, 6], &[6, 1, 5]], // 11101010
+    &[&[3, 0, 6], &[6, 0, 5]], // 11101011
+    &[&[5, 2, 3], &[6, 2, 5]], // 11101100
+    &[&[5, 0, 3], &[6, 0, 5]], // 11101101
+    &[&[2, 1, 5, 6]], // 11101110
+    &[&[6, 0, 5]], // 11101111
+    &[], // 11110000
+    &[&[5, 0, 7], &[7, 0, 6]], // 11110001
+    &[&[6, 1, 4], &[7, 1, 6]], // 11110010
+    &[&[1, 0, 6, 7]], // 11110011
+    &[&[4, 2, 5], &[5, 2, 7]], // 11110100
+    &[&[5, 0, 2, 7]], // 11110101
+    &[&[2, 1, 4], &[7, 1, 2]], // 11110110
+    &[&[7, 1, 2]], // 11110111
+    &[&[4, 3, 5], &[6, 3, 4]], // 11111000
+    &[&[5, 0, 3], &[3, 0, 6]], // 11111001
+    &[&[3, 1, 4, 6]], // 11111010
+    &[&[3, 0, 6]], // 11111011
+    &[&[4, 2, 3, 5]], // 11111100
+    &[&[5, 0, 3]], // 11111101
+    &[&[2, 1, 4]], // 11111110
+    &[], // 11111111
+];
@@ -0,0 +1,218 @@
+//! Checks the lookup tables of `lut` for all 3^8 cube configurations.
+
+use bevy::utils::HashMap;
+
+use crate::chunk::{pos::Axis, voxel::Corner, Corners};
+
+use super::{
+    cube_faces, cube_from_index, cube_index,
+    generate::{cross, hull_facets, offset, side_face},
+    plane_corners, plane_face, side, CubeFace, FacePlane, Polygon, CUBE_CONFIGURATIONS,
+};
+
+const AXES: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
+
+/// Describes every inconsistency of the tables, empty if there is none:
+/// - the faces of every configuration are the ones derived from its convex hull, facing outwards,
+/// - no face touches an Air corner,
+/// - the faces of every cube without Air corners form a closed surface,
+/// - the face on a plane shared by two neighbouring cubes is the same for both of them.
+pub fn verify() -> Vec<String> {
+    let mut errors = Vec::new();
+    for index in 0..CUBE_CONFIGURATIONS {
+        let cor = cube_from_index(index);
+        if cube_index(&cor) != index {
+            errors.push(format!(
+                "{index}: cube_index does not invert cube_from_index"
+            ));
+        }
+        let faces = cube_faces(&cor);
+        check_faces(&cor, &faces)
+            .into_iter()
+            .chain(check_closed(&cor, &faces))
+            .chain(check_neighbours(&cor))
+            .for_each(|error| errors.push(format!("{index} {}: {error}", describe(&cor))));
+    }
+    errors
+}
+
+fn describe(cor: &Corners<Corner>) -> String {
+    cor.to_array().map(Corner::to_char).iter().collect()
+}
+
+/// The polygon starting at its smallest corner, so equal polygons compare equal.
+fn canonical(polygon: &Polygon) -> Vec<u8> {
+    let corners = polygon.corners();
+    let start = (0..corners.len()).min_by_key(|i| corners[*i]).unwrap_or(0);
+    corners[start..]
+        .iter()
+        .chain(&corners[..start])
+        .copied()
+        .collect()
+}
+
+fn polygon_normal(polygon: &Polygon) -> [i32; 3] {
+    let [a, b, c] = [0, 1, 2].map(|i| offset(polygon.corners()[i]));
+    let sub = |p: [i32; 3], q: [i32; 3]| [p[0] - q[0], p[1] - q[1], p[2] - q[2]];
+    cross(sub(b, a), sub(c, a))
+}
+
+fn has_air(cor: &Corners<Corner>) -> bool {
+    cor.each_ref().into_iter().any(|corner| corner.air())
+}
+
+/// Compares the faces with the facets of the convex hull, or for open cubes with the faces
+/// of the planes on their own.
+fn check_faces(cor: &Corners<Corner>, faces: &[CubeFace]) -> Vec<String> {
+    let mut errors = Vec::new();
+    let hull = match has_air(cor) {
+        true => Vec::new(),
+        false => hull_facets(cor.configuration()),
+    };
+    let mut expected: Vec<(FacePlane, Vec<u8>)> = hull
+        .iter()
+        .map(|facet| (facet.plane(), facet.corners.clone()))
+        .collect();
+    if hull.is_empty() {
+        let cor = cor.to_array();
+        for axis in AXES {
+            for far in [false, true] {
+                let plane = plane_corners(axis, far);
+                let face = side_face(plane.map(|corner| cor[corner as usize]));
+                if face.is_empty() {
+                    continue;
+                }
+                let polygon =
+                    Polygon::new(&face.iter().map(|s| plane[*s as usize]).collect::<Vec<_>>());
+                let (plane, polygon) = match far {
+                    true => (FacePlane::Far(axis), polygon),
+                    false => (FacePlane::Near(axis), polygon.reversed()),
+                };
+                expected.push((plane, canonical(&polygon)));
+            }
+        }
+    }
+    let mut expected: Vec<(FacePlane, Vec<u8>)> = expected
+        .into_iter()
+        .map(|(plane, corners)| (plane, canonical(&Polygon::new(&corners))))
+        .collect();
+
+    let corners = cor.to_array();
+    for face in faces {
+        if face
+            .polygon
+            .corners()
+            .iter()
+            .any(|c| corners[*c as usize].air())
+        {
+            errors.push(format!("face {:?} touches an Air corner", face.polygon));
+        }
+        let normal = polygon_normal(&face.polygon);
+        let facing = match face.plane {
+            FacePlane::Near(axis) => Some((axis, -1)),
+            FacePlane::Far(axis) => Some((axis, 1)),
+            FacePlane::Inner => None,
+        };
+        if let Some((axis, direction)) = facing {
+            let i = AXES.iter().position(|a| *a == axis).unwrap_or_default();
+            let far = face.plane == FacePlane::Far(axis);
+            if face
+                .polygon
+                .corners()
+                .iter()
+                .any(|c| (offset(*c)[i] == 1) != far)
+            {
+                errors.push(format!(
+                    "{:?} face {:?} is not on its plane",
+                    face.plane, face.polygon
+                ));
+            }
+            if normal[i].signum() != direction {
+                errors.push(format!(
+                    "{:?} face {:?} faces inwards",
+                    face.plane, face.polygon
+                ));
+            }
+        }
+        let key = (face.plane, canonical(&face.polygon));
+        match expected.iter().position(|e| *e == key) {
+            Some(i) => {
+                expected.swap_remove(i);
+            }
+            None => errors.push(format!("unexpected face {key:?}")),
+        }
+    }
+    errors.extend(expected.iter().map(|face| format!("missing face {face:?}")));
+    errors
+}
+
+/// Every edge of a closed surface is used once in each direction.
+fn check_closed(cor: &Corners<Corner>, faces: &[CubeFace]) -> Vec<String> {
+    if has_air(cor) || hull_facets(cor.configuration()).is_empty() {
+        return Vec::new();
+    }
+    let mut edges: HashMap<(u8, u8), i32> = HashMap::new();
+    for face in faces {
+        let corners = face.polygon.corners();
+        for (i, a) in corners.iter().enumerate() {
+            let b = corners[(i + 1) % corners.len()];
+            *edges.entry((*a, b)).or_default() += 1;
+        }
+    }
+    edges
+        .iter()
+        .filter(|((a, b), count)| edges.get(&(*b, *a)) != Some(*count) || **count != 1)
+        .map(|((a, b), count)| {
+            format!("edge {a}->{b} is used {count} times, but not once in each direction")
+        })
+        .collect()
+}
+
+/// The far faces of the cube have to match the near faces of its neighbours for all
+/// configurations of the corners they do not share.
+fn check_neighbours(cor: &Corners<Corner>) -> Vec<String> {
+    let mut errors = Vec::new();
+    let cor = cor.to_array();
+    for (i, axis) in AXES.into_iter().enumerate() {
+        let far = plane_corners(axis, true);
+        let near = plane_corners(axis, false);
+        let face = plane_face(far, &Corners::from_array(cor));
+        // positions in the neighbour, which is one step further along the axis:
+        let expected = face.map(|polygon| {
+            polygon
+                .corners()
+                .iter()
+                .map(|c| {
+                    let mut pos = offset(*c);
+                    pos[i] -= 1;
+                    pos
+                })
+                .collect::<Vec<_>>()
+        });
+        for rest in 0..81 {
+            let mut neighbour = [Corner::Air; 8];
+            let mut digits = rest;
+            for s in 0..4 {
+                neighbour[near[s] as usize] = cor[far[s] as usize];
+                neighbour[far[s] as usize] =
+                    [Corner::Air, Corner::Weak, Corner::Strong][digits % 3];
+                digits /= 3;
+            }
+            let actual = side(axis, &Corners::from_array(neighbour)).map(|polygon| {
+                polygon
+                    .corners()
+                    .iter()
+                    .map(|c| offset(*c))
+                    .collect::<Vec<_>>()
+            });
+            if actual != expected {
+                errors.push(format!(
+                    "the {axis:?} face {expected:?} does not match the face {actual:?} of the neighbour {}",
+                    describe(&Corners::from_array(neighbour))
+                ));
+                break;
+            }
+        }
+    }
+    errors
+}
@@ -1,61 +1,193 @@
 use bevy::{
-    prelude::{Color, Gizmos, Mesh, Vec3},
-    render::{mesh::Indices, render_graph::Edge, render_resource::PrimitiveTopology},
-    utils::HashMap,
+    prelude::{Color, Gizmos, Mesh, Resource, Vec3},
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+    utils::{HashMap, HashSet},
 };
 
-use crate::chunk::voxel::{Corner, Matter};
+use crate::chunk::voxel::{Corner, Edge, Matter};
 
-use self::{pos::Pos, voxel::Voxel};
+use self::{
+    light::Light,
+    pos::{Axis, Pos},
+    voxel::Voxel,
+};
 
+pub mod ao;
+pub mod blend;
+pub mod brush;
+pub mod collider;
+pub mod components;
+pub mod dual;
+pub mod edit;
 pub mod examples;
 pub mod ir;
+pub mod light;
+pub mod lod;
+pub mod lut;
+pub mod picking;
 pub mod pos;
+pub mod prefab;
+pub mod remesh;
+pub mod section;
+pub mod stats;
+pub mod structure;
+pub mod symmetry;
 
 pub mod voxel;
 
-use Corner::*;
-
-/// This is only the size in X and Z direction. Chunks are 256 voxels high.
+/// This is only the size in X and Z direction. Chunks are 256 voxels high,
+/// split into vertical sections of `section::SECTION_HEIGHT` voxels.
 pub const CHUNK_SIZE: u8 = 32;
 
-/// x,y,z as chunk indexes
+/// x,y,z as chunk indexes. y is the index of the vertical section, see `section`.
 pub type ChunkPos = (isize, isize, isize);
 
+#[derive(Resource, Debug, Clone, Default)]
 pub struct ChunkWorld {
     pub chunks: HashMap<ChunkPos, Chunk>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct Chunk {
     pub voxels: HashMap<Pos, Voxel>,
     pub edges: HashMap<Pos, Vec<Edge>>,
+    /// voxels modified since the last remesh
+    pub dirty: HashSet<Pos>,
+    /// corner of all voxels inside the chunk bounds that are not stored in `voxels`.
+    /// Air for regular chunks, Strong for fully solid sections.
+    pub fill: Corner,
+    /// corners of the neighbouring chunks in the shell from -1 to CHUNK_SIZE + 1 around the chunk,
+    /// needed to build the faces and ambient occlusion of the outermost voxels. Their faces reach
+    /// CHUNK_SIZE, and the ambient occlusion of a vertex looks at the corners one further.
+    pub border: HashMap<Pos, Corner>,
+    /// light of the corners inside the chunk bounds that differ from `light_fill`, see `light`.
+    pub light: HashMap<Pos, Light>,
+    /// light of all corners inside the chunk bounds that are not stored in `light`.
+    pub light_fill: Light,
+    /// light of the neighbouring chunks in the same shell as `border`.
+    pub border_light: HashMap<Pos, Light>,
+    /// voxels modified since the last light update, see `ChunkWorld::update_light`.
+    pub light_dirty: HashSet<Pos>,
 }
 
 impl Chunk {
+    /// The corners of the unit cube spanned by the voxel at `pos`, see `VoxelCornerInfo`.
+    pub fn corner_info(&self, pos: Pos) -> VoxelCornerInfo {
+        self.get_voxel_corner_info(pos, self.get_voxel_corner(&pos))
+    }
+
     fn get_voxel_corner_info(&self, o: Pos, o_corner: Corner) -> VoxelCornerInfo {
-        let x = o.plus_x();
-        let y = o.plus_y();
-        let z = o.plus_z();
-        let xy = o.plus_xy();
-        let xz = o.plus_xz();
-        let yz = o.plus_yz();
-        let xyz = o.plus_xyz();
-
-        let cor: Corners<Corner> = {
-            Corners {
-                o: o_corner,
-                x: self.get_voxel_corner(&x),
-                y: self.get_voxel_corner(&y),
-                z: self.get_voxel_corner(&z),
-                xy: self.get_voxel_corner(&xy),
-                xz: self.get_voxel_corner(&xz),
-                yz: self.get_voxel_corner(&yz),
-                xyz: self.get_voxel_corner(&xyz),
-            }
+        // corners beyond the i8 range lie outside of the chunk and its border, they are Air.
+        let pos = Corners::OFFSETS.map(|offset| o.checked_add(offset));
+        let cor = Corners::OFFSETS
+            .zip(pos)
+            .map(|(offset, pos)| match (offset == Pos::ZERO, pos) {
+                (true, _) => o_corner,
+                (false, Some(pos)) => self.get_voxel_corner(&pos),
+                (false, None) => Corner::Air,
+            });
+        VoxelCornerInfo { cor, pos }
+    }
+
+    fn get_voxel_corner(&self, pos: &Pos) -> Corner {
+        match self.voxels.get(pos) {
+            Some(voxel) => voxel.corner,
+            None => match self.border.get(pos) {
+                Some(corner) => *corner,
+                None => self.unstored_voxel(*pos).corner,
+            },
+        }
+    }
+
+    pub fn get_voxel(&self, pos: Pos) -> Voxel {
+        self.voxels
+            .get(&pos)
+            .copied()
+            .unwrap_or_else(|| self.unstored_voxel(pos))
+    }
+
+    /// Stores the voxel, voxels that equal the fill of the chunk are removed from the chunk.
+    /// Returns the voxel that was previously at this position.
+    pub fn set_voxel(&mut self, pos: Pos, voxel: Voxel) -> Voxel {
+        self.dirty.insert(pos);
+        self.light_dirty.insert(pos);
+        let old = if voxel == self.unstored_voxel(pos) {
+            self.voxels.remove(&pos)
+        } else {
+            self.voxels.insert(pos, voxel)
         };
+        old.unwrap_or_else(|| self.unstored_voxel(pos))
+    }
+
+    /// A chunk where every voxel is a Strong corner without sides, like the inside of a mountain.
+    pub fn full() -> Self {
+        Chunk {
+            fill: Corner::Strong,
+            ..Default::default()
+        }
+    }
+
+    /// true if the chunk produces no faces and all its corners are Air.
+    pub fn is_empty(&self) -> bool {
+        self.fill.air() && self.voxels.is_empty() && self.edges.is_empty()
+    }
+
+    /// true if the chunk produces no faces and all its corners are Strong.
+    pub fn is_full(&self) -> bool {
+        self.fill.strong() && self.voxels.is_empty() && self.edges.is_empty()
+    }
 
-        let pos = Corners {
+    /// true if the position lies inside of the CHUNK_SIZE³ bounds of the chunk.
+    pub fn in_bounds(pos: Pos) -> bool {
+        pos.in_chunk()
+    }
+
+    fn unstored_voxel(&self, pos: Pos) -> Voxel {
+        if Chunk::in_bounds(pos) {
+            Voxel {
+                corner: self.fill,
+                ..Default::default()
+            }
+        } else {
+            Voxel::default()
+        }
+    }
+}
+
+/// One value for each of the 8 corners of a unit cube, named after the axes they are offset along.
+///
+/// Iteration, `to_array` and the bits of `Corners::configuration` use the index `x + 2y + 4z` of the offset,
+/// so the order is o, x, y, xy, z, xz, yz, xyz.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Corners<T> {
+    /// the corner of the voxel itself, the mesher skips voxels where it is air.
+    pub o: T,
+    pub x: T,
+    pub y: T,
+    pub z: T,
+    pub xy: T,
+    pub xz: T,
+    pub yz: T,
+    pub xyz: T,
+}
+
+impl Corners<Pos> {
+    /// The offset of each corner from `o`.
+    pub const OFFSETS: Corners<Pos> = Corners {
+        o: Pos::new(0, 0, 0),
+        x: Pos::new(1, 0, 0),
+        y: Pos::new(0, 1, 0),
+        z: Pos::new(0, 0, 1),
+        xy: Pos::new(1, 1, 0),
+        xz: Pos::new(1, 0, 1),
+        yz: Pos::new(0, 1, 1),
+        xyz: Pos::new(1, 1, 1),
+    };
+}
+
+impl<T> Corners<T> {
+    pub fn from_array([o, x, y, xy, z, xz, yz, xyz]: [T; 8]) -> Self {
+        Corners {
             o,
             x,
             y,
@@ -64,86 +196,115 @@ impl Chunk {
             xz,
             yz,
             xyz,
-        };
-        VoxelCornerInfo { cor, pos }
+        }
     }
 
-    fn get_voxel_corner(&self, pos: &Pos) -> Corner {
-        self.voxels.get(pos).map(|e| e.corner).unwrap_or_default()
+    pub fn to_array(self) -> [T; 8] {
+        let Corners {
+            o,
+            x,
+            y,
+            z,
+            xy,
+            xz,
+            yz,
+            xyz,
+        } = self;
+        [o, x, y, xy, z, xz, yz, xyz]
+    }
+
+    /// The value of the corner at `offset`, None if it is not an offset of `Corners::OFFSETS`.
+    pub fn get(&self, offset: Pos) -> Option<&T> {
+        let Pos { x, y, z } = offset;
+        if !(0..=1).contains(&x) || !(0..=1).contains(&y) || !(0..=1).contains(&z) {
+            return None;
+        }
+        Some(self.each_ref().to_array()[(x + 2 * y + 4 * z) as usize])
+    }
+
+    pub fn each_ref(&self) -> Corners<&T> {
+        Corners {
+            o: &self.o,
+            x: &self.x,
+            y: &self.y,
+            z: &self.z,
+            xy: &self.xy,
+            xz: &self.xz,
+            yz: &self.yz,
+            xyz: &self.xyz,
+        }
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Corners<U> {
+        Corners::from_array(self.to_array().map(f))
+    }
+
+    pub fn zip<U>(self, other: Corners<U>) -> Corners<(T, U)> {
+        let mut other = other.to_array().into_iter();
+        self.map(|value| (value, other.next().expect("8 corners")))
+    }
+
+    /// The corners with their offsets from `o`.
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        Corners::OFFSETS.zip(self.each_ref()).to_array().into_iter()
     }
 }
 
-struct Corners<T> {
-    /// should never be air!
-    o: T,
-    x: T,
-    y: T,
-    z: T,
-    xy: T,
-    xz: T,
-    yz: T,
-    xyz: T,
+impl<T> IntoIterator for Corners<T> {
+    type Item = T;
+    type IntoIter = std::array::IntoIter<T, 8>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.to_array().into_iter()
+    }
 }
 
-struct VoxelCornerInfo {
-    cor: Corners<Corner>,
-    pos: Corners<Pos>,
+impl Corners<Corner> {
+    /// The configuration of the cube for lookup tables like in marching cubes:
+    /// bit `x + 2y + 4z` is set if the corner at that offset is Strong.
+    pub fn configuration(&self) -> u8 {
+        self.iter()
+            .enumerate()
+            .filter(|(_, (_, corner))| corner.strong())
+            .fold(0, |index, (bit, _)| index | (1 << bit))
+    }
 }
 
-const W: Corner = Weak;
-const S: Corner = Strong;
+/// The corners of the unit cube spanned by a voxel and their positions, which decide the faces the voxel
+/// owns, see `Chunk::corner_info`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VoxelCornerInfo {
+    pub cor: Corners<Corner>,
+    /// None for the corners beyond the i8 range, which are Air and never part of a face.
+    pub pos: Corners<Option<Pos>>,
+}
 
 impl VoxelCornerInfo {
+    /// See `Corners::configuration`.
+    pub fn configuration(&self) -> u8 {
+        self.cor.configuration()
+    }
+
     pub fn x_side(&self) -> Side {
-        let VoxelCornerInfo { pos, cor } = &self;
-        match (cor.o, cor.y, cor.yz, cor.z) {
-            (S, S, S, S) => Side::Quad(pos.o, pos.y, pos.yz, pos.z),
-            (W, S, S, S) => Side::Triag(pos.y, pos.yz, pos.z),
-            (S, W, S, S) => Side::Triag(pos.o, pos.yz, pos.z),
-            (S, S, W, S) => Side::Triag(pos.o, pos.y, pos.z),
-            (S, S, S, W) => Side::Triag(pos.o, pos.y, pos.yz),
-            _ => Side::None,
-        }
+        self.side(Axis::X)
     }
 
     pub fn y_side(&self) -> Side {
-        let VoxelCornerInfo { pos, cor } = &self;
-        match (cor.o, cor.x, cor.xz, cor.z) {
-            (S, S, S, S) => Side::Quad(pos.o, pos.x, pos.xz, pos.z),
-            (W, S, S, S) => Side::Triag(pos.x, pos.xz, pos.z),
-            (S, W, S, S) => Side::Triag(pos.o, pos.xz, pos.z),
-            (S, S, W, S) => Side::Triag(pos.o, pos.x, pos.z),
-            (S, S, S, W) => Side::Triag(pos.o, pos.x, pos.xz),
-            _ => Side::None,
-        }
+        self.side(Axis::Y)
     }
 
     pub fn z_side(&self) -> Side {
-        let VoxelCornerInfo { pos, cor } = &self;
-        match (cor.o, cor.x, cor.xy, cor.y) {
-            (S, S, S, S) => Side::Quad(pos.o, pos.x, pos.xy, pos.y),
-            (W, S, S, S) => Side::Triag(pos.x, pos.xy, pos.y),
-            (S, W, S, S) => Side::Triag(pos.o, pos.xy, pos.y),
-            (S, S, W, S) => Side::Triag(pos.o, pos.x, pos.y),
-            (S, S, S, W) => Side::Triag(pos.o, pos.x, pos.xy),
-            _ => Side::None,
-        }
+        self.side(Axis::Z)
     }
 
-    pub fn inner_side(&self) -> InnerSide {
-        let VoxelCornerInfo { pos, cor } = &self;
-        match (cor.o, cor.z, cor.xz, cor.x, cor.y, cor.yz, cor.xyz, cor.xz) {
-            // exactly one corner is weak, all others strong: -> single triangle
-            (W, S, S, S, S, S, S, S) => InnerSide::Triag(pos.x, pos.y, pos.z),
-            (S, W, S, S, S, S, S, S) => InnerSide::Triag(pos.o, pos.xz, pos.yz),
-            (S, S, W, S, S, S, S, S) => InnerSide::Triag(pos.x, pos.xyz, pos.z),
-            (S, S, S, W, S, S, S, S) => InnerSide::Triag(pos.o, pos.xz, pos.x),
-            // (S, S, S, S, W, S, S, S) => InnerSide::Triag(),
-            // (S, S, S, S, S, W, S, S) => InnerSide::Triag(),
-            // (S, S, S, S, S, S, W, S) => InnerSide::Triag(),
-            // (S, S, S, S, S, S, S, W) => InnerSide::Triag(),
-            _ => InnerSide::None,
-        }
+    /// The face of the voxel orthogonal to `axis`, see `lut::side`.
+    pub fn side(&self, axis: Axis) -> Side {
+        lut::side(axis, &self.cor).map_or(Side::None, |polygon| polygon.to_side(&self.pos))
+    }
+
+    /// The diagonal faces inside of the cube, see `lut::inner`.
+    pub fn inner_sides(&self) -> impl Iterator<Item = Side> + '_ {
+        lut::inner(&self.cor).map(|polygon| polygon.to_side(&self.pos))
     }
 }
 
@@ -154,15 +315,15 @@ pub enum Side {
     Quad(Pos, Pos, Pos, Pos),
 }
 
-#[derive(Debug, Clone)]
-pub enum InnerSide {
-    None,
-    Triag(Pos, Pos, Pos),
-    DoubleTriag {
-        p1: Pos,
-        crease: (Pos, Pos),
-        p2: Pos,
-    },
+impl Side {
+    /// true if `pos` is one of the corners of the face.
+    pub fn contains(&self, pos: Pos) -> bool {
+        match *self {
+            Side::None => false,
+            Side::Triag(a, b, c) => [a, b, c].contains(&pos),
+            Side::Quad(a, b, c, d) => [a, b, c, d].contains(&pos),
+        }
+    }
 }
 
 impl Chunk {
@@ -235,3 +396,62 @@ impl Chunk {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corners_beyond_i8_are_air_without_position() {
+        let mut chunk = Chunk::default();
+        let o = Pos::new(i8::MAX, 0, 0);
+        for pos in [o, o + Pos::Y, o + Pos::Z, o + Pos::Y + Pos::Z] {
+            let voxel = Voxel {
+                corner: Corner::Strong,
+                ..Default::default()
+            };
+            chunk.set_voxel(pos, voxel);
+        }
+        let info = chunk.corner_info(o);
+        for (offset, (corner, pos)) in Corners::OFFSETS.zip(info.cor.zip(info.pos)) {
+            match offset.x {
+                0 => assert_eq!((corner, pos), (Corner::Strong, Some(o + offset))),
+                _ => assert_eq!((corner, pos), (Corner::Air, None)),
+            }
+        }
+        // the solid corners still form the face towards +x:
+        assert!(matches!(info.x_side(), Side::Quad(..)));
+    }
+
+    #[test]
+    fn corners_are_indexed_by_offset() {
+        let index = Corners::OFFSETS.map(|Pos { x, y, z }| x + 2 * y + 4 * z);
+        assert_eq!(index.to_array(), [0, 1, 2, 3, 4, 5, 6, 7]);
+        for (i, offset) in Corners::OFFSETS.into_iter().enumerate() {
+            assert_eq!(index.get(offset), Some(&(i as i8)));
+        }
+        for outside in [Pos::new(-1, 0, 0), Pos::new(0, 2, 0), Pos::new(1, 1, 2)] {
+            assert_eq!(index.get(outside), None);
+        }
+        // iter pairs every value with its own offset, in the order of to_array:
+        let iterated: Vec<_> = index.iter().map(|(offset, i)| (offset, *i)).collect();
+        let zipped: Vec<_> = Corners::OFFSETS.zip(index).into_iter().collect();
+        assert_eq!(iterated, zipped);
+        assert!(iterated.iter().enumerate().all(|(i, (_, v))| *v == i as i8));
+    }
+
+    #[test]
+    fn configuration_sets_the_bit_of_each_strong_offset() {
+        for offset in Corners::OFFSETS {
+            let cor = Corners::OFFSETS.map(|o| match o == offset {
+                true => Corner::Strong,
+                false => Corner::Weak,
+            });
+            let bit = offset.x + 2 * offset.y + 4 * offset.z;
+            assert_eq!(cor.configuration(), 1 << bit, "{offset:?}");
+        }
+        let all = Corners::OFFSETS.map(|_| Corner::Strong);
+        assert_eq!(all.configuration(), u8::MAX);
+        assert_eq!(all.map(|_| Corner::Weak).configuration(), 0);
+    }
+}
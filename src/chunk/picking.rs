@@ -0,0 +1,112 @@
+use bevy::prelude::{Ray, Vec3};
+
+use super::{
+    ir::ChunkIR,
+    pos::{Axis, Pos},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub struct RayHit {
+    pub distance: f32,
+    pub point: Vec3,
+    /// normal of the hit face, always pointing back towards the ray origin.
+    pub normal: Vec3,
+    /// the voxel that owns the hit face.
+    pub voxel: Pos,
+    /// which side of `voxel` was hit. None if the face is not axis aligned (e.g. diagonal inner faces).
+    pub side: Option<Axis>,
+}
+
+impl ChunkIR {
+    /// Casts a ray against all quads and triangles and returns the closest hit.
+    ///
+    /// Faces are double sided, so they can be hit from both directions.
+    pub fn raycast(&self, ray: Ray) -> Option<RayHit> {
+        let mut closest: Option<RayHit> = None;
+
+        let mut test_face = |corners: &[Pos]| {
+            let verts: Vec<Vec3> = corners.iter().map(|p| Vec3::from(*p)).collect();
+            for i in 1..(verts.len() - 1) {
+                let Some(distance) =
+                    ray_triangle_intersection(ray, verts[0], verts[i], verts[i + 1])
+                else {
+                    continue;
+                };
+                if closest.as_ref().is_some_and(|c| c.distance <= distance) {
+                    continue;
+                }
+                let mut normal = (verts[i] - verts[0])
+                    .cross(verts[i + 1] - verts[0])
+                    .normalize();
+                if normal.dot(ray.direction) > 0.0 {
+                    normal = -normal;
+                }
+                closest = Some(RayHit {
+                    distance,
+                    point: ray.get_point(distance),
+                    normal,
+                    voxel: face_owner(corners),
+                    side: face_axis(corners),
+                });
+            }
+        };
+
+        for quad in self.quads.iter() {
+            test_face(&[quad.a, quad.b, quad.c, quad.d]);
+        }
+        for triag in self.triags.iter() {
+            test_face(&[triag.a, triag.b, triag.c]);
+        }
+
+        closest
+    }
+}
+
+/// Möller–Trumbore, returns the distance along the ray. Hits from both sides are reported.
+fn ray_triangle_intersection(ray: Ray, a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+    const EPSILON: f32 = 1e-6;
+    let ab = b - a;
+    let ac = c - a;
+    let p = ray.direction.cross(ac);
+    let det = ab.dot(p);
+    if det.abs() < EPSILON {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let t_vec = ray.origin - a;
+    let u = t_vec.dot(p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = t_vec.cross(ab);
+    let v = ray.direction.dot(q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let t = ac.dot(q) * inv_det;
+    (t > EPSILON).then_some(t)
+}
+
+/// Every side spans from its owning voxel into the +x/+y/+z directions,
+/// so the owner is the component-wise minimum of the face corners.
+fn face_owner(corners: &[Pos]) -> Pos {
+    corners.iter().skip(1).fold(corners[0], |m, p| Pos {
+        x: m.x.min(p.x),
+        y: m.y.min(p.y),
+        z: m.z.min(p.z),
+    })
+}
+
+/// The axis a face is orthogonal to, if all its corners share one coordinate.
+fn face_axis(corners: &[Pos]) -> Option<Axis> {
+    let first = corners[0];
+    if corners.iter().all(|p| p.x == first.x) {
+        Some(Axis::X)
+    } else if corners.iter().all(|p| p.y == first.y) {
+        Some(Axis::Y)
+    } else if corners.iter().all(|p| p.z == first.z) {
+        Some(Axis::Z)
+    } else {
+        None
+    }
+}
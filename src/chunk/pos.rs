@@ -1,5 +1,7 @@
 use bevy::prelude::Vec3;
 
+use super::CHUNK_SIZE;
+
 #[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct Pos {
     pub x: i8,
@@ -8,10 +10,100 @@ pub struct Pos {
 }
 
 impl Pos {
-    pub fn new(x: i8, y: i8, z: i8) -> Self {
+    pub const ZERO: Pos = Pos::new(0, 0, 0);
+    pub const X: Pos = Pos::new(1, 0, 0);
+    pub const Y: Pos = Pos::new(0, 1, 0);
+    pub const Z: Pos = Pos::new(0, 0, 1);
+
+    pub const fn new(x: i8, y: i8, z: i8) -> Self {
         Pos { x, y, z }
     }
 
+    /// None if any coordinate overflows.
+    pub fn checked_add(self, rhs: Pos) -> Option<Pos> {
+        Some(Pos {
+            x: self.x.checked_add(rhs.x)?,
+            y: self.y.checked_add(rhs.y)?,
+            z: self.z.checked_add(rhs.z)?,
+        })
+    }
+
+    /// None if any coordinate overflows.
+    pub fn checked_sub(self, rhs: Pos) -> Option<Pos> {
+        Some(Pos {
+            x: self.x.checked_sub(rhs.x)?,
+            y: self.y.checked_sub(rhs.y)?,
+            z: self.z.checked_sub(rhs.z)?,
+        })
+    }
+
+    pub fn wrapping_add(self, rhs: Pos) -> Pos {
+        Pos {
+            x: self.x.wrapping_add(rhs.x),
+            y: self.y.wrapping_add(rhs.y),
+            z: self.z.wrapping_add(rhs.z),
+        }
+    }
+
+    pub fn wrapping_sub(self, rhs: Pos) -> Pos {
+        Pos {
+            x: self.x.wrapping_sub(rhs.x),
+            y: self.y.wrapping_sub(rhs.y),
+            z: self.z.wrapping_sub(rhs.z),
+        }
+    }
+
+    pub fn saturating_add(self, rhs: Pos) -> Pos {
+        Pos {
+            x: self.x.saturating_add(rhs.x),
+            y: self.y.saturating_add(rhs.y),
+            z: self.z.saturating_add(rhs.z),
+        }
+    }
+
+    pub fn saturating_sub(self, rhs: Pos) -> Pos {
+        Pos {
+            x: self.x.saturating_sub(rhs.x),
+            y: self.y.saturating_sub(rhs.y),
+            z: self.z.saturating_sub(rhs.z),
+        }
+    }
+
+    /// true if the position lies inside of the CHUNK_SIZE³ bounds of a chunk.
+    pub fn in_chunk(&self) -> bool {
+        let range = 0..CHUNK_SIZE as i8;
+        range.contains(&self.x) && range.contains(&self.y) && range.contains(&self.z)
+    }
+
+    /// The 6 neighbours sharing a face of the unit cube around the position.
+    /// Neighbours outside of the i8 range are left out, like all neighbour iterators.
+    pub fn neighbors6(self) -> impl Iterator<Item = Pos> {
+        self.neighbors(|d| d == 1)
+    }
+
+    /// The 18 neighbours sharing a face or an edge of the unit cube around the position.
+    pub fn neighbors18(self) -> impl Iterator<Item = Pos> {
+        self.neighbors(|d| d <= 2)
+    }
+
+    /// All 26 neighbours of the position.
+    pub fn neighbors26(self) -> impl Iterator<Item = Pos> {
+        self.neighbors(|_| true)
+    }
+
+    /// Neighbours whose offset has a number of non zero coordinates accepted by `filter`.
+    fn neighbors(self, filter: impl Fn(usize) -> bool) -> impl Iterator<Item = Pos> {
+        PosRange::new(Pos::new(-1, -1, -1), Pos::new(1, 1, 1))
+            .into_iter()
+            .filter(move |d| {
+                let non_zero = [d.x, d.y, d.z].iter().filter(|c| **c != 0).count();
+                non_zero > 0 && filter(non_zero)
+            })
+            .filter_map(move |d| self.checked_add(d))
+    }
+
+    /// The `plus_*` helpers use plain arithmetic and panic on overflow in debug builds,
+    /// use `checked_add` for positions that can reach `i8::MAX`.
     pub fn plus_x(&self) -> Self {
         Pos {
             x: self.x + 1,
@@ -69,6 +161,90 @@ impl Pos {
     }
 }
 
+/// The box of all positions between `min` and `max`, both inclusive.
+/// Iterates with x in the outer and z in the inner loop.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct PosRange {
+    pub min: Pos,
+    pub max: Pos,
+}
+
+impl PosRange {
+    pub fn new(min: Pos, max: Pos) -> Self {
+        PosRange { min, max }
+    }
+
+    /// All positions inside of the bounds of a chunk, see `Pos::in_chunk`.
+    pub fn chunk() -> Self {
+        let last = CHUNK_SIZE as i8 - 1;
+        PosRange::new(Pos::ZERO, Pos::new(last, last, last))
+    }
+
+    pub fn contains(&self, pos: Pos) -> bool {
+        (self.min.x..=self.max.x).contains(&pos.x)
+            && (self.min.y..=self.max.y).contains(&pos.y)
+            && (self.min.z..=self.max.z).contains(&pos.z)
+    }
+
+    /// Number of positions in the box, 0 if `max` is smaller than `min` in any coordinate.
+    pub fn len(&self) -> usize {
+        let extent = |min: i8, max: i8| (max as i16 - min as i16 + 1).max(0) as usize;
+        extent(self.min.x, self.max.x)
+            * extent(self.min.y, self.max.y)
+            * extent(self.min.z, self.max.z)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl IntoIterator for PosRange {
+    type Item = Pos;
+    type IntoIter = PosRangeIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        PosRangeIter {
+            next: (!self.is_empty()).then_some(self.min),
+            range: self,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PosRangeIter {
+    range: PosRange,
+    next: Option<Pos>,
+}
+
+impl Iterator for PosRangeIter {
+    type Item = Pos;
+
+    fn next(&mut self) -> Option<Pos> {
+        let current = self.next?;
+        let PosRange { min, max } = self.range;
+        // counting up never overflows, a coordinate is only incremented while it is below its max:
+        self.next = if current.z < max.z {
+            Some(Pos::new(current.x, current.y, current.z + 1))
+        } else if current.y < max.y {
+            Some(Pos::new(current.x, current.y + 1, min.z))
+        } else if current.x < max.x {
+            Some(Pos::new(current.x + 1, min.y, min.z))
+        } else {
+            None
+        };
+        Some(current)
+    }
+}
+
+/// The axis a voxel side is orthogonal to.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
 impl From<Pos> for [f32; 3] {
     fn from(pos: Pos) -> Self {
         [pos.x as f32, pos.y as f32, pos.z as f32]
@@ -131,3 +307,86 @@ impl std::ops::SubAssign for Pos {
 //         }
 //     };
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_iterates_z_fastest() {
+        let range = PosRange::new(Pos::new(0, 0, 0), Pos::new(1, 1, 1));
+        let order: Vec<Pos> = range.into_iter().collect();
+        let expected = [
+            (0, 0, 0),
+            (0, 0, 1),
+            (0, 1, 0),
+            (0, 1, 1),
+            (1, 0, 0),
+            (1, 0, 1),
+            (1, 1, 0),
+            (1, 1, 1),
+        ]
+        .map(|(x, y, z)| Pos::new(x, y, z));
+        assert_eq!(order, expected);
+        assert_eq!(range.len(), expected.len());
+    }
+
+    #[test]
+    fn range_reaches_the_end_of_i8() {
+        let range = PosRange::new(Pos::new(126, i8::MIN, 0), Pos::new(i8::MAX, i8::MIN, 0));
+        let all: Vec<Pos> = range.into_iter().collect();
+        assert_eq!(
+            all,
+            [Pos::new(126, i8::MIN, 0), Pos::new(i8::MAX, i8::MIN, 0)]
+        );
+    }
+
+    #[test]
+    fn inverted_ranges_are_empty() {
+        for max in [Pos::new(-1, 3, 3), Pos::new(3, -1, 3), Pos::new(3, 3, -1)] {
+            let range = PosRange::new(Pos::ZERO, max);
+            assert_eq!(range.len(), 0);
+            assert!(range.is_empty());
+            assert_eq!(range.into_iter().next(), None);
+        }
+        let range = PosRange::new(Pos::new(i8::MAX, 0, 0), Pos::new(i8::MIN, 0, 0));
+        assert!(range.is_empty());
+        assert!(!PosRange::new(Pos::ZERO, Pos::ZERO).is_empty());
+        assert_eq!(PosRange::chunk().len(), (CHUNK_SIZE as usize).pow(3));
+    }
+
+    #[test]
+    fn neighbours_leave_out_overflows() {
+        let counts = |pos: Pos| {
+            [
+                pos.neighbors6().count(),
+                pos.neighbors18().count(),
+                pos.neighbors26().count(),
+            ]
+        };
+        assert_eq!(counts(Pos::ZERO), [6, 18, 26]);
+        // on a face of the i8 cube a third of the 3x3x3 block is missing:
+        assert_eq!(counts(Pos::new(i8::MAX, 0, 0)), [5, 13, 17]);
+        assert_eq!(counts(Pos::new(0, i8::MIN, 0)), [5, 13, 17]);
+        // at a corner only the 2x2x2 block towards the inside is left:
+        assert_eq!(counts(Pos::new(i8::MAX, i8::MIN, i8::MAX)), [3, 6, 7]);
+        assert!(Pos::new(i8::MAX, i8::MIN, 0)
+            .neighbors26()
+            .all(|n| n.x >= i8::MAX - 1 && n.y <= i8::MIN + 1));
+    }
+
+    #[test]
+    fn in_chunk() {
+        let last = CHUNK_SIZE as i8 - 1;
+        assert!(Pos::ZERO.in_chunk());
+        assert!(Pos::new(last, last, last).in_chunk());
+        for outside in [
+            Pos::new(-1, 0, 0),
+            Pos::new(0, last + 1, 0),
+            Pos::new(0, 0, i8::MIN),
+        ] {
+            assert!(!outside.in_chunk(), "{outside:?}");
+        }
+        assert!(PosRange::chunk().into_iter().all(|pos| pos.in_chunk()));
+    }
+}
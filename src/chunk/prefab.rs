@@ -0,0 +1,397 @@
+//! Copy and paste of rectangular regions of a ChunkWorld.
+//!
+//! Prefabs can be rotated and mirrored, which moves faces and edges to other voxels, see `symmetry`.
+
+use bevy::utils::HashMap;
+
+use super::{
+    edit::{ChunkEdit, WorldEdit},
+    light::{from_world_pos, WorldPos},
+    pos::{Axis, Pos},
+    structure::Solids,
+    symmetry::{face_corners, Content, Symmetry, AXES},
+    voxel::{Edge, Voxel},
+    ChunkWorld,
+};
+
+/// A captured region, independent of where it was captured.
+/// Corners range from `Pos(0,0,0)` to `max`, both inclusive, so a prefab is at most
+/// `i8::MAX + 1` corners long in every direction.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Prefab {
+    pub max: Pos,
+    /// all voxels of the region that are not the default voxel.
+    pub voxels: HashMap<Pos, Voxel>,
+    pub edges: HashMap<Pos, Vec<Edge>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PasteMode {
+    /// the whole region is replaced, Air of the prefab clears the world.
+    #[default]
+    Overwrite,
+    /// only solid corners, sides and edges of the prefab are written, everything else is kept.
+    /// Corners only get more solid, a Weak corner of the prefab keeps a Strong one of the world.
+    Merge,
+}
+
+impl Prefab {
+    fn in_bounds(&self, pos: Pos) -> bool {
+        (0..=self.max.x).contains(&pos.x)
+            && (0..=self.max.y).contains(&pos.y)
+            && (0..=self.max.z).contains(&pos.z)
+    }
+
+    fn voxel(&self, pos: Pos) -> Voxel {
+        self.voxels.get(&pos).copied().unwrap_or_default()
+    }
+
+    /// true if the side along `axis` of the voxel at `pos` lies inside of the region.
+    fn contains_side(&self, pos: Pos, axis: Axis) -> bool {
+        face_corners(pos, axis)
+            .iter()
+            .all(|corner| self.in_bounds(*corner))
+    }
+
+    /// true if the inner faces of the voxel at `pos` lie inside of the region.
+    fn contains_inner(&self, pos: Pos) -> bool {
+        pos.checked_add(Pos::new(1, 1, 1))
+            .is_some_and(|corner| self.in_bounds(corner))
+    }
+
+    /// true if the edge starting at `pos` ends inside of the region.
+    fn contains_edge(&self, pos: Pos, edge: &Edge) -> bool {
+        pos.checked_add(edge.kind().delta())
+            .is_some_and(|end| self.in_bounds(end))
+    }
+
+    /// The prefab transformed by the symmetry and moved back to `Pos(0,0,0)`.
+    pub fn transformed(&self, symmetry: Symmetry) -> Prefab {
+        let content = Content {
+            max: self.max,
+            voxels: self.voxels.clone(),
+            edges: self.edges.clone(),
+        }
+        .transformed(symmetry);
+        Prefab {
+            max: content.max,
+            voxels: content.voxels,
+            edges: content.edges,
+        }
+    }
+
+    /// Rotates the prefab by `quarter_turns` times 90° counterclockwise around `axis`
+    /// (seen from its positive end), negative turns rotate clockwise.
+    pub fn rotated(&self, axis: Axis, quarter_turns: i32) -> Prefab {
+        self.transformed(Symmetry::rotation(axis, quarter_turns))
+    }
+
+    /// Mirrors the prefab along `axis`.
+    pub fn mirrored(&self, axis: Axis) -> Prefab {
+        self.transformed(Symmetry::mirror(axis))
+    }
+}
+
+impl ChunkWorld {
+    /// Captures the region between the corners `min` and `max`, both inclusive.
+    /// Faces and edges that reach out of the region are left out.
+    ///
+    /// Panics if the region is more than `i8::MAX + 1` corners long in any direction.
+    pub fn capture(&self, min: WorldPos, max: WorldPos) -> Prefab {
+        let local =
+            |value: isize| -> i8 { value.try_into().expect("region too large for a prefab") };
+        let mut prefab = Prefab {
+            max: Pos::new(
+                local(max.0 - min.0),
+                local(max.1 - min.1),
+                local(max.2 - min.2),
+            ),
+            ..Default::default()
+        };
+        let world_pos = |pos: Pos| {
+            (
+                min.0 + pos.x as isize,
+                min.1 + pos.y as isize,
+                min.2 + pos.z as isize,
+            )
+        };
+        for x in 0..=prefab.max.x {
+            for y in 0..=prefab.max.y {
+                for z in 0..=prefab.max.z {
+                    let pos = Pos::new(x, y, z);
+                    let mut voxel = self.voxel(world_pos(pos));
+                    for axis in AXES {
+                        if !prefab.contains_side(pos, axis) {
+                            *voxel.side_mut(axis) = None;
+                        }
+                    }
+                    if !prefab.contains_inner(pos) {
+                        voxel.inner = None;
+                    }
+                    if voxel != Voxel::default() {
+                        prefab.voxels.insert(pos, voxel);
+                    }
+                    let edges: Vec<Edge> = self
+                        .edges(world_pos(pos))
+                        .iter()
+                        .filter(|edge| prefab.contains_edge(pos, edge))
+                        .copied()
+                        .collect();
+                    if !edges.is_empty() {
+                        prefab.edges.insert(pos, edges);
+                    }
+                }
+            }
+        }
+        prefab
+    }
+
+    /// The edit that pastes the prefab with its `Pos(0,0,0)` at `at`.
+    /// Only loaded sections are written.
+    ///
+    /// Faces and edges of the world that reach out of the region are kept in both modes, like `capture`
+    /// leaves them out, so pasting a captured region back onto itself changes nothing.
+    pub fn paste_edit(&self, prefab: &Prefab, at: WorldPos, mode: PasteMode) -> WorldEdit {
+        let mut edits: HashMap<_, Vec<ChunkEdit>> = HashMap::new();
+        for x in 0..=prefab.max.x {
+            for y in 0..=prefab.max.y {
+                for z in 0..=prefab.max.z {
+                    let local = Pos::new(x, y, z);
+                    let world_pos = (at.0 + x as isize, at.1 + y as isize, at.2 + z as isize);
+                    let (chunk_pos, pos) = from_world_pos(world_pos);
+                    if !self.chunks.contains_key(&chunk_pos) {
+                        continue;
+                    }
+                    let chunk_edits = edits.entry(chunk_pos).or_default();
+
+                    let old = self.voxel(world_pos);
+                    let new = prefab.voxel(local);
+                    let voxel = match mode {
+                        PasteMode::Overwrite => {
+                            let mut voxel = new;
+                            for axis in AXES {
+                                if !prefab.contains_side(local, axis) {
+                                    *voxel.side_mut(axis) = old.side(axis);
+                                }
+                            }
+                            if !prefab.contains_inner(local) {
+                                voxel.inner = old.inner;
+                            }
+                            voxel
+                        }
+                        PasteMode::Merge => Voxel {
+                            x_side: new.x_side.or(old.x_side),
+                            y_side: new.y_side.or(old.y_side),
+                            z_side: new.z_side.or(old.z_side),
+                            inner: new.inner.or(old.inner),
+                            corner: new.corner.max(old.corner),
+                        },
+                    };
+                    if voxel != old {
+                        chunk_edits.push(ChunkEdit::SetVoxel { pos, voxel });
+                    }
+
+                    let old_edges = self.edges(world_pos);
+                    let new_edges = prefab
+                        .edges
+                        .get(&local)
+                        .map(|e| e.as_slice())
+                        .unwrap_or_default();
+                    if mode == PasteMode::Overwrite {
+                        chunk_edits.extend(
+                            old_edges
+                                .iter()
+                                .filter(|edge| {
+                                    prefab.contains_edge(local, edge) && !new_edges.contains(edge)
+                                })
+                                .map(|edge| ChunkEdit::RemoveEdge { pos, edge: *edge }),
+                        );
+                    }
+                    chunk_edits.extend(
+                        new_edges
+                            .iter()
+                            .filter(|edge| !old_edges.contains(edge))
+                            .map(|edge| ChunkEdit::AddEdge { pos, edge: *edge }),
+                    );
+                }
+            }
+        }
+        let mut edits: Vec<_> = edits
+            .into_iter()
+            .filter(|(_, edits)| !edits.is_empty())
+            .collect();
+        edits.sort_by_key(|(chunk_pos, _)| *chunk_pos);
+        WorldEdit::Batch(
+            edits
+                .into_iter()
+                .map(|(chunk_pos, edits)| WorldEdit::Chunk(chunk_pos, ChunkEdit::Batch(edits)))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{
+        examples::flat_ground,
+        voxel::{Corner, EdgeKind, Matter},
+        Chunk,
+    };
+
+    fn test_world() -> ChunkWorld {
+        let mut world = ChunkWorld::default();
+        let mut chunk = flat_ground((0, 0, 0), 4);
+        // a pillar with sides on every voxel, crossing the far boundary of the captured region:
+        for y in 5..12 {
+            chunk.set_voxel(
+                Pos::new(6, y, 6),
+                Voxel::new(
+                    Some(Matter::Wood),
+                    Some(Matter::Wood),
+                    Some(Matter::Wood),
+                    Corner::Strong,
+                ),
+            );
+        }
+        chunk.voxels.get_mut(&Pos::new(6, 8, 6)).unwrap().inner = Some(Matter::Lamp);
+        // edges that end inside of the region and outside of it:
+        chunk.edges.insert(
+            Pos::new(6, 8, 6),
+            vec![
+                Edge::new(EdgeKind::Y, Some(Matter::Wood)),
+                Edge::new(EdgeKind::X, None),
+            ],
+        );
+        world.chunks.insert((0, 0, 0), chunk);
+        world
+    }
+
+    #[test]
+    fn paste_onto_itself_changes_nothing() {
+        let world = test_world();
+        let (min, max) = ((2, 2, 2), (6, 8, 6));
+        let prefab = world.capture(min, max);
+        // faces and edges crossing the boundary at x, y and z = 6 or y = 8 are not captured:
+        assert_eq!(prefab.voxel(Pos::new(4, 6, 4)).x_side, None);
+        assert_eq!(prefab.voxel(Pos::new(4, 6, 4)).inner, None);
+        assert!(prefab.edges.is_empty());
+
+        for mode in [PasteMode::Overwrite, PasteMode::Merge] {
+            let edit = world.paste_edit(&prefab, min, mode);
+            assert_eq!(edit, WorldEdit::Batch(vec![]), "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn overwrite_clears_inside_and_keeps_outside() {
+        let mut world = test_world();
+        let empty = Prefab {
+            max: Pos::new(4, 6, 4),
+            ..Default::default()
+        };
+        world
+            .paste_edit(&empty, (2, 2, 2), PasteMode::Overwrite)
+            .apply(&mut world);
+        let chunk = &world.chunks[&(0, 0, 0)];
+        // the ground inside of the region is cleared:
+        assert_eq!(chunk.get_voxel(Pos::new(3, 4, 3)), Voxel::default());
+        // the boundary voxel keeps the faces and edges that reach out of the region:
+        let boundary = chunk.get_voxel(Pos::new(6, 8, 6));
+        assert_eq!(boundary.corner, Corner::Air);
+        assert_eq!(boundary.x_side, Some(Matter::Wood));
+        assert_eq!(boundary.y_side, Some(Matter::Wood));
+        assert_eq!(boundary.inner, Some(Matter::Lamp));
+        assert_eq!(
+            chunk.edges[&Pos::new(6, 8, 6)],
+            vec![
+                Edge::new(EdgeKind::Y, Some(Matter::Wood)),
+                Edge::new(EdgeKind::X, None)
+            ]
+        );
+    }
+
+    /// An L shaped pillar: Wood sides from y = 10 to 13 at x/z = 10, an arm towards +x at y = 12
+    /// and a beam along the arm on top of the pillar.
+    fn pillar_world() -> ChunkWorld {
+        let mut world = ChunkWorld::default();
+        let mut chunk = Chunk::default();
+        let wood = Some(Matter::Wood);
+        for y in 10..=13 {
+            chunk.set_voxel(
+                Pos::new(10, y, 10),
+                Voxel::new(wood, None, None, Corner::Strong),
+            );
+        }
+        chunk.set_voxel(
+            Pos::new(11, 12, 10),
+            Voxel::new(None, Some(Matter::Dirt), None, Corner::Strong),
+        );
+        chunk
+            .edges
+            .insert(Pos::new(10, 13, 10), vec![Edge::new(EdgeKind::X, wood)]);
+        world.chunks.insert((0, 0, 0), chunk);
+        world
+    }
+
+    #[test]
+    fn rotated_prefab_is_pasted_rotated() {
+        let mut world = pillar_world();
+        let prefab = world.capture((9, 9, 9), (12, 14, 12));
+        assert_eq!(prefab.max, Pos::new(3, 5, 3));
+        // (x, y, z) turns to (z, y, -x), so the arm points towards -z:
+        let rotated = prefab.rotated(Axis::Y, 1);
+        assert_eq!(rotated.max, Pos::new(3, 5, 3));
+        let at = (20, 9, 20);
+        world
+            .paste_edit(&rotated, at, PasteMode::Overwrite)
+            .apply(&mut world);
+        assert_eq!(world.capture(at, (23, 14, 23)), rotated);
+
+        // the pillar moved from local x/z (1, 1) to (1, 2):
+        let corner = |world: &ChunkWorld, pos: WorldPos| world.voxel(pos).corner;
+        assert_eq!(corner(&world, (21, 11, 22)), Corner::Strong);
+        assert_eq!(corner(&world, (21, 12, 21)), Corner::Strong);
+        assert_eq!(corner(&world, (22, 12, 22)), Corner::Air);
+        // the x sides of the pillar are z sides now, the beam points from the arm back to the pillar:
+        assert_eq!(world.voxel((21, 11, 22)).z_side, Some(Matter::Wood));
+        assert_eq!(world.voxel((21, 11, 22)).x_side, None);
+        assert_eq!(
+            world.edges((21, 13, 21)),
+            &[Edge::new(EdgeKind::Z, Some(Matter::Wood))]
+        );
+
+        // mirrored along x, the arm points towards -x:
+        let mirrored = prefab.mirrored(Axis::X);
+        let at = (0, 9, 20);
+        world
+            .paste_edit(&mirrored, at, PasteMode::Overwrite)
+            .apply(&mut world);
+        assert_eq!(world.capture(at, (3, 14, 23)), mirrored);
+        assert_eq!(corner(&world, (2, 11, 21)), Corner::Strong);
+        assert_eq!(corner(&world, (1, 12, 21)), Corner::Strong);
+        assert_eq!(corner(&world, (3, 12, 21)), Corner::Air);
+    }
+
+    #[test]
+    fn merge_never_weakens_corners() {
+        let mut world = pillar_world();
+        let mut prefab = Prefab {
+            max: Pos::new(1, 0, 0),
+            ..Default::default()
+        };
+        for x in 0..=1 {
+            prefab.voxels.insert(
+                Pos::new(x, 0, 0),
+                Voxel::new(None, None, None, Corner::Weak),
+            );
+        }
+        // the first corner lands on the pillar, the second one in the air next to it:
+        world
+            .paste_edit(&prefab, (10, 11, 10), PasteMode::Merge)
+            .apply(&mut world);
+        assert_eq!(world.voxel((10, 11, 10)).corner, Corner::Strong);
+        assert_eq!(world.voxel((10, 11, 10)).x_side, Some(Matter::Wood));
+        assert_eq!(world.voxel((11, 11, 10)).corner, Corner::Weak);
+    }
+}
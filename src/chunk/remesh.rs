@@ -0,0 +1,171 @@
+use bevy::{
+    prelude::Mesh,
+    utils::{HashMap, HashSet},
+};
+
+use super::{
+    ir::{ChunkIR, MeshBuffers, Mesher},
+    pos::Pos,
+    Chunk,
+};
+
+/// Edge length of the cubic regions a chunk is remeshed in.
+pub const REGION_SIZE: i8 = 8;
+
+/// The region a voxel belongs to, as region indices (voxel pos divided by REGION_SIZE).
+pub fn region_of(pos: Pos) -> Pos {
+    Pos::new(
+        pos.x.div_euclid(REGION_SIZE),
+        pos.y.div_euclid(REGION_SIZE),
+        pos.z.div_euclid(REGION_SIZE),
+    )
+}
+
+fn region_min(region: Pos) -> Pos {
+    Pos::new(
+        region.x * REGION_SIZE,
+        region.y * REGION_SIZE,
+        region.z * REGION_SIZE,
+    )
+}
+
+#[derive(Debug, Clone, Default)]
+struct Region {
+    ir: ChunkIR,
+    buffers: MeshBuffers,
+}
+
+/// ChunkIR and mesh buffers of a chunk, cached per REGION_SIZE³ region.
+///
+/// After the chunk is modified, `update` only rebuilds the regions containing dirty voxels
+/// and splices them back into the combined ChunkIR.
+/// `Mesher::DualContouring` always rebuilds the whole chunk as a single region,
+/// because every cell vertex depends on the corners around its cell.
+#[derive(Debug, Clone, Default)]
+pub struct CachedChunkIR {
+    regions: HashMap<Pos, Region>,
+    ir: ChunkIR,
+    mesher: Mesher,
+}
+
+impl CachedChunkIR {
+    pub fn construct_from_chunk(chunk: &mut Chunk) -> Self {
+        CachedChunkIR::construct_with_mesher(chunk, Mesher::default())
+    }
+
+    pub fn construct_with_mesher(chunk: &mut Chunk, mesher: Mesher) -> Self {
+        let mut cached = CachedChunkIR {
+            mesher,
+            ..Default::default()
+        };
+        match mesher {
+            Mesher::Corners => {
+                let regions: HashSet<Pos> = chunk.voxels.keys().map(|p| region_of(*p)).collect();
+                for region in regions {
+                    cached.rebuild_region(chunk, region);
+                }
+            }
+            Mesher::DualContouring => cached.rebuild_whole(chunk),
+        }
+        chunk.dirty.clear();
+        cached.splice();
+        cached
+    }
+
+    /// Rebuilds all regions affected by the dirty voxels of the chunk and clears them.
+    /// Returns false if nothing was dirty.
+    pub fn update(&mut self, chunk: &mut Chunk) -> bool {
+        if chunk.dirty.is_empty() {
+            return false;
+        }
+        match self.mesher {
+            Mesher::Corners => {
+                for region in dirty_regions(&chunk.dirty) {
+                    self.rebuild_region(chunk, region);
+                }
+            }
+            Mesher::DualContouring => self.rebuild_whole(chunk),
+        }
+        chunk.dirty.clear();
+        self.splice();
+        true
+    }
+
+    pub fn mesher(&self) -> Mesher {
+        self.mesher
+    }
+
+    pub fn ir(&self) -> &ChunkIR {
+        &self.ir
+    }
+
+    /// Builds the mesh out of the cached per region buffers.
+    pub fn construct_mesh(&self) -> Mesh {
+        let mut buffers = MeshBuffers::default();
+        for region in self.sorted_regions() {
+            buffers.extend(&region.buffers);
+        }
+        buffers.into_mesh()
+    }
+
+    fn rebuild_region(&mut self, chunk: &Chunk, region: Pos) {
+        let ir = ChunkIR::construct_from_region(chunk, region_min(region), REGION_SIZE);
+        if ir.quads.is_empty()
+            && ir.triags.is_empty()
+            && ir.edges.is_empty()
+            && ir.dual_quads.is_empty()
+        {
+            self.regions.remove(&region);
+            return;
+        }
+        let buffers = ir.construct_mesh_buffers();
+        self.regions.insert(region, Region { ir, buffers });
+    }
+
+    fn rebuild_whole(&mut self, chunk: &Chunk) {
+        self.regions.clear();
+        let ir = ChunkIR::construct(chunk, self.mesher);
+        let buffers = ir.construct_mesh_buffers();
+        self.regions.insert(Pos::ZERO, Region { ir, buffers });
+    }
+
+    fn splice(&mut self) {
+        let mut ir = ChunkIR::default();
+        for region in self.sorted_regions() {
+            ir.extend(&region.ir);
+        }
+        self.ir = ir;
+    }
+
+    /// regions in a stable order, so the mesh layout does not depend on hashing.
+    fn sorted_regions(&self) -> Vec<&Region> {
+        let mut keys: Vec<&Pos> = self.regions.keys().collect();
+        keys.sort();
+        keys.into_iter().map(|k| &self.regions[k]).collect()
+    }
+}
+
+/// A face owned by voxel `o` spans the corners of `o` and its +x/+y/+z neighbours,
+/// its ambient occlusion also looks one corner further in every direction.
+/// So a dirty voxel also dirties the voxels up to two steps in the negative
+/// and one step in the positive directions.
+fn dirty_regions(dirty: &HashSet<Pos>) -> HashSet<Pos> {
+    let mut regions = HashSet::new();
+    for pos in dirty.iter() {
+        for dx in -1..=2 {
+            for dy in -1..=2 {
+                for dz in -1..=2 {
+                    let (Some(x), Some(y), Some(z)) = (
+                        pos.x.checked_sub(dx),
+                        pos.y.checked_sub(dy),
+                        pos.z.checked_sub(dz),
+                    ) else {
+                        continue;
+                    };
+                    regions.insert(region_of(Pos::new(x, y, z)));
+                }
+            }
+        }
+    }
+    regions
+}
@@ -0,0 +1,174 @@
+use bevy::prelude::Vec3;
+
+use super::{
+    light::Light,
+    pos::{Axis, Pos},
+    voxel::Corner,
+    Chunk, ChunkPos, ChunkWorld, CHUNK_SIZE,
+};
+
+/// Chunks are split into cubic vertical sections, each stored as its own `Chunk` in the ChunkWorld.
+pub const SECTION_HEIGHT: u8 = CHUNK_SIZE;
+
+/// Number of sections stacked on top of each other to form one 256 voxels high chunk column.
+pub const SECTIONS_PER_COLUMN: isize = 256 / SECTION_HEIGHT as isize;
+
+/// World space position of the voxel at Pos(0,0,0) of the section.
+pub fn section_origin(chunk_pos: ChunkPos) -> Vec3 {
+    let size = CHUNK_SIZE as f32;
+    Vec3::new(
+        chunk_pos.0 as f32 * size,
+        chunk_pos.1 as f32 * SECTION_HEIGHT as f32,
+        chunk_pos.2 as f32 * size,
+    )
+}
+
+/// Order of the sides returned by `Chunk::solid_sides`.
+pub const SIDES: [(Axis, bool); 6] = [
+    (Axis::X, false),
+    (Axis::X, true),
+    (Axis::Y, false),
+    (Axis::Y, true),
+    (Axis::Z, false),
+    (Axis::Z, true),
+];
+
+impl Chunk {
+    /// For every side in `SIDES` order, true if every voxel of the outermost layer on that side has
+    /// a Strong corner and a face along the axis of the side, so the layer is covered by full squares.
+    /// Nothing behind a solid side can be seen through it.
+    ///
+    /// Strong corners alone are not enough, voxels without faces are not drawn and can be seen through.
+    /// Full chunks are the exception, all their sides are solid: they have no faces themselves,
+    /// but everything around them that can be seen is drawn by their neighbours.
+    /// The dual contouring mesher places its faces between the corners, so its meshes are only culled
+    /// conservatively.
+    pub fn solid_sides(&self) -> [bool; 6] {
+        if self.is_full() {
+            return [true; 6];
+        }
+        let last = CHUNK_SIZE as i8 - 1;
+        SIDES.map(|(axis, positive)| {
+            let layer = if positive { last } else { 0 };
+            (0..=last).all(|a| {
+                (0..=last).all(|b| {
+                    let pos = match axis {
+                        Axis::X => Pos::new(layer, a, b),
+                        Axis::Y => Pos::new(a, layer, b),
+                        Axis::Z => Pos::new(a, b, layer),
+                    };
+                    let voxel = self.get_voxel(pos);
+                    voxel.corner.strong() && voxel.side(axis).is_some()
+                })
+            })
+        })
+    }
+}
+
+impl ChunkWorld {
+    /// All existing sections of the column at x/z, from bottom to top.
+    pub fn column(&self, x: isize, z: isize) -> impl Iterator<Item = (ChunkPos, &Chunk)> {
+        (0..SECTIONS_PER_COLUMN).filter_map(move |y| {
+            let chunk_pos = (x, y, z);
+            self.chunks.get(&chunk_pos).map(|c| (chunk_pos, c))
+        })
+    }
+
+    /// Copies the corners and light of the neighbouring chunks in the shell around the chunk at `chunk_pos`
+    /// into its `Chunk::border` and `Chunk::border_light`, see there for its extent.
+    /// Voxels whose border corners or light changed are marked dirty.
+    pub fn sync_border(&mut self, chunk_pos: ChunkPos) {
+        let Some(chunk) = self.chunks.get(&chunk_pos) else {
+            return;
+        };
+        let size = CHUNK_SIZE as i8;
+        let mut border = chunk.border.clone();
+        let mut border_light = chunk.border_light.clone();
+        let mut changed: Vec<Pos> = vec![];
+
+        for x in -1..=size + 1 {
+            for y in -1..=size + 1 {
+                for z in -1..=size + 1 {
+                    let pos = Pos::new(x, y, z);
+                    if Chunk::in_bounds(pos) {
+                        continue;
+                    }
+                    let neighbor_pos = (
+                        chunk_pos.0 + x.div_euclid(size) as isize,
+                        chunk_pos.1 + y.div_euclid(size) as isize,
+                        chunk_pos.2 + z.div_euclid(size) as isize,
+                    );
+                    let local =
+                        Pos::new(x.rem_euclid(size), y.rem_euclid(size), z.rem_euclid(size));
+                    let (corner, light) = match self.chunks.get(&neighbor_pos) {
+                        Some(n) => (n.get_voxel_corner(&local), n.get_light(local)),
+                        None if neighbor_pos.1 >= SECTIONS_PER_COLUMN => (Corner::Air, Light::SKY),
+                        None => (Corner::Air, Light::default()),
+                    };
+
+                    let old = if corner.air() {
+                        border.remove(&pos)
+                    } else {
+                        border.insert(pos, corner)
+                    };
+                    let old_light = if light == Light::default() {
+                        border_light.remove(&pos)
+                    } else {
+                        border_light.insert(pos, light)
+                    };
+                    if old.unwrap_or_default() != corner || old_light.unwrap_or_default() != light {
+                        changed.push(pos);
+                    }
+                }
+            }
+        }
+
+        let chunk = self.chunks.get_mut(&chunk_pos).expect("checked above");
+        chunk.border = border;
+        chunk.border_light = border_light;
+        chunk.dirty.extend(changed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{examples::flat_ground, voxel::Matter};
+
+    #[test]
+    fn faceless_layers_are_not_solid() {
+        assert_eq!(Chunk::default().solid_sides(), [false; 6]);
+        // full chunks block the view nonetheless:
+        assert_eq!(Chunk::full().solid_sides(), [true; 6]);
+        // the faces of the ground are inside of the section, not on its outermost layers:
+        assert_eq!(flat_ground((0, 0, 0), 8).solid_sides(), [false; 6]);
+    }
+
+    #[test]
+    fn covered_layer_is_solid() {
+        let mut chunk = Chunk::full();
+        for a in 0..CHUNK_SIZE as i8 {
+            for b in 0..CHUNK_SIZE as i8 {
+                let pos = Pos::new(a, 0, b);
+                let mut voxel = chunk.get_voxel(pos);
+                voxel.y_side = Some(Matter::Dirt);
+                chunk.set_voxel(pos, voxel);
+            }
+        }
+        let solid = chunk.solid_sides();
+        assert_eq!(solid, [false, false, true, false, false, false]);
+
+        // a single voxel without its face opens the layer:
+        let pos = Pos::new(5, 0, 7);
+        let mut voxel = chunk.get_voxel(pos);
+        voxel.y_side = None;
+        chunk.set_voxel(pos, voxel);
+        assert_eq!(chunk.solid_sides(), [false; 6]);
+
+        // as does a corner that is not Strong:
+        voxel.y_side = Some(Matter::Dirt);
+        voxel.corner = Corner::Weak;
+        chunk.set_voxel(pos, voxel);
+        assert_eq!(chunk.solid_sides(), [false; 6]);
+    }
+}
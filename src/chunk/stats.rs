@@ -0,0 +1,259 @@
+//! Size and cost of chunk meshes, to spot regressions of the meshers.
+//!
+//! `MeshStats::measure` meshes a chunk from scratch and times every phase, `bin/meshstats` prints
+//! the report for the example chunks and `bevy::diagnostics` reports the section meshes of a running app.
+
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use bevy::prelude::Mesh;
+
+use super::{
+    ir::{ChunkIR, Mesher},
+    lut::{side_from_index, side_index, SIDE_CONFIGURATIONS},
+    pos::Axis,
+    voxel::Corner,
+    Chunk,
+};
+
+/// Counts, sizes and timings of one or more chunk meshes, sum them up with `add`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshStats {
+    /// number of meshes summed up.
+    pub meshes: usize,
+    pub quads: usize,
+    pub triags: usize,
+    pub edges: usize,
+    pub dual_quads: usize,
+    /// see `ChunkIR::triangle_count`.
+    pub triangles: usize,
+    /// vertices and indices of the meshes. Faces are double sided and do not share vertices.
+    pub vertices: usize,
+    pub indices: usize,
+    /// size of all vertex attributes and indices.
+    pub bytes: usize,
+    /// how often the side of each configuration in `0..SIDE_CONFIGURATIONS` was looked up,
+    /// see `lut::side_index`. Only filled by `measure` with `Mesher::Corners`.
+    pub side_configurations: [usize; SIDE_CONFIGURATIONS],
+    /// building the ChunkIR, including the ambient occlusion, light and matter weights of its faces.
+    pub ir_time: Duration,
+    /// building the mesh buffers out of the ChunkIR.
+    pub buffer_time: Duration,
+    /// building the bevy Mesh out of the buffers.
+    pub mesh_time: Duration,
+}
+
+impl Default for MeshStats {
+    fn default() -> Self {
+        MeshStats {
+            meshes: 0,
+            quads: 0,
+            triags: 0,
+            edges: 0,
+            dual_quads: 0,
+            triangles: 0,
+            vertices: 0,
+            indices: 0,
+            bytes: 0,
+            side_configurations: [0; SIDE_CONFIGURATIONS],
+            ir_time: Duration::ZERO,
+            buffer_time: Duration::ZERO,
+            mesh_time: Duration::ZERO,
+        }
+    }
+}
+
+impl MeshStats {
+    /// Meshes the chunk from scratch, the border has to be up to date like for `ChunkIR::construct`.
+    pub fn measure(chunk: &Chunk, mesher: Mesher) -> (Mesh, MeshStats) {
+        let start = Instant::now();
+        let ir = ChunkIR::construct(chunk, mesher);
+        let ir_time = start.elapsed();
+
+        let start = Instant::now();
+        let buffers = ir.construct_mesh_buffers();
+        let buffer_time = start.elapsed();
+
+        let start = Instant::now();
+        let mesh = buffers.into_mesh();
+        let mesh_time = start.elapsed();
+
+        let mut stats = MeshStats::count(&ir, &mesh);
+        if mesher == Mesher::Corners {
+            stats.side_configurations = side_configurations(chunk);
+        }
+        stats.ir_time = ir_time;
+        stats.buffer_time = buffer_time;
+        stats.mesh_time = mesh_time;
+        (mesh, stats)
+    }
+
+    /// Counts the faces of the ChunkIR and the size of the mesh built from it, without timings.
+    pub fn count(ir: &ChunkIR, mesh: &Mesh) -> MeshStats {
+        let attribute_bytes: usize = mesh
+            .attributes()
+            .map(|(_, values)| values.get_bytes().len())
+            .sum();
+        let index_bytes = mesh.get_index_buffer_bytes().map_or(0, |bytes| bytes.len());
+        MeshStats {
+            meshes: 1,
+            quads: ir.quads.len(),
+            triags: ir.triags.len(),
+            edges: ir.edges.len(),
+            dual_quads: ir.dual_quads.len(),
+            triangles: ir.triangle_count(),
+            vertices: mesh.count_vertices(),
+            indices: mesh.indices().map_or(0, |indices| indices.len()),
+            bytes: attribute_bytes + index_bytes,
+            ..Default::default()
+        }
+    }
+
+    /// Adds the counts and timings of `other` to these.
+    pub fn add(&mut self, other: &MeshStats) {
+        self.meshes += other.meshes;
+        self.quads += other.quads;
+        self.triags += other.triags;
+        self.edges += other.edges;
+        self.dual_quads += other.dual_quads;
+        self.triangles += other.triangles;
+        self.vertices += other.vertices;
+        self.indices += other.indices;
+        self.bytes += other.bytes;
+        for (count, other) in self
+            .side_configurations
+            .iter_mut()
+            .zip(other.side_configurations)
+        {
+            *count += other;
+        }
+        self.ir_time += other.ir_time;
+        self.buffer_time += other.buffer_time;
+        self.mesh_time += other.mesh_time;
+    }
+
+    pub fn total_time(&self) -> Duration {
+        self.ir_time + self.buffer_time + self.mesh_time
+    }
+}
+
+/// The configurations of all sides the corner mesher looks up, like `ChunkIR::construct_from_chunk`.
+fn side_configurations(chunk: &Chunk) -> [usize; SIDE_CONFIGURATIONS] {
+    let mut counts = [0; SIDE_CONFIGURATIONS];
+    for (pos, voxel) in chunk.voxels.iter() {
+        if voxel.corner.air() {
+            continue;
+        }
+        let corner_info = chunk.get_voxel_corner_info(*pos, voxel.corner);
+        for axis in [Axis::X, Axis::Y, Axis::Z] {
+            if voxel.side(axis).is_some() {
+                counts[side_index(axis, &corner_info.cor)] += 1;
+            }
+        }
+    }
+    counts
+}
+
+/// The report printed by `bin/meshstats`: all counts, the timings per phase and the side configurations
+/// from the most to the least frequent, with the corners of the plane as A, W and S in the order of
+/// `lut::plane_corners`.
+impl fmt::Display for MeshStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "meshes:      {}", self.meshes)?;
+        writeln!(
+            f,
+            "faces:       {} quads, {} triags, {} edges, {} dual quads",
+            self.quads, self.triags, self.edges, self.dual_quads
+        )?;
+        writeln!(f, "triangles:   {}", self.triangles)?;
+        writeln!(
+            f,
+            "buffers:     {} vertices, {} indices, {} bytes",
+            self.vertices, self.indices, self.bytes
+        )?;
+        writeln!(
+            f,
+            "time:        {:?} ir, {:?} buffers, {:?} mesh, {:?} total",
+            self.ir_time,
+            self.buffer_time,
+            self.mesh_time,
+            self.total_time()
+        )?;
+        let mut configurations: Vec<(usize, usize)> = self
+            .side_configurations
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, count)| *count > 0)
+            .collect();
+        configurations.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        write!(f, "sides:       {} configurations", configurations.len())?;
+        for (index, count) in configurations {
+            let corners: String = side_from_index(index).map(Corner::to_char).iter().collect();
+            write!(f, "\n  {corners} {count}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::examples::{solid_cube, solid_cube_weak_corner};
+
+    fn count(chunk: &Chunk) -> MeshStats {
+        let ir = ChunkIR::construct(chunk, Mesher::Corners);
+        let mesh = ir.construct_mesh_buffers().into_mesh();
+        MeshStats::count(&ir, &mesh)
+    }
+
+    #[test]
+    fn count_faces() {
+        let stats = count(&solid_cube_weak_corner());
+        assert_eq!(stats.meshes, 1);
+        assert!(stats.quads > 0 && stats.triags > 0, "{stats:?}");
+        assert_eq!(stats.triangles, 2 * stats.quads + stats.triags);
+        assert!(stats.vertices > 0 && stats.indices >= 3 * stats.triangles);
+        assert!(stats.bytes > 0);
+    }
+
+    #[test]
+    fn add_sums_up() {
+        let (_, a) = MeshStats::measure(&solid_cube(), Mesher::Corners);
+        let (_, b) = MeshStats::measure(&solid_cube_weak_corner(), Mesher::Corners);
+        let mut sum = MeshStats::default();
+        sum.add(&a);
+        sum.add(&b);
+        assert_eq!(sum.meshes, 2);
+        assert_eq!(sum.quads, a.quads + b.quads);
+        assert_eq!(sum.triags, a.triags + b.triags);
+        assert_eq!(sum.triangles, a.triangles + b.triangles);
+        assert_eq!(sum.vertices, a.vertices + b.vertices);
+        assert_eq!(sum.indices, a.indices + b.indices);
+        assert_eq!(sum.bytes, a.bytes + b.bytes);
+        for i in 0..SIDE_CONFIGURATIONS {
+            let counts = [&a, &b].map(|stats| stats.side_configurations[i]);
+            assert_eq!(sum.side_configurations[i], counts[0] + counts[1]);
+        }
+        assert_eq!(sum.total_time(), a.total_time() + b.total_time());
+    }
+
+    #[test]
+    fn configurations_are_printed_as_letters() {
+        let (_, stats) = MeshStats::measure(&solid_cube_weak_corner(), Mesher::Corners);
+        let report = stats.to_string();
+        let sides: Vec<&str> = report
+            .lines()
+            .skip_while(|l| !l.starts_with("sides:"))
+            .skip(1)
+            .collect();
+        assert!(!sides.is_empty(), "{report}");
+        for line in sides {
+            let corners = line.trim().split(' ').next().unwrap_or_default();
+            assert_eq!(corners.len(), 4, "{line}");
+            assert!(corners.chars().all(|c| "AWS".contains(c)), "{line}");
+        }
+    }
+}
@@ -0,0 +1,656 @@
+//! Structural integrity of solid corners.
+//!
+//! Every corner that is not Air can carry load. Load is carried from the ground, the lowest voxel
+//! layer of the world, up through neighbouring corners and along edges. Vertical steps are free,
+//! every horizontal step uses up one unit of the `Matter::strength` of the corners it passes,
+//! so a horizontal run of a matter reaches out `strength` voxels from the closest vertical support.
+//! Weak corners only have half the strength of their matter.
+//!
+//! Corners that can not be reached from the ground within these limits collapse.
+
+use std::collections::VecDeque;
+
+use bevy::utils::{HashMap, HashSet};
+
+use super::{
+    edit::{ChunkEdit, WorldEdit},
+    light::{from_world_pos, to_world_pos, WorldPos},
+    pos::Pos,
+    voxel::{Corner, Edge, Matter, Voxel},
+    Chunk, ChunkWorld,
+};
+
+/// Corners without any faces touching them, like the inside of solid terrain, are made of this.
+pub const UNFACED_MATTER: Matter = Matter::Dirt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructureSettings {
+    /// maximum number of corners visited per check. Corners at the edge of the visited region
+    /// are assumed to be supported, so large structures are only checked near the edit.
+    pub max_search: usize,
+    /// if true, unsupported islands are removed after an edit and fall down as `Debris`.
+    /// Otherwise they are only reported.
+    pub detach_debris: bool,
+}
+
+impl Default for StructureSettings {
+    fn default() -> Self {
+        Self {
+            max_search: 8192,
+            detach_debris: false,
+        }
+    }
+}
+
+/// Result of a structure check, with `WorldPos` for a ChunkWorld and `Pos` for a single Chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructureReport<P> {
+    /// all corners that would collapse, sorted.
+    pub collapsing: Vec<P>,
+    /// the collapsing corners, grouped into the connected pieces that would fall down together.
+    pub islands: Vec<Vec<P>>,
+    /// true if `StructureSettings::max_search` was reached before all connected corners were visited.
+    pub truncated: bool,
+}
+
+impl<P> StructureReport<P> {
+    /// true if nothing collapses.
+    pub fn is_stable(&self) -> bool {
+        self.collapsing.is_empty()
+    }
+}
+
+/// A detached island, stored as a standalone Chunk that can be meshed and animated on its own.
+#[derive(Debug, Clone)]
+pub struct Debris {
+    /// world position of `Pos(0,0,0)` of the chunk.
+    pub origin: WorldPos,
+    pub chunk: Chunk,
+}
+
+/// Read access to solid corners, shared by ChunkWorld and standalone Chunks.
+pub(super) trait Solids {
+    fn voxel(&self, world_pos: WorldPos) -> Voxel;
+
+    fn corner(&self, world_pos: WorldPos) -> Corner;
+
+    fn edges(&self, world_pos: WorldPos) -> &[Edge];
+
+    /// false if the content at the position is unknown, like sections that are not loaded.
+    fn loaded(&self, world_pos: WorldPos) -> bool;
+
+    /// All corners at least one edge starts at.
+    fn edge_starts(&self) -> Vec<WorldPos>;
+}
+
+impl Solids for ChunkWorld {
+    fn voxel(&self, world_pos: WorldPos) -> Voxel {
+        let (chunk_pos, pos) = from_world_pos(world_pos);
+        self.chunks
+            .get(&chunk_pos)
+            .map(|chunk| chunk.get_voxel(pos))
+            .unwrap_or_default()
+    }
+
+    fn corner(&self, world_pos: WorldPos) -> Corner {
+        self.voxel(world_pos).corner
+    }
+
+    fn edges(&self, world_pos: WorldPos) -> &[Edge] {
+        let (chunk_pos, pos) = from_world_pos(world_pos);
+        self.chunks
+            .get(&chunk_pos)
+            .and_then(|chunk| chunk.edges.get(&pos))
+            .map(|edges| edges.as_slice())
+            .unwrap_or_default()
+    }
+
+    fn loaded(&self, world_pos: WorldPos) -> bool {
+        world_pos.1 < 0 || self.chunks.contains_key(&from_world_pos(world_pos).0)
+    }
+
+    fn edge_starts(&self) -> Vec<WorldPos> {
+        self.chunks
+            .iter()
+            .flat_map(|(chunk_pos, chunk)| {
+                chunk.edges.keys().map(|pos| to_world_pos(*chunk_pos, *pos))
+            })
+            .collect()
+    }
+}
+
+/// A standalone chunk lies at the origin of the world, everything outside of it is Air.
+impl Solids for Chunk {
+    fn voxel(&self, world_pos: WorldPos) -> Voxel {
+        match to_pos(world_pos) {
+            Some(pos) if Chunk::in_bounds(pos) => self.get_voxel(pos),
+            _ => Voxel::default(),
+        }
+    }
+
+    fn corner(&self, world_pos: WorldPos) -> Corner {
+        self.voxel(world_pos).corner
+    }
+
+    fn edges(&self, world_pos: WorldPos) -> &[Edge] {
+        to_pos(world_pos)
+            .and_then(|pos| self.edges.get(&pos))
+            .map(|edges| edges.as_slice())
+            .unwrap_or_default()
+    }
+
+    fn loaded(&self, _world_pos: WorldPos) -> bool {
+        true
+    }
+
+    fn edge_starts(&self) -> Vec<WorldPos> {
+        self.edges.keys().map(|pos| from_pos(*pos)).collect()
+    }
+}
+
+pub(super) fn to_pos(world_pos: WorldPos) -> Option<Pos> {
+    Some(Pos::new(
+        world_pos.0.try_into().ok()?,
+        world_pos.1.try_into().ok()?,
+        world_pos.2.try_into().ok()?,
+    ))
+}
+
+pub(super) fn from_pos(pos: Pos) -> WorldPos {
+    (pos.x as isize, pos.y as isize, pos.z as isize)
+}
+
+pub(super) fn offset(world_pos: WorldPos, d: Pos) -> WorldPos {
+    (
+        world_pos.0 + d.x as isize,
+        world_pos.1 + d.y as isize,
+        world_pos.2 + d.z as isize,
+    )
+}
+
+pub(super) const NEIGHBORS: [Pos; 6] = [
+    Pos { x: -1, y: 0, z: 0 },
+    Pos { x: 1, y: 0, z: 0 },
+    Pos { x: 0, y: -1, z: 0 },
+    Pos { x: 0, y: 1, z: 0 },
+    Pos { x: 0, y: 0, z: -1 },
+    Pos { x: 0, y: 0, z: 1 },
+];
+
+/// A connection between two solid corners that load can be carried along.
+#[derive(Debug, Clone, Copy)]
+struct Link {
+    to: WorldPos,
+    /// strength used up by crossing the link, its horizontal length.
+    cost: u8,
+    /// the most load the link itself can carry, the strength of the edge matter for edges.
+    limit: u8,
+}
+
+fn horizontal_cost(d: Pos) -> u8 {
+    d.x.unsigned_abs() + d.z.unsigned_abs()
+}
+
+fn strength_of(matter: Option<Matter>) -> u8 {
+    matter.unwrap_or(UNFACED_MATTER).strength()
+}
+
+/// Strength of the corner at `world_pos`: the strength of the strongest matter of the sides
+/// and inner faces touching it, halved for Weak corners. 0 for Air.
+fn corner_strength(solids: &impl Solids, world_pos: WorldPos) -> u8 {
+    let corner = solids.corner(world_pos);
+    if corner.air() {
+        return 0;
+    }
+    let strength = touching_matters(solids, world_pos)
+        .into_iter()
+        .map(|matter| matter.strength())
+        .max()
+        .unwrap_or(UNFACED_MATTER.strength());
+    match corner {
+        Corner::Weak => strength / 2,
+        _ => strength,
+    }
+}
+
+/// The matters of the sides and inner faces of the voxels around `world_pos` that touch the corner.
+pub(super) fn touching_matters(solids: &impl Solids, world_pos: WorldPos) -> Vec<Matter> {
+    let mut matters: Vec<Matter> = vec![];
+    for dx in -1..=0 {
+        for dy in -1..=0 {
+            for dz in -1..=0 {
+                let voxel = solids.voxel(offset(world_pos, Pos::new(dx, dy, dz)));
+                // a side at a voxel covers the corners of the voxel on its plane:
+                if dx == 0 {
+                    matters.extend(voxel.x_side);
+                }
+                if dy == 0 {
+                    matters.extend(voxel.y_side);
+                }
+                if dz == 0 {
+                    matters.extend(voxel.z_side);
+                }
+                matters.extend(voxel.inner);
+            }
+        }
+    }
+    matters
+}
+
+/// The edges ending at every corner, built once per search. Edges are stored at the corner they start at,
+/// so otherwise every corner an edge could start at would have to be looked at for every visited corner.
+#[derive(Debug, Default)]
+pub(super) struct EdgeIndex(HashMap<WorldPos, Vec<(WorldPos, Edge)>>);
+
+impl EdgeIndex {
+    pub(super) fn new(solids: &impl Solids) -> Self {
+        let mut ends: HashMap<WorldPos, Vec<(WorldPos, Edge)>> = HashMap::new();
+        for start in solids.edge_starts() {
+            for edge in solids.edges(start) {
+                ends.entry(offset(start, edge.kind().delta()))
+                    .or_default()
+                    .push((start, *edge));
+            }
+        }
+        Self(ends)
+    }
+
+    /// The edges ending at `world_pos`, with the corners they start at.
+    pub(super) fn ending_at(&self, world_pos: WorldPos) -> &[(WorldPos, Edge)] {
+        self.0
+            .get(&world_pos)
+            .map(|ends| ends.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+/// All links from the solid corner at `world_pos` to its solid neighbours:
+/// the 6 direct neighbours and the other ends of the edges starting or ending at the corner.
+fn links(solids: &impl Solids, world_pos: WorldPos, edges: &EdgeIndex) -> Vec<Link> {
+    let mut links: Vec<Link> = NEIGHBORS
+        .iter()
+        .map(|d| Link {
+            to: offset(world_pos, *d),
+            cost: horizontal_cost(*d),
+            limit: u8::MAX,
+        })
+        .collect();
+    for edge in solids.edges(world_pos) {
+        let d = edge.kind().delta();
+        links.push(Link {
+            to: offset(world_pos, d),
+            cost: horizontal_cost(d),
+            limit: strength_of(edge.matter()),
+        });
+    }
+    for (start, edge) in edges.ending_at(world_pos) {
+        links.push(Link {
+            to: *start,
+            cost: horizontal_cost(edge.kind().delta()),
+            limit: strength_of(edge.matter()),
+        });
+    }
+    links
+}
+
+/// Checks which corners connected to the corners at or next to `around` would collapse.
+fn check(
+    solids: &impl Solids,
+    around: &[WorldPos],
+    settings: &StructureSettings,
+) -> StructureReport<WorldPos> {
+    let edges = EdgeIndex::new(solids);
+    let solid = |world_pos: WorldPos| solids.loaded(world_pos) && !solids.corner(world_pos).air();
+
+    // visit the connected corners breadth first, starting at the edited ones and their neighbours:
+    let mut index: HashMap<WorldPos, usize> = HashMap::new();
+    let mut nodes: Vec<WorldPos> = vec![];
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    for world_pos in around {
+        let seeds = std::iter::once(*world_pos)
+            .chain(links(solids, *world_pos, &edges).into_iter().map(|l| l.to));
+        for seed in seeds {
+            if solid(seed) && !index.contains_key(&seed) {
+                index.insert(seed, nodes.len());
+                queue.push_back(nodes.len());
+                nodes.push(seed);
+            }
+        }
+    }
+
+    let mut anchored: Vec<bool> = nodes.iter().map(|n| n.1 == 0).collect();
+    // links are stored in both directions, so corners at the edge of the visited region
+    // that are never expanded can still pass on their support:
+    let mut node_links: Vec<Vec<Link>> = vec![vec![]; nodes.len()];
+    let mut expanded: Vec<bool> = vec![false; nodes.len()];
+    let mut truncated = false;
+    while let Some(i) = queue.pop_front() {
+        if nodes.len() >= settings.max_search {
+            // the unvisited rest is assumed to hold everything at the edge of the visited region:
+            truncated = true;
+            anchored[i] = true;
+            for j in queue.drain(..) {
+                anchored[j] = true;
+            }
+            break;
+        }
+        expanded[i] = true;
+        for link in links(solids, nodes[i], &edges) {
+            if !solids.loaded(link.to) {
+                // we do not know what holds the corner, so do not let it collapse
+                anchored[i] = true;
+                continue;
+            }
+            if solids.corner(link.to).air() {
+                continue;
+            }
+            let j = match index.get(&link.to) {
+                Some(j) => *j,
+                None => {
+                    let j = nodes.len();
+                    index.insert(link.to, j);
+                    anchored.push(link.to.1 == 0);
+                    node_links.push(vec![]);
+                    expanded.push(false);
+                    queue.push_back(j);
+                    nodes.push(link.to);
+                    j
+                }
+            };
+            // links to expanded corners were added when those were expanded
+            if !expanded[j] {
+                node_links[j].push(Link {
+                    to: nodes[i],
+                    ..link
+                });
+                node_links[i].push(link);
+            }
+        }
+    }
+
+    // spread the support from the anchors, strongest first:
+    let strengths: Vec<u8> = nodes.iter().map(|n| corner_strength(solids, *n)).collect();
+    let max_strength = strengths.iter().copied().max().unwrap_or(0) as usize;
+    let mut support: Vec<Option<u8>> = vec![None; nodes.len()];
+    let mut buckets: Vec<Vec<usize>> = vec![vec![]; max_strength + 1];
+    for (i, anchor) in anchored.iter().enumerate() {
+        if *anchor {
+            support[i] = Some(strengths[i]);
+            buckets[strengths[i] as usize].push(i);
+        }
+    }
+    for level in (0..=max_strength).rev() {
+        while let Some(i) = buckets[level].pop() {
+            if support[i] != Some(level as u8) {
+                continue;
+            }
+            for link in node_links[i].iter() {
+                let j = index[&link.to];
+                let Some(carried) = (level as u8).checked_sub(link.cost) else {
+                    continue;
+                };
+                let carried = carried.min(link.limit).min(strengths[j]);
+                if support[j].is_none_or(|s| s < carried) {
+                    support[j] = Some(carried);
+                    buckets[carried as usize].push(j);
+                }
+            }
+        }
+    }
+
+    let mut collapsing: Vec<WorldPos> = nodes
+        .iter()
+        .zip(support.iter())
+        .filter(|(_, s)| s.is_none())
+        .map(|(n, _)| *n)
+        .collect();
+    collapsing.sort();
+
+    // group the collapsing corners into connected islands:
+    let falling: HashSet<WorldPos> = collapsing.iter().copied().collect();
+    let mut grouped: HashSet<WorldPos> = HashSet::new();
+    let mut islands: Vec<Vec<WorldPos>> = vec![];
+    for start in collapsing.iter() {
+        if !grouped.insert(*start) {
+            continue;
+        }
+        let mut island = vec![*start];
+        let mut stack = vec![*start];
+        while let Some(world_pos) = stack.pop() {
+            for link in node_links[index[&world_pos]].iter() {
+                if falling.contains(&link.to) && grouped.insert(link.to) {
+                    island.push(link.to);
+                    stack.push(link.to);
+                }
+            }
+        }
+        island.sort();
+        islands.push(island);
+    }
+
+    StructureReport {
+        collapsing,
+        islands,
+        truncated,
+    }
+}
+
+/// Copies the voxels and edges of the island into a Debris chunk.
+/// None if the island is too large to be stored in a single Chunk.
+fn debris(solids: &impl Solids, island: &[WorldPos]) -> Option<Debris> {
+    let first = island.first()?;
+    let mut min = *first;
+    for world_pos in island {
+        min = (
+            min.0.min(world_pos.0),
+            min.1.min(world_pos.1),
+            min.2.min(world_pos.2),
+        );
+    }
+    let mut chunk = Chunk::default();
+    for world_pos in island {
+        let pos = to_pos((
+            world_pos.0 - min.0,
+            world_pos.1 - min.1,
+            world_pos.2 - min.2,
+        ))?;
+        chunk.set_voxel(pos, solids.voxel(*world_pos));
+        let edges = solids.edges(*world_pos);
+        if !edges.is_empty() {
+            chunk.edges.insert(pos, edges.to_vec());
+        }
+    }
+    Some(Debris { origin: min, chunk })
+}
+
+/// Edits that remove the voxel and the edges at `world_pos`, which is `pos` inside of its chunk.
+pub(super) fn removal_edits(solids: &impl Solids, pos: Pos, world_pos: WorldPos) -> Vec<ChunkEdit> {
+    let mut edits = vec![ChunkEdit::SetVoxel {
+        pos,
+        voxel: Voxel::default(),
+    }];
+    edits.extend(
+        solids
+            .edges(world_pos)
+            .iter()
+            .map(|edge| ChunkEdit::RemoveEdge { pos, edge: *edge }),
+    );
+    edits
+}
+
+impl ChunkWorld {
+    /// Strength of the corner at the position, 0 for Air. See the module documentation.
+    pub fn corner_strength(&self, world_pos: WorldPos) -> u8 {
+        corner_strength(self, world_pos)
+    }
+
+    /// Finds the corners that would collapse after the voxels at `around` were modified.
+    /// Only corners connected to `around` are checked.
+    pub fn check_structure(
+        &self,
+        around: &[WorldPos],
+        settings: &StructureSettings,
+    ) -> StructureReport<WorldPos> {
+        check(self, around, settings)
+    }
+
+    /// The edit that removes all islands of the report, and the islands as debris.
+    /// Islands too large to fit into a Chunk are removed without debris.
+    pub fn detach_islands(&self, report: &StructureReport<WorldPos>) -> (WorldEdit, Vec<Debris>) {
+        let debris = report
+            .islands
+            .iter()
+            .filter_map(|island| debris(self, island))
+            .collect();
+        let edits = report
+            .collapsing
+            .iter()
+            .map(|world_pos| {
+                let (chunk_pos, pos) = from_world_pos(*world_pos);
+                WorldEdit::Chunk(
+                    chunk_pos,
+                    ChunkEdit::Batch(removal_edits(self, pos, *world_pos)),
+                )
+            })
+            .collect();
+        (WorldEdit::Batch(edits), debris)
+    }
+}
+
+impl Chunk {
+    /// Like `ChunkWorld::check_structure`, for a chunk that is not part of a ChunkWorld.
+    /// Its lowest layer at y = 0 is the ground, everything around it is Air.
+    pub fn check_structure(
+        &self,
+        around: &[Pos],
+        settings: &StructureSettings,
+    ) -> StructureReport<Pos> {
+        let around: Vec<WorldPos> = around.iter().map(|pos| from_pos(*pos)).collect();
+        let report = check(self, &around, settings);
+        let to_pos = |world_pos: &WorldPos| to_pos(*world_pos).expect("lies inside of the chunk");
+        StructureReport {
+            collapsing: report.collapsing.iter().map(to_pos).collect(),
+            islands: report
+                .islands
+                .iter()
+                .map(|island| island.iter().map(to_pos).collect())
+                .collect(),
+            truncated: report.truncated,
+        }
+    }
+
+    /// Like `ChunkWorld::detach_islands`, the debris origins are positions in the chunk.
+    pub fn detach_islands(&self, report: &StructureReport<Pos>) -> (ChunkEdit, Vec<Debris>) {
+        let debris = report
+            .islands
+            .iter()
+            .filter_map(|island| {
+                let island: Vec<WorldPos> = island.iter().map(|pos| from_pos(*pos)).collect();
+                debris(self, &island)
+            })
+            .collect();
+        let edits = report
+            .collapsing
+            .iter()
+            .flat_map(|pos| removal_edits(self, *pos, from_pos(*pos)))
+            .collect();
+        (ChunkEdit::Batch(edits), debris)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOP: i8 = 5;
+
+    fn strong() -> Voxel {
+        Voxel::new(None, None, None, Corner::Strong)
+    }
+
+    /// A pillar of faceless corners at x = z = 5 from the ground up to `TOP`,
+    /// with an overhang of `length` corners towards +x on top of it.
+    fn overhang(length: i8) -> Chunk {
+        let mut chunk = Chunk::default();
+        for y in 0..=TOP {
+            chunk.set_voxel(Pos::new(5, y, 5), strong());
+        }
+        for x in 6..6 + length {
+            chunk.set_voxel(Pos::new(x, TOP, 5), strong());
+        }
+        chunk
+    }
+
+    fn tip(length: i8) -> Pos {
+        Pos::new(5 + length, TOP, 5)
+    }
+
+    #[test]
+    fn long_overhang_breaks() {
+        let reach = UNFACED_MATTER.strength() as i8;
+        let settings = StructureSettings::default();
+        let chunk = overhang(reach);
+        assert!(chunk.check_structure(&[tip(reach)], &settings).is_stable());
+
+        let chunk = overhang(reach + 2);
+        let report = chunk.check_structure(&[tip(reach + 2)], &settings);
+        assert!(!report.truncated);
+        let expected = vec![tip(reach + 1), tip(reach + 2)];
+        assert_eq!(report.collapsing, expected);
+        assert_eq!(report.islands, vec![expected]);
+    }
+
+    #[test]
+    fn pillar_holds_overhang() {
+        let length = UNFACED_MATTER.strength() as i8 + 2;
+        let mut chunk = overhang(length);
+        let support = tip(length);
+        for y in 0..TOP {
+            chunk.set_voxel(Pos::new(support.x, y, support.z), strong());
+        }
+        let report = chunk.check_structure(&[tip(length)], &StructureSettings::default());
+        assert!(report.is_stable());
+    }
+
+    #[test]
+    fn search_is_bounded() {
+        let length = UNFACED_MATTER.strength() as i8 + 2;
+        let chunk = overhang(length);
+        let settings = StructureSettings {
+            max_search: 4,
+            ..Default::default()
+        };
+        // the pillar is never reached, the corners at the edge of the search are assumed to hold:
+        let report = chunk.check_structure(&[tip(length)], &settings);
+        assert!(report.truncated);
+        assert!(report.is_stable());
+    }
+
+    #[test]
+    fn floating_corners_are_detached() {
+        let length = UNFACED_MATTER.strength() as i8 + 2;
+        let mut chunk = overhang(length);
+        let report = chunk.check_structure(&[tip(length)], &StructureSettings::default());
+        let (edit, debris) = chunk.detach_islands(&report);
+
+        assert_eq!(debris.len(), 1);
+        let falling = tip(length - 1);
+        assert_eq!(debris[0].origin, from_pos(falling));
+        let mut pieces: Vec<Pos> = debris[0].chunk.voxels.keys().copied().collect();
+        pieces.sort();
+        assert_eq!(pieces, vec![Pos::new(0, 0, 0), Pos::new(1, 0, 0)]);
+
+        let before = chunk.voxels.clone();
+        edit.apply(&mut chunk);
+        for (pos, voxel) in before {
+            let expected = match report.collapsing.contains(&pos) {
+                true => Voxel::default(),
+                false => voxel,
+            };
+            assert_eq!(chunk.get_voxel(pos), expected, "{pos:?}");
+        }
+        assert!(chunk
+            .check_structure(&[tip(length - 2)], &StructureSettings::default())
+            .is_stable());
+    }
+}
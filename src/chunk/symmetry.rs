@@ -0,0 +1,458 @@
+//! The 48 symmetries of the voxel lattice: the 24 rotations of a cube and the 24 rotations combined with a reflection.
+//!
+//! A voxel owns the faces on the planes through its corner towards +x, +y and +z, and edges are stored
+//! at one of their two ends with a kind whose offset points into the positive half space.
+//! A symmetry can turn these around, so content is not transformed voxel by voxel: every face is transformed
+//! as a whole and stored at the voxel owning its new position, and edges that would point backwards
+//! are stored at their other end.
+
+use bevy::utils::HashMap;
+
+use super::{
+    pos::{Axis, Pos},
+    voxel::{Edge, EdgeKind, Voxel},
+    Chunk, CHUNK_SIZE,
+};
+
+/// A rotation or reflection that maps the lattice onto itself: coordinate `i` of a transformed position is
+/// `signs[i]` times coordinate `axes[i]` of the original position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symmetry {
+    axes: [usize; 3],
+    signs: [i8; 3],
+}
+
+const PERMUTATIONS: [[usize; 3]; 6] = [
+    [0, 1, 2],
+    [0, 2, 1],
+    [1, 0, 2],
+    [1, 2, 0],
+    [2, 0, 1],
+    [2, 1, 0],
+];
+
+impl Symmetry {
+    pub const IDENTITY: Symmetry = Symmetry {
+        axes: [0, 1, 2],
+        signs: [1, 1, 1],
+    };
+
+    /// All 48 symmetries, starting with the identity.
+    pub fn all() -> impl Iterator<Item = Symmetry> {
+        PERMUTATIONS.into_iter().flat_map(|axes| {
+            (0..8).map(move |i| Symmetry {
+                axes,
+                signs: [0, 1, 2].map(|bit| if i & (1 << bit) == 0 { 1 } else { -1 }),
+            })
+        })
+    }
+
+    /// The 24 symmetries that are proper rotations, without a reflection.
+    pub fn rotations() -> impl Iterator<Item = Symmetry> {
+        Symmetry::all().filter(|s| s.is_rotation())
+    }
+
+    /// 90° counterclockwise around `axis`, seen from the positive end of the axis.
+    pub fn quarter_turn(axis: Axis) -> Symmetry {
+        match axis {
+            Axis::X => Symmetry {
+                axes: [0, 2, 1],
+                signs: [1, -1, 1],
+            },
+            Axis::Y => Symmetry {
+                axes: [2, 1, 0],
+                signs: [1, 1, -1],
+            },
+            Axis::Z => Symmetry {
+                axes: [1, 0, 2],
+                signs: [-1, 1, 1],
+            },
+        }
+    }
+
+    /// `quarter_turns` times `quarter_turn(axis)`, negative turns rotate clockwise.
+    pub fn rotation(axis: Axis, quarter_turns: i32) -> Symmetry {
+        let turn = Symmetry::quarter_turn(axis);
+        (0..quarter_turns.rem_euclid(4)).fold(Symmetry::IDENTITY, |s, _| s.then(turn))
+    }
+
+    /// Reflection at the plane orthogonal to `axis`.
+    pub fn mirror(axis: Axis) -> Symmetry {
+        let mut signs = [1; 3];
+        signs[axis_index(axis)] = -1;
+        Symmetry {
+            axes: [0, 1, 2],
+            signs,
+        }
+    }
+
+    /// false if the symmetry includes a reflection.
+    pub fn is_rotation(&self) -> bool {
+        let inversions = (0..3)
+            .flat_map(|i| (i + 1..3).map(move |j| (i, j)))
+            .filter(|(i, j)| self.axes[*i] > self.axes[*j])
+            .count();
+        let sign: i8 = self.signs.iter().product();
+        (sign == 1) == (inversions % 2 == 0)
+    }
+
+    /// The symmetry that first applies `self`, then `next`.
+    pub fn then(&self, next: Symmetry) -> Symmetry {
+        Symmetry {
+            axes: next.axes.map(|a| self.axes[a]),
+            signs: [0, 1, 2].map(|i| next.signs[i] * self.signs[next.axes[i]]),
+        }
+    }
+
+    pub fn inverse(&self) -> Symmetry {
+        let mut inverse = Symmetry::IDENTITY;
+        for i in 0..3 {
+            inverse.axes[self.axes[i]] = i;
+            inverse.signs[self.axes[i]] = self.signs[i];
+        }
+        inverse
+    }
+
+    /// Transforms a position around the origin.
+    pub fn apply(&self, pos: Pos) -> Pos {
+        let coords = [pos.x, pos.y, pos.z];
+        let c = |i: usize| coords[self.axes[i]] * self.signs[i];
+        Pos::new(c(0), c(1), c(2))
+    }
+
+    /// The axis `axis` is mapped to, and true if it is flipped.
+    pub fn apply_axis(&self, axis: Axis) -> (Axis, bool) {
+        let i = axis_index(axis);
+        let target = (0..3)
+            .find(|t| self.axes[*t] == i)
+            .expect("axes is a permutation");
+        (AXES[target], self.signs[target] < 0)
+    }
+
+    /// The transformed box between `Pos(0,0,0)` and `max` moved back to `Pos(0,0,0)`:
+    /// returns the function mapping positions of the box into it, and its new `max`.
+    fn in_box(&self, max: Pos) -> (impl Fn(Pos) -> Pos + '_, Pos) {
+        let a = self.apply(Pos::default());
+        let b = self.apply(max);
+        let shift = Pos::default() - min(a, b);
+        (
+            move |pos: Pos| self.apply(pos) + shift,
+            self::max(a, b) + shift,
+        )
+    }
+}
+
+impl EdgeKind {
+    /// The kind of the transformed edge, and true if it now points backwards,
+    /// so it has to be stored at its other end.
+    pub fn transformed(&self, symmetry: Symmetry) -> (EdgeKind, bool) {
+        let delta = symmetry.apply(self.delta());
+        EdgeKind::canonical((delta.x, delta.y, delta.z))
+            .expect("the edge kinds are closed under all symmetries")
+    }
+}
+
+fn axis_index(axis: Axis) -> usize {
+    match axis {
+        Axis::X => 0,
+        Axis::Y => 1,
+        Axis::Z => 2,
+    }
+}
+
+pub(super) const AXES: [Axis; 3] = [Axis::X, Axis::Y, Axis::Z];
+
+fn unit(index: usize) -> Pos {
+    let mut coords = [0; 3];
+    coords[index] = 1;
+    Pos::new(coords[0], coords[1], coords[2])
+}
+
+fn min(a: Pos, b: Pos) -> Pos {
+    Pos::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z))
+}
+
+fn max(a: Pos, b: Pos) -> Pos {
+    Pos::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z))
+}
+
+/// The 4 corners of the face of `axis` owned by the voxel at `pos`.
+pub(super) fn face_corners(pos: Pos, axis: Axis) -> [Pos; 4] {
+    let i = axis_index(axis);
+    let u = unit((i + 1) % 3);
+    let v = unit((i + 2) % 3);
+    [pos, pos + u, pos + u + v, pos + v]
+}
+
+/// The voxel content of a box between `Pos(0,0,0)` and `max`, see `Prefab`.
+pub(super) struct Content {
+    pub max: Pos,
+    pub voxels: HashMap<Pos, Voxel>,
+    pub edges: HashMap<Pos, Vec<Edge>>,
+}
+
+impl Content {
+    /// Maps every corner, face and edge with the symmetry and moves the result back into the box
+    /// between `Pos(0,0,0)` and the new `max`.
+    pub(super) fn transformed(&self, symmetry: Symmetry) -> Content {
+        let (map, max) = symmetry.in_box(self.max);
+        let mut content = Content {
+            max,
+            voxels: HashMap::new(),
+            edges: HashMap::new(),
+        };
+        for (pos, voxel) in self.voxels.iter() {
+            content.voxels.entry(map(*pos)).or_default().corner = voxel.corner;
+            for axis in AXES {
+                let Some(matter) = voxel.side(axis) else {
+                    continue;
+                };
+                let corners = face_corners(*pos, axis).map(&map);
+                let owner = corners.iter().copied().fold(corners[0], min);
+                let (face_axis, _) = symmetry.apply_axis(axis);
+                *content.voxels.entry(owner).or_default().side_mut(face_axis) = Some(matter);
+            }
+            if let Some(matter) = voxel.inner {
+                let owner = min(map(*pos), map(*pos + Pos::new(1, 1, 1)));
+                content.voxels.entry(owner).or_default().inner = Some(matter);
+            }
+        }
+        content.voxels.retain(|_, voxel| *voxel != Voxel::default());
+
+        for (pos, edges) in self.edges.iter() {
+            for edge in edges {
+                let (kind, backwards) = edge.kind().transformed(symmetry);
+                let start = match backwards {
+                    true => map(*pos + edge.kind().delta()),
+                    false => map(*pos),
+                };
+                content
+                    .edges
+                    .entry(start)
+                    .or_default()
+                    .push(Edge::new(kind, edge.matter()));
+            }
+        }
+        content
+    }
+}
+
+impl Chunk {
+    /// The chunk with its content transformed by the symmetry around the center of its corners,
+    /// so the corners inside of the chunk bounds are mapped onto each other.
+    ///
+    /// Faces and edges are moved to the voxels that own them afterwards. The faces of the outermost voxels
+    /// that reach into the neighbouring chunks can end up owned by a voxel outside of the chunk bounds,
+    /// they are kept, so transforming back restores the chunk.
+    /// Light, border and meshes have to be computed again.
+    pub fn transformed(&self, symmetry: Symmetry) -> Chunk {
+        let last = CHUNK_SIZE as i8 - 1;
+        let mut voxels = self.voxels.clone();
+        // the fill of Strong sections is stored explicitly, so Air moves with the symmetry:
+        if !self.fill.air() {
+            for x in 0..=last {
+                for y in 0..=last {
+                    for z in 0..=last {
+                        let pos = Pos::new(x, y, z);
+                        voxels.entry(pos).or_insert_with(|| self.get_voxel(pos));
+                    }
+                }
+            }
+        }
+        let content = Content {
+            max: Pos::new(last, last, last),
+            voxels,
+            edges: self.edges.clone(),
+        }
+        .transformed(symmetry);
+
+        let mut voxels = content.voxels;
+        if !self.fill.air() {
+            // Air moved into the chunk has to be stored explicitly now
+            for x in 0..=last {
+                for y in 0..=last {
+                    for z in 0..=last {
+                        voxels.entry(Pos::new(x, y, z)).or_default();
+                    }
+                }
+            }
+        }
+        let mut chunk = Chunk {
+            fill: self.fill,
+            edges: content.edges,
+            ..Default::default()
+        };
+        chunk.voxels = voxels
+            .into_iter()
+            .filter(|(pos, voxel)| *voxel != chunk.unstored_voxel(*pos))
+            .collect();
+        chunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::{
+        examples::example_chunks,
+        voxel::{Corner, Matter},
+    };
+
+    /// The example chunks with faces on the outermost layer and an edge of every kind added,
+    /// some of them reaching out of the chunk.
+    fn test_chunks() -> Vec<Chunk> {
+        let last = CHUNK_SIZE as i8 - 1;
+        let mut chunks = example_chunks();
+        chunks.push(Chunk::full());
+        for chunk in chunks.iter_mut() {
+            for pos in [
+                Pos::new(0, 5, 7),
+                Pos::new(last, 5, 7),
+                Pos::new(4, last, 0),
+                Pos::new(last, last, last),
+            ] {
+                let matter = Some(Matter::Wood);
+                chunk.set_voxel(
+                    pos,
+                    Voxel {
+                        inner: matter,
+                        ..Voxel::new(matter, matter, matter, Corner::Strong)
+                    },
+                );
+            }
+            for (i, kind) in EdgeKind::iter().enumerate() {
+                let start = match i % 2 {
+                    0 => Pos::new(8, 8, i as i8 % CHUNK_SIZE as i8),
+                    _ => Pos::new(last, last - 1, i as i8 % CHUNK_SIZE as i8),
+                };
+                chunk
+                    .edges
+                    .entry(start)
+                    .or_default()
+                    .push(Edge::new(kind, Some(Matter::Dirt)));
+            }
+        }
+        chunks
+    }
+
+    fn sorted_edges(chunk: &Chunk) -> Vec<(Pos, usize, Option<Matter>)> {
+        let mut edges: Vec<_> = chunk
+            .edges
+            .iter()
+            .flat_map(|(pos, edges)| {
+                edges.iter().map(|edge| {
+                    let kind = EdgeKind::iter().position(|k| k == edge.kind());
+                    (*pos, kind.unwrap(), edge.matter())
+                })
+            })
+            .collect();
+        edges.sort_by_key(|(pos, kind, _)| (*pos, *kind));
+        edges
+    }
+
+    #[test]
+    fn transforming_back_restores_chunk() {
+        for chunk in test_chunks() {
+            for s in Symmetry::all() {
+                let restored = chunk.transformed(s).transformed(s.inverse());
+                assert_eq!(restored.fill, chunk.fill, "{s:?}");
+                assert_eq!(restored.voxels, chunk.voxels, "{s:?}");
+                assert_eq!(sorted_edges(&restored), sorted_edges(&chunk), "{s:?}");
+            }
+        }
+    }
+
+    /// A box of 3³ corners with a single Dirt side on the voxel at `pos`.
+    fn single_side(pos: Pos, axis: Axis) -> Content {
+        let mut voxel = Voxel::default();
+        *voxel.side_mut(axis) = Some(Matter::Dirt);
+        Content {
+            max: Pos::new(2, 2, 2),
+            voxels: [(pos, voxel)].into_iter().collect(),
+            edges: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn quarter_turn_moves_side_to_its_owner() {
+        // (x, y, z) turns to (-y, x, z), moved back into the box by x + 2:
+        let content = single_side(Pos::new(1, 0, 0), Axis::X);
+        let turned = content.transformed(Symmetry::quarter_turn(Axis::Z));
+        assert_eq!(turned.max, Pos::new(2, 2, 2));
+        // the corners of the face are (1|2, 1, 0|1), so it is owned by (1, 1, 0), not by (2, 1, 0),
+        // where the voxel itself ends up:
+        let mut expected = Voxel::default();
+        expected.y_side = Some(Matter::Dirt);
+        assert_eq!(
+            turned.voxels,
+            [(Pos::new(1, 1, 0), expected)].into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn mirrored_edge_kind() {
+        assert_eq!(
+            EdgeKind::XYm.transformed(Symmetry::mirror(Axis::Y)),
+            (EdgeKind::XY, false)
+        );
+        // pointing backwards after mirroring along x, so it is stored at its other end:
+        assert_eq!(
+            EdgeKind::XYm.transformed(Symmetry::mirror(Axis::X)),
+            (EdgeKind::XY, true)
+        );
+    }
+
+    #[test]
+    fn transformed_faces_cover_mapped_corners() {
+        for s in Symmetry::all() {
+            for axis in AXES {
+                let pos = Pos::new(0, 1, 0);
+                let content = single_side(pos, axis);
+                let (map, _) = s.in_box(content.max);
+                let mut mapped = face_corners(pos, axis).map(&map);
+
+                let transformed = content.transformed(s);
+                assert_eq!(transformed.voxels.len(), 1);
+                let (owner, voxel) = transformed.voxels.iter().next().unwrap();
+                let sides: Vec<Axis> = AXES
+                    .into_iter()
+                    .filter(|a| voxel.side(*a).is_some())
+                    .collect();
+                assert_eq!(sides, vec![s.apply_axis(axis).0], "{s:?} {axis:?}");
+                let mut corners = face_corners(*owner, sides[0]);
+                mapped.sort();
+                corners.sort();
+                assert_eq!(corners, mapped, "{s:?} {axis:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_undoes_symmetry() {
+        for s in Symmetry::all() {
+            assert_eq!(s.then(s.inverse()), Symmetry::IDENTITY, "{s:?}");
+            assert_eq!(s.inverse().then(s), Symmetry::IDENTITY, "{s:?}");
+        }
+    }
+
+    #[test]
+    fn four_quarter_turns_are_identity() {
+        for axis in AXES {
+            let turn = Symmetry::quarter_turn(axis);
+            assert!(turn.is_rotation());
+            let turns = (0..4).fold(Symmetry::IDENTITY, |s, _| s.then(turn));
+            assert_eq!(turns, Symmetry::IDENTITY, "{axis:?}");
+            assert_eq!(Symmetry::rotation(axis, 4), Symmetry::IDENTITY);
+            assert_ne!(Symmetry::rotation(axis, 2), Symmetry::IDENTITY);
+        }
+    }
+
+    #[test]
+    fn counts() {
+        assert_eq!(Symmetry::rotations().count(), 24);
+        assert_eq!(Symmetry::all().count(), 48);
+        assert_eq!(Symmetry::all().next(), Some(Symmetry::IDENTITY));
+        let distinct: std::collections::HashSet<_> = Symmetry::all().collect();
+        assert_eq!(distinct.len(), 48);
+    }
+}
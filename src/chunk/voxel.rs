@@ -1,6 +1,8 @@
 use bevy::prelude::default;
 
-#[derive(Debug, Clone, Copy)]
+use super::pos::{Axis, Pos};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct Voxel {
     /// Some if x_side is filled
     pub x_side: Option<Matter>,
@@ -28,9 +30,26 @@ impl Voxel {
             corner: corner,
         }
     }
+
+    pub fn side(&self, axis: Axis) -> Option<Matter> {
+        match axis {
+            Axis::X => self.x_side,
+            Axis::Y => self.y_side,
+            Axis::Z => self.z_side,
+        }
+    }
+
+    pub fn side_mut(&mut self, axis: Axis) -> &mut Option<Matter> {
+        match axis {
+            Axis::X => &mut self.x_side,
+            Axis::Y => &mut self.y_side,
+            Axis::Z => &mut self.z_side,
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Ordered from the least to the most solid corner.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Corner {
     #[default]
     Air,
@@ -53,16 +72,56 @@ impl Corner {
     pub fn air(&self) -> bool {
         matches!(self, Corner::Air)
     }
+
+    /// A, W or S, to print configurations of corners like in `bin/meshstats` and `bin/lut`.
+    pub fn to_char(self) -> char {
+        match self {
+            Corner::Air => 'A',
+            Corner::Weak => 'W',
+            Corner::Strong => 'S',
+        }
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Edge {
     matter: Option<Matter>,
     kind: EdgeKind,
 }
 
-#[derive(Debug, Clone, Copy)]
-/// 33 possible edge kinds
+impl Edge {
+    pub fn new(kind: EdgeKind, matter: Option<Matter>) -> Self {
+        Self { matter, kind }
+    }
+
+    /// The edge between the corners `start` and `start + offset`, with the corner it is stored at,
+    /// see `EdgeKind::canonical`. None if no edge kind has this offset.
+    pub fn between(
+        start: Pos,
+        offset: (i8, i8, i8),
+        matter: Option<Matter>,
+    ) -> Option<(Pos, Edge)> {
+        let (kind, negated) = EdgeKind::canonical(offset)?;
+        let start = match negated {
+            true => start + Pos::new(offset.0, offset.1, offset.2),
+            false => start,
+        };
+        Some((start, Edge::new(kind, matter)))
+    }
+
+    pub fn matter(&self) -> Option<Matter> {
+        self.matter
+    }
+
+    pub fn kind(&self) -> EdgeKind {
+        self.kind
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// 37 possible edge kinds, named after their offset, see `EdgeKind::offset`.
+/// Only offsets whose first non zero coordinate is positive are listed,
+/// an edge in the opposite direction is stored at its other end.
 pub enum EdgeKind {
     /// 1,0,0
     X,
@@ -140,9 +199,207 @@ pub enum EdgeKind {
     XYmZmext,
 }
 
-#[derive(Debug, Clone, Copy)]
+impl EdgeKind {
+    pub const ALL: [EdgeKind; 37] = [
+        EdgeKind::X,
+        EdgeKind::Y,
+        EdgeKind::Z,
+        EdgeKind::XY,
+        EdgeKind::XYm,
+        EdgeKind::XZ,
+        EdgeKind::XZm,
+        EdgeKind::YZ,
+        EdgeKind::YZm,
+        EdgeKind::XextY,
+        EdgeKind::XYext,
+        EdgeKind::XextYm,
+        EdgeKind::XYmext,
+        EdgeKind::XextZ,
+        EdgeKind::XZext,
+        EdgeKind::XextZm,
+        EdgeKind::XZmext,
+        EdgeKind::YextZ,
+        EdgeKind::YZext,
+        EdgeKind::YextZm,
+        EdgeKind::YZmext,
+        EdgeKind::XYZ,
+        EdgeKind::XYZm,
+        EdgeKind::XYmZ,
+        EdgeKind::XYmZm,
+        EdgeKind::XextYZ,
+        EdgeKind::XYextZ,
+        EdgeKind::XYZext,
+        EdgeKind::XextYZm,
+        EdgeKind::XYextZm,
+        EdgeKind::XYZmext,
+        EdgeKind::XextYmZ,
+        EdgeKind::XYmextZ,
+        EdgeKind::XYmZext,
+        EdgeKind::XextYmZm,
+        EdgeKind::XYmextZm,
+        EdgeKind::XYmZmext,
+    ];
+
+    pub fn iter() -> impl Iterator<Item = EdgeKind> {
+        EdgeKind::ALL.into_iter()
+    }
+
+    /// Offset from the corner the edge is stored at to the corner it ends at.
+    pub fn offset(&self) -> (i8, i8, i8) {
+        match self {
+            EdgeKind::X => (1, 0, 0),
+            EdgeKind::Y => (0, 1, 0),
+            EdgeKind::Z => (0, 0, 1),
+            EdgeKind::XY => (1, 1, 0),
+            EdgeKind::XYm => (1, -1, 0),
+            EdgeKind::XZ => (1, 0, 1),
+            EdgeKind::XZm => (1, 0, -1),
+            EdgeKind::YZ => (0, 1, 1),
+            EdgeKind::YZm => (0, 1, -1),
+            EdgeKind::XextY => (2, 1, 0),
+            EdgeKind::XYext => (1, 2, 0),
+            EdgeKind::XextYm => (2, -1, 0),
+            EdgeKind::XYmext => (1, -2, 0),
+            EdgeKind::XextZ => (2, 0, 1),
+            EdgeKind::XZext => (1, 0, 2),
+            EdgeKind::XextZm => (2, 0, -1),
+            EdgeKind::XZmext => (1, 0, -2),
+            EdgeKind::YextZ => (0, 2, 1),
+            EdgeKind::YZext => (0, 1, 2),
+            EdgeKind::YextZm => (0, 2, -1),
+            EdgeKind::YZmext => (0, 1, -2),
+            EdgeKind::XYZ => (1, 1, 1),
+            EdgeKind::XYZm => (1, 1, -1),
+            EdgeKind::XYmZ => (1, -1, 1),
+            EdgeKind::XYmZm => (1, -1, -1),
+            EdgeKind::XextYZ => (2, 1, 1),
+            EdgeKind::XYextZ => (1, 2, 1),
+            EdgeKind::XYZext => (1, 1, 2),
+            EdgeKind::XextYZm => (2, 1, -1),
+            EdgeKind::XYextZm => (1, 2, -1),
+            EdgeKind::XYZmext => (1, 1, -2),
+            EdgeKind::XextYmZ => (2, -1, 1),
+            EdgeKind::XYmextZ => (1, -2, 1),
+            EdgeKind::XYmZext => (1, -1, 2),
+            EdgeKind::XextYmZm => (2, -1, -1),
+            EdgeKind::XYmextZm => (1, -2, -1),
+            EdgeKind::XYmZmext => (1, -1, -2),
+        }
+    }
+
+    pub(crate) fn delta(&self) -> Pos {
+        let (x, y, z) = self.offset();
+        Pos::new(x, y, z)
+    }
+
+    /// The kind with this offset, None if the offset is not listed,
+    /// like negated offsets or offsets that are too long.
+    pub fn from_offset(offset: (i8, i8, i8)) -> Option<EdgeKind> {
+        EdgeKind::iter().find(|kind| kind.offset() == offset)
+    }
+
+    /// The kind for an edge along `offset` in either direction, and true if the kind points the
+    /// opposite way, so the edge has to be stored at the other end. None for offsets of no edge kind.
+    pub fn canonical(offset: (i8, i8, i8)) -> Option<(EdgeKind, bool)> {
+        let (x, y, z) = offset;
+        // no edge is longer than 2 along an axis, longer offsets could not be negated:
+        if [x, y, z].iter().any(|c| !(-2..=2).contains(c)) {
+            return None;
+        }
+        let negated = [x, y, z].into_iter().find(|c| *c != 0)? < 0;
+        let offset = match negated {
+            true => (-x, -y, -z),
+            false => offset,
+        };
+        EdgeKind::from_offset(offset).map(|kind| (kind, negated))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 /// material something is made of
 pub enum Matter {
     Dirt,
     Wood,
+    Lamp,
+}
+
+/// Number of variants of `Matter`.
+pub const MATTER_COUNT: usize = 3;
+
+impl Matter {
+    /// All matters, ordered by `Matter::index`.
+    pub const ALL: [Matter; MATTER_COUNT] = [Matter::Dirt, Matter::Wood, Matter::Lamp];
+
+    /// Position of the matter in `Matter::ALL`, used to index per matter data like `blend::MatterWeights`
+    /// and the matter attribute of chunk meshes.
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// How many voxels a horizontal run of this matter reaches out from the closest
+    /// vertical support before it breaks, see `structure`.
+    pub fn strength(&self) -> u8 {
+        match self {
+            Matter::Dirt => 3,
+            Matter::Wood => 8,
+            Matter::Lamp => 1,
+        }
+    }
+
+    /// Block light level emitted by voxels with a side of this matter, 0 for most matters.
+    pub fn emission(&self) -> u8 {
+        match self {
+            Matter::Dirt | Matter::Wood => 0,
+            Matter::Lamp => 14,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+    use crate::chunk::symmetry::Symmetry;
+
+    #[test]
+    fn edge_kinds_are_distinct() {
+        let kinds: HashSet<_> = EdgeKind::iter().collect();
+        let offsets: HashSet<_> = EdgeKind::iter().map(|k| k.offset()).collect();
+        assert_eq!(kinds.len(), 37);
+        assert_eq!(offsets.len(), 37);
+    }
+
+    #[test]
+    fn offsets_round_trip() {
+        for kind in EdgeKind::iter() {
+            let (x, y, z) = kind.offset();
+            assert_eq!(EdgeKind::from_offset((x, y, z)), Some(kind));
+            assert_eq!(EdgeKind::canonical((x, y, z)), Some((kind, false)));
+            assert_eq!(EdgeKind::from_offset((-x, -y, -z)), None);
+            assert_eq!(EdgeKind::canonical((-x, -y, -z)), Some((kind, true)));
+        }
+    }
+
+    #[test]
+    fn canonical_rejects_other_offsets() {
+        assert_eq!(EdgeKind::canonical((0, 0, 0)), None);
+        assert_eq!(EdgeKind::canonical((2, 2, 0)), None);
+        assert_eq!(EdgeKind::canonical((3, 0, 0)), None);
+        assert_eq!(EdgeKind::canonical((i8::MIN, 0, 0)), None);
+        assert_eq!(EdgeKind::canonical((1, i8::MIN, i8::MAX)), None);
+    }
+
+    #[test]
+    fn edge_kinds_are_closed_under_symmetries() {
+        for s in Symmetry::all() {
+            for kind in EdgeKind::iter() {
+                let delta = s.apply(kind.delta());
+                assert!(
+                    EdgeKind::canonical((delta.x, delta.y, delta.z)).is_some(),
+                    "{kind:?} {s:?}"
+                );
+            }
+        }
+    }
 }
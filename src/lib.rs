@@ -2,3 +2,4 @@ pub mod bevy;
 pub mod chunk;
 
 pub use bevy::pan_orbit_cam::PanOrbitCameraPlugin;
+pub use bevy::world::VoxelWorldPlugin;